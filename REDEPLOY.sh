@@ -1,7 +1,21 @@
 #!/bin/bash
 
+set -e
+
 source .env
+source .compose-env
 
 docker pull jlai241/stacc-api:$API_VERSION
 docker compose down
-docker compose --env-file .env --env-file .compose-env up -d api
+
+# `--wait` blocks until the healthcheck (`/api/ready`) passes, and fails if it never does.
+docker compose --env-file .env --env-file .compose-env up -d --wait api
+
+# Make sure the running API is the version that was just pulled.
+DEPLOYED_VERSION=$(curl -fsS "http://localhost:${STACC_API_PORT_NUMBER}/api/version" | grep -oP '"version":"\K[^"]+')
+if [ "$DEPLOYED_VERSION" != "$API_VERSION" ]; then
+  echo "Expected API version $API_VERSION, but $DEPLOYED_VERSION is running!"
+  exit 1
+fi
+
+echo "API version $DEPLOYED_VERSION is up and ready!"
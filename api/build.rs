@@ -0,0 +1,25 @@
+//! Embeds the git SHA the API is built from so that `/api/version` can report it.
+
+use std::{env, process::Command};
+
+fn main() {
+    // Docker builds do not have the `.git` directory, so the SHA is passed in as a build argument.
+    let git_sha = env::var("GIT_SHA")
+        .ok()
+        .filter(|git_sha| !git_sha.is_empty())
+        .or_else(|| {
+            Command::new("git")
+                .args(["rev-parse", "--short", "HEAD"])
+                .output()
+                .ok()
+                .filter(|output| output.status.success())
+                .and_then(|output| String::from_utf8(output.stdout).ok())
+                .map(|git_sha| git_sha.trim().to_string())
+        })
+        .unwrap_or_else(|| "unknown".to_string());
+
+    println!("cargo:rustc-env=GIT_SHA={git_sha}");
+    println!("cargo:rerun-if-env-changed=GIT_SHA");
+    println!("cargo:rerun-if-changed=../.git/HEAD");
+    println!("cargo:rerun-if-changed=../.git/refs/heads");
+}
@@ -1,50 +1,184 @@
 //! Contains all errors that may be raised in `stacc`.
 
-use actix_web::{
-    body::BoxBody,
-    http::{header::ContentType, StatusCode},
-    HttpResponse, ResponseError,
-};
+use actix_web::{body::BoxBody, http::StatusCode, HttpRequest, HttpResponse, ResponseError};
 use derive_more::{self, Display};
+use mongodb::error::ErrorKind;
 use thiserror::Error;
+use tracing::error;
 
-use crate::models::data::Response;
+use crate::models::problem::Problem;
 
-/// Contains all error variants for errors that may be raised by Actix Web endpoints.
+/// The media type of RFC 7807 problem details.
+const PROBLEM_JSON: &str = "application/problem+json";
+
+/// Contains all error variants for errors that may be raised by Actix Web endpoints. Each variant
+/// is returned as an RFC 7807 `application/problem+json` body with a stable `code`.
 #[derive(Debug, Display, derive_more::Error)]
 pub enum StaccResponseError {
-    /// Something fucked up when pinging the Chicago map APIs.
-    #[display(fmt = "Chicago API error: {error}")]
-    ChicagoAPIError { error: String },
+    /// The request contained invalid input.
+    #[display(fmt = "Bad input: {error}")]
+    BadInput { error: String },
+
+    /// MongoDB returned an error for a query.
+    #[display(fmt = "Database error: {error}")]
+    DatabaseError { error: String },
+
+    /// MongoDB could not be reached.
+    #[display(fmt = "Database unavailable: {error}")]
+    DatabaseUnavailable { error: String },
+
+    /// The requested document does not exist.
+    #[display(fmt = "Not found: {error}")]
+    NotFound { error: String },
+
+    /// The client has made too many requests.
+    #[display(fmt = "Rate limited: {error}")]
+    RateLimited { error: String },
+
+    /// The request was made to an admin-only endpoint without a valid admin token.
+    #[display(fmt = "Unauthorized: {error}")]
+    Unauthorized { error: String },
+
+    /// An upstream API (e.g. Socrata) returned data that could not be understood.
+    #[display(fmt = "Upstream bad data: {error}")]
+    UpstreamBadData { error: String },
+
+    /// An upstream API did not respond in time.
+    #[display(fmt = "Upstream timeout: {error}")]
+    UpstreamTimeout { error: String },
+
+    /// An upstream API could not be reached or returned an error.
+    #[display(fmt = "Upstream unavailable: {error}")]
+    UpstreamUnavailable { error: String },
+}
+
+impl StaccResponseError {
+    /// The stable, machine-readable code for this error.
+    pub fn code(&self) -> &'static str {
+        match self {
+            StaccResponseError::BadInput { .. } => "bad-input",
+            StaccResponseError::DatabaseError { .. } => "database-error",
+            StaccResponseError::DatabaseUnavailable { .. } => "database-unavailable",
+            StaccResponseError::NotFound { .. } => "not-found",
+            StaccResponseError::RateLimited { .. } => "rate-limited",
+            StaccResponseError::Unauthorized { .. } => "unauthorized",
+            StaccResponseError::UpstreamBadData { .. } => "upstream-bad-data",
+            StaccResponseError::UpstreamTimeout { .. } => "upstream-timeout",
+            StaccResponseError::UpstreamUnavailable { .. } => "upstream-unavailable",
+        }
+    }
+
+    /// A short summary of this kind of error.
+    fn title(&self) -> &'static str {
+        match self {
+            StaccResponseError::BadInput { .. } => "Bad input",
+            StaccResponseError::DatabaseError { .. } => "Database error",
+            StaccResponseError::DatabaseUnavailable { .. } => "Database unavailable",
+            StaccResponseError::NotFound { .. } => "Not found",
+            StaccResponseError::RateLimited { .. } => "Rate limited",
+            StaccResponseError::Unauthorized { .. } => "Unauthorized",
+            StaccResponseError::UpstreamBadData { .. } => "Upstream bad data",
+            StaccResponseError::UpstreamTimeout { .. } => "Upstream timeout",
+            StaccResponseError::UpstreamUnavailable { .. } => "Upstream unavailable",
+        }
+    }
+
+    /// The explanation of this occurrence of the error.
+    fn detail(&self) -> &str {
+        match self {
+            StaccResponseError::BadInput { error }
+            | StaccResponseError::DatabaseError { error }
+            | StaccResponseError::DatabaseUnavailable { error }
+            | StaccResponseError::NotFound { error }
+            | StaccResponseError::RateLimited { error }
+            | StaccResponseError::Unauthorized { error }
+            | StaccResponseError::UpstreamBadData { error }
+            | StaccResponseError::UpstreamTimeout { error }
+            | StaccResponseError::UpstreamUnavailable { error } => error,
+        }
+    }
+
+    /// Classify an error from a request to an upstream API. The underlying error is logged rather
+    /// than returned, as it may contain the upstream's URL and credentials.
+    pub fn upstream(upstream: &str, error: StaccError) -> Self {
+        error!(upstream, %error, "upstream request failed");
+
+        match error {
+            StaccError::Reqwest(error) if error.is_timeout() => {
+                StaccResponseError::UpstreamTimeout {
+                    error: format!("{upstream} did not respond in time."),
+                }
+            }
+            StaccError::Reqwest(error) if error.is_decode() => {
+                StaccResponseError::UpstreamBadData {
+                    error: format!("{upstream} returned data that could not be read."),
+                }
+            }
+            StaccError::SerdeJSONError(_) => StaccResponseError::UpstreamBadData {
+                error: format!("{upstream} returned data that could not be read."),
+            },
+            _ => StaccResponseError::UpstreamUnavailable {
+                error: format!("{upstream} could not be reached."),
+            },
+        }
+    }
+}
+
+/// Return an extractor's error, e.g. for a malformed JSON body, as a `bad-input` problem rather than
+/// Actix Web's plain text response.
+pub fn extractor_error(error: impl std::fmt::Display, _request: &HttpRequest) -> actix_web::Error {
+    StaccResponseError::BadInput {
+        error: error.to_string(),
+    }
+    .into()
+}
 
-    /// A generic error variant for MongoDB.
-    #[display(fmt = "MongoDB error: {error}")]
-    MongoDBError { error: String },
+impl From<mongodb::error::Error> for StaccResponseError {
+    /// Classify a MongoDB error. The underlying error is logged rather than returned, as it may
+    /// describe the database's internals.
+    fn from(error: mongodb::error::Error) -> Self {
+        error!(%error, "mongodb operation failed");
 
-    /// Could not find a document within MongoDB.
-    #[display(fmt = "MongoDB search error: {error}")]
-    MongoDBSearchError { error: String },
+        match *error.kind {
+            ErrorKind::ConnectionPoolCleared { .. }
+            | ErrorKind::DnsResolve { .. }
+            | ErrorKind::Io(_)
+            | ErrorKind::ServerSelection { .. } => StaccResponseError::DatabaseUnavailable {
+                error: "The database could not be reached. Try again later.".to_string(),
+            },
+            _ => StaccResponseError::DatabaseError {
+                error: "The database could not complete the request.".to_string(),
+            },
+        }
+    }
 }
 
 impl ResponseError for StaccResponseError {
     fn error_response(&self) -> HttpResponse<BoxBody> {
-        HttpResponse::build(self.status_code())
-            .insert_header(ContentType::json())
-            .json(Response {
-                message: self.to_string(),
-                status_code: self.status_code().as_u16(),
+        let status_code = self.status_code();
+
+        HttpResponse::build(status_code)
+            .content_type(PROBLEM_JSON)
+            .json(Problem {
+                code: self.code(),
+                detail: self.detail().to_string(),
+                status: status_code.as_u16(),
+                title: self.title(),
+                problem_type: format!("urn:stacc:problem:{}", self.code()),
             })
     }
 
     fn status_code(&self) -> StatusCode {
         match *self {
-            StaccResponseError::ChicagoAPIError { .. } => {
-                StatusCode::from_u16(500).unwrap_or(StatusCode::BAD_REQUEST)
-            }
-            StaccResponseError::MongoDBError { .. } => {
-                StatusCode::from_u16(500).unwrap_or(StatusCode::BAD_REQUEST)
-            }
-            StaccResponseError::MongoDBSearchError { .. } => StatusCode::NOT_FOUND,
+            StaccResponseError::BadInput { .. } => StatusCode::BAD_REQUEST,
+            StaccResponseError::DatabaseError { .. } => StatusCode::INTERNAL_SERVER_ERROR,
+            StaccResponseError::DatabaseUnavailable { .. } => StatusCode::SERVICE_UNAVAILABLE,
+            StaccResponseError::NotFound { .. } => StatusCode::NOT_FOUND,
+            StaccResponseError::RateLimited { .. } => StatusCode::TOO_MANY_REQUESTS,
+            StaccResponseError::Unauthorized { .. } => StatusCode::UNAUTHORIZED,
+            StaccResponseError::UpstreamBadData { .. } => StatusCode::BAD_GATEWAY,
+            StaccResponseError::UpstreamTimeout { .. } => StatusCode::GATEWAY_TIMEOUT,
+            StaccResponseError::UpstreamUnavailable { .. } => StatusCode::BAD_GATEWAY,
         }
     }
 }
@@ -53,9 +187,33 @@ impl ResponseError for StaccResponseError {
 /// `HttpResponse` (functions that are not Actix Web endpoints).
 #[derive(Debug, Error)]
 pub enum StaccError {
-    /// Something fucked up while retrieving an environment variable.
-    #[error("Environment error: {0}")]
-    Environment(#[from] std::env::VarError),
+    /// The configuration is missing values or contains invalid ones.
+    #[error("Config error: found {} problem(s) in the configuration", .0.len())]
+    Config(Vec<String>),
+
+    /// Something fucked up while building an email.
+    #[error("Email error: {0}")]
+    Email(#[from] lettre::error::Error),
+
+    /// Something fucked up while parsing an email address.
+    #[error("Email address error: {0}")]
+    EmailAddress(#[from] lettre::address::AddressError),
+
+    /// Something tried to make the API request a URL that is not on the public internet.
+    #[error("Forbidden URL error: {0} is not a public HTTP(S) URL")]
+    ForbiddenUrl(String),
+
+    /// Something fucked up while reading or writing a file.
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// Something fucked up while installing the logger.
+    #[error("Logging error: {0}")]
+    Logging(#[from] tracing_subscriber::util::TryInitError),
+
+    /// Something fucked up while downloading or converting a background.
+    #[error("Media error: {0}")]
+    Media(String),
 
     /// Something fucked up with MongoDB.
     #[error("MongoDB error: {0}")]
@@ -68,4 +226,76 @@ pub enum StaccError {
     /// Something fucked up with `serde_json`.
     #[error("Serde JSON error: {0}")]
     SerdeJSONError(#[from] serde_json::Error),
+
+    /// Something fucked up while creating a signature.
+    #[error("Signature error: {0}")]
+    Signature(#[from] hmac::digest::InvalidLength),
+
+    /// Something fucked up while talking to the SMTP server.
+    #[error("SMTP error: {0}")]
+    Smtp(#[from] lettre::transport::smtp::Error),
+
+    /// Something fucked up while parsing a URL.
+    #[error("URL error: {0}")]
+    Url(#[from] url::ParseError),
+
+    /// Something fucked up while fetching a page for a Webmention.
+    #[error("Webmention error: {0}")]
+    Webmention(String),
+}
+
+/// Contains all errors that stop the API from starting, or stop it from running.
+#[derive(Debug, Error)]
+pub enum StartupError {
+    /// The server could not bind to its port.
+    #[error("Could not bind to port {port}: {error}")]
+    Bind { error: std::io::Error, port: u16 },
+
+    /// The configuration could not be loaded.
+    #[error("{0}")]
+    Config(StaccError),
+
+    /// The logger could not be installed.
+    #[error("Could not install the logger: {0}")]
+    Logging(StaccError),
+
+    /// The media cache could not be created.
+    #[error("Could not create the media cache: {0}")]
+    MediaCache(StaccError),
+
+    /// The SMTP mailer could not be created.
+    #[error("Could not create the SMTP mailer: {0}")]
+    Mailer(StaccError),
+
+    /// MongoDB could not be reached.
+    #[error("Could not connect to MongoDB after {attempts} attempts: {error}")]
+    MongoDB { attempts: u32, error: StaccError },
+
+    /// The server stopped with an error.
+    #[error("The server stopped unexpectedly: {0}")]
+    Server(std::io::Error),
+
+    /// The Webmention client could not be created.
+    #[error("Could not create the Webmention client: {0}")]
+    WebmentionClient(StaccError),
+}
+
+impl StartupError {
+    /// The status the API exits with, following the BSD `sysexits.h` conventions so that
+    /// supervisors can tell a bad configuration apart from an unavailable dependency.
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            // EX_CONFIG
+            StartupError::Config(_)
+            | StartupError::Mailer(_)
+            | StartupError::MediaCache(_)
+            | StartupError::WebmentionClient(_) => 78,
+            // EX_UNAVAILABLE
+            StartupError::MongoDB { .. } => 69,
+            // EX_OSERR
+            StartupError::Bind { .. } | StartupError::Server(_) => 71,
+            // EX_SOFTWARE
+            StartupError::Logging(_) => 70,
+        }
+    }
 }
@@ -1,20 +1,31 @@
 //! The API for the `stacc`.
 #![allow(clippy::enum_variant_names)]
 
-use std::env;
+use std::{process::ExitCode, time::Duration};
 
 use actix_cors::Cors;
 use actix_web::{
     http::header,
-    middleware::Logger,
     web::{self, Data},
     App, HttpServer,
 };
 use ansi_term::{Color, Style};
 use dotenv::dotenv;
-use env_logger::Env;
+use mongodb::bson::doc;
+use tracing::{info, warn};
 
-use utils::{environment::EnvironmentVariables, mongo::Mongo};
+use errors::StartupError;
+use models::{background::BackgroundGIF, story::Story};
+use utils::{
+    config::Config,
+    logging,
+    mailer::Mailer,
+    media::MediaCache,
+    mongo::{self, Mongo},
+    pool::RandomPool,
+    tasks::BackgroundTasks,
+    webmention::WebmentionClient,
+};
 
 mod errors;
 mod middleware;
@@ -22,8 +33,28 @@ mod models;
 mod routes;
 mod utils;
 
+/// How long in-flight requests, and then background tasks, are given to finish once the API has
+/// been told to shut down.
+const SHUTDOWN_GRACE_PERIOD: Duration = Duration::from_secs(30);
+
+/// How long backgrounds and stories are cached before they are reloaded from MongoDB. Changes made
+/// through the API are picked up immediately; this only bounds how stale changes made directly in
+/// MongoDB can be.
+const RANDOM_POOL_MAX_AGE: Duration = Duration::from_secs(300);
+
 #[actix_web::main]
-async fn main() {
+async fn main() -> ExitCode {
+    match run().await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("{}", Color::Red.bold().paint(error.to_string()));
+            ExitCode::from(error.exit_code())
+        }
+    }
+}
+
+/// Start the API and run it until it is told to shut down, eg. by `SIGTERM`.
+async fn run() -> Result<(), StartupError> {
     println!(
         "\n{}",
         Style::default().bold().paint("🚀 Initializing API.")
@@ -32,54 +63,159 @@ async fn main() {
     println!("📃 Reading environment variables from the `.env` file.");
     dotenv().ok();
 
-    if let Err(error) = utils::checks::run_environment_checks() {
-        println!("{}", Color::Red.bold().paint(error.to_string()));
-    } else {
-        // Instantiate the API logger.
-        env::set_var("RUST_LOG", "debug");
-        env_logger::init_from_env(Env::new().default_filter_or("debug"));
+    let config = Config::load().map_err(StartupError::Config)?;
+    logging::init(&config.logging).map_err(StartupError::Logging)?;
 
-        let mongo = Data::new(
-            Mongo::init()
+    let mongo =
+        Data::new(
+            Mongo::connect(&config)
                 .await
-                .expect("COULD NOT INSTANTIATE A NEW MONGODB CLIENT INSTANCE!"),
+                .map_err(|error| StartupError::MongoDB {
+                    attempts: mongo::CONNECT_ATTEMPTS,
+                    error,
+                })?,
         );
 
-        HttpServer::new(move || {
-            App::new()
-                .app_data(mongo.clone())
-                .service(
-                    web::scope("api")
-                        .service(routes::misc::chiraq)
-                        .service(routes::misc::get_background_gif)
-                        .service(routes::misc::story)
-                        .service(
-                            web::scope("/blog")
-                                .service(routes::posts::get_all_posts)
-                                .service(routes::posts::get_single_post),
-                        ),
-                )
-                .wrap(
-                    Cors::default()
-                        .allowed_header(header::CONTENT_TYPE)
-                        .allowed_methods(vec!["GET"])
-                        .allowed_origin(
-                            &EnvironmentVariables::StaccDomain
-                                .env_var()
-                                .unwrap_or("UNKNOWN".to_string()),
-                        ),
-                )
-                .wrap(Logger::default())
-        })
-        .bind(format!(
-            "0.0.0.0:{}",
-            &EnvironmentVariables::StaccAPIPortNumber
-                .env_var()
-                .expect("FAILED TO GET API PORT NUMBER")
-        ))
-        .expect("FAILED TO BIND TO THE SOCKET ADDRESS")
-        .run()
-        .await
-        .expect("API FAILED TO START");
+    mongo.backfill_ids().await;
+    mongo.create_indexes().await;
+
+    let mailer = Data::new(Mailer::new(&config).map_err(StartupError::Mailer)?);
+    let media_cache = Data::new(MediaCache::new(&config.media).map_err(StartupError::MediaCache)?);
+    let webmention_client = Data::new(
+        WebmentionClient::new(config.webmention.proxy.clone())
+            .map_err(StartupError::WebmentionClient)?,
+    );
+
+    // Visitor logging, Webmentions, and emails run in the background, and are finished before the
+    // API exits.
+    let tasks = Data::new(BackgroundTasks::new());
+    let server_tasks = tasks.clone();
+
+    // Backgrounds and stories are cached in memory and chosen from at random. Backgrounds stored
+    // before the `enabled` and `weight` fields existed are treated as enabled with a weight of 1.
+    let background_pool = Data::new(RandomPool::<BackgroundGIF>::new(
+        doc! { "enabled": { "$ne": false }, "weight": { "$ne": 0 } },
+        RANDOM_POOL_MAX_AGE,
+    ));
+    let story_pool = Data::new(RandomPool::<Story>::new(
+        routes::stories::approved_stories(),
+        RANDOM_POOL_MAX_AGE,
+    ));
+
+    // Visitors are recorded by a single writer in the background, which is finished before the API
+    // exits.
+    let (visitor_log, visitor_writer) = middleware::visitors::VisitorLog::new(mongo.clone());
+    tasks.spawn(visitor_writer.run());
+    let visitor_log = Data::new(visitor_log);
+    let server_visitor_log = visitor_log.clone();
+
+    // Behind a proxy that is not on loopback (e.g. nginx on the host, with the API in Docker), every
+    // reader has the proxy's address unless the proxy is trusted. The per-route limits would then
+    // be shared by the whole site.
+    if config.rate_limit.enabled
+        && config
+            .proxy
+            .trusted
+            .iter()
+            .all(|network| network.addr().is_loopback())
+    {
+        warn!(
+            "only loopback proxies are trusted, so readers behind any other proxy share one rate limit; set STACC_TRUSTED_PROXIES (see docs/docker.md)"
+        );
     }
+
+    // The rate limiter's buckets are shared by every worker.
+    let rate_limit = middleware::rate_limit::RateLimit::new(&config.rate_limit);
+
+    let port = config.server.port;
+    let config = Data::new(config);
+
+    HttpServer::new(move || {
+        App::new()
+            .app_data(background_pool.clone())
+            .app_data(config.clone())
+            .app_data(web::FormConfig::default().error_handler(errors::extractor_error))
+            .app_data(web::JsonConfig::default().error_handler(errors::extractor_error))
+            .app_data(web::PathConfig::default().error_handler(errors::extractor_error))
+            .app_data(web::QueryConfig::default().error_handler(errors::extractor_error))
+            .app_data(mailer.clone())
+            .app_data(media_cache.clone())
+            .app_data(mongo.clone())
+            .app_data(server_tasks.clone())
+            .app_data(story_pool.clone())
+            .app_data(server_visitor_log.clone())
+            .app_data(webmention_client.clone())
+            .service(routes::metrics::metrics)
+            .service(
+                web::scope("api")
+                    .service(routes::misc::chiraq)
+                    .service(routes::subscriptions::confirm_subscription)
+                    .service(routes::misc::get_background_gif)
+                    .service(routes::health::health)
+                    .service(routes::media::get_media)
+                    .service(routes::health::ready)
+                    .service(routes::stories::get_stories)
+                    .service(routes::stories::get_story)
+                    .service(routes::stories::random_story)
+                    .service(routes::stories::submit_story)
+                    .service(routes::subscriptions::subscribe)
+                    .service(routes::subscriptions::unsubscribe)
+                    .service(routes::health::version)
+                    .service(routes::csp::receive_csp_report)
+                    .service(routes::visitors::receive_visit_beacon)
+                    .service(routes::webmentions::receive_webmention)
+                    .service(
+                        web::scope("/admin")
+                            .service(routes::backgrounds::add_background)
+                            .service(routes::comments::approve_comment)
+                            .service(routes::backgrounds::disable_background)
+                            .service(routes::backgrounds::enable_background)
+                            .service(routes::backgrounds::get_backgrounds)
+                            .service(routes::comments::get_moderation_queue)
+                            .service(routes::comments::reject_comment)
+                            .service(routes::stories::approve_story)
+                            .service(routes::stories::get_story_queue)
+                            .service(routes::stories::reject_story)
+                            .service(routes::backgrounds::remove_background),
+                    )
+                    .service(
+                        web::scope("/blog")
+                            .service(routes::comments::get_post_comments)
+                            .service(routes::comments::submit_comment)
+                            .service(routes::posts::edit_post)
+                            .service(routes::posts::get_all_posts)
+                            .service(routes::posts::get_single_post)
+                            .service(routes::posts::publish_post)
+                            .service(routes::revisions::get_post_revisions)
+                            .service(routes::revisions::get_revision_diff)
+                            .service(routes::webmentions::get_post_webmentions),
+                    ),
+            )
+            .wrap(middleware::visitors::LogVisitors::new(
+                &config.visitors,
+                server_visitor_log.clone(),
+            ))
+            .wrap(rate_limit.clone())
+            .wrap(
+                Cors::default()
+                    .allowed_header(header::CONTENT_TYPE)
+                    .allowed_methods(vec!["GET", "POST"])
+                    .allowed_origin(&config.server.domain),
+            )
+            .wrap(middleware::security_headers::security_headers())
+            .wrap(middleware::metrics::RequestMetrics)
+            .wrap(middleware::request_id::RequestId)
+    })
+    .shutdown_timeout(SHUTDOWN_GRACE_PERIOD.as_secs())
+    .bind(("0.0.0.0", port))
+    .map_err(|error| StartupError::Bind { error, port })?
+    .run()
+    .await
+    .map_err(StartupError::Server)?;
+
+    visitor_log.shutdown();
+    tasks.shutdown(SHUTDOWN_GRACE_PERIOD).await;
+    info!("api stopped");
+
+    Ok(())
 }
@@ -0,0 +1,248 @@
+//! Contains the Prometheus metrics for the API, along with the middleware and MongoDB command
+//! listener that record them. The metrics are served in the Prometheus text format at `/metrics`.
+
+use std::{
+    collections::HashMap,
+    future::{ready, Future, Ready},
+    rc::Rc,
+    sync::Mutex,
+    time::Instant,
+};
+
+use actix_web::{
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    Error,
+};
+use futures_util::future::LocalBoxFuture;
+use lazy_static::lazy_static;
+use mongodb::{
+    bson::Bson,
+    event::command::{
+        CommandEventHandler, CommandFailedEvent, CommandStartedEvent, CommandSucceededEvent,
+    },
+};
+use prometheus::{
+    register_histogram_vec, register_int_counter, register_int_counter_vec, HistogramVec,
+    IntCounter, IntCounterVec,
+};
+
+lazy_static! {
+    /// The number of Content Security Policy violations reported by browsers, by directive.
+    pub static ref CSP_VIOLATIONS: IntCounterVec = register_int_counter_vec!(
+        "stacc_csp_violations_total",
+        "The number of Content Security Policy violations reported by browsers.",
+        &["directive"]
+    )
+    .expect("FAILED TO REGISTER THE CSP VIOLATIONS METRIC");
+    /// The number of HTTP requests handled, by method, route, and status code.
+    pub static ref HTTP_REQUESTS: IntCounterVec = register_int_counter_vec!(
+        "stacc_http_requests_total",
+        "The number of HTTP requests handled.",
+        &["method", "route", "status"]
+    )
+    .expect("FAILED TO REGISTER THE HTTP REQUESTS METRIC");
+    /// How long HTTP requests take to handle, by method and route.
+    pub static ref HTTP_REQUEST_DURATION: HistogramVec = register_histogram_vec!(
+        "stacc_http_request_duration_seconds",
+        "How long HTTP requests take to handle.",
+        &["method", "route"]
+    )
+    .expect("FAILED TO REGISTER THE HTTP REQUEST DURATION METRIC");
+    /// How long MongoDB operations take, by collection and operation.
+    pub static ref MONGODB_OPERATION_DURATION: HistogramVec = register_histogram_vec!(
+        "stacc_mongodb_operation_duration_seconds",
+        "How long MongoDB operations take.",
+        &["collection", "operation"],
+        vec![0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5]
+    )
+    .expect("FAILED TO REGISTER THE MONGODB OPERATION DURATION METRIC");
+    /// The number of failed MongoDB operations, by collection and operation.
+    pub static ref MONGODB_OPERATION_FAILURES: IntCounterVec = register_int_counter_vec!(
+        "stacc_mongodb_operation_failures_total",
+        "The number of failed MongoDB operations.",
+        &["collection", "operation"]
+    )
+    .expect("FAILED TO REGISTER THE MONGODB OPERATION FAILURES METRIC");
+    /// The number of times a post has been viewed, by post ID.
+    pub static ref POST_VIEWS: IntCounterVec = register_int_counter_vec!(
+        "stacc_post_views_total",
+        "The number of times a post has been viewed.",
+        &["post_id"]
+    )
+    .expect("FAILED TO REGISTER THE POST VIEWS METRIC");
+    /// The number of requests rejected by the rate limiter, by the rule that limited them.
+    pub static ref RATE_LIMITED_REQUESTS: IntCounterVec = register_int_counter_vec!(
+        "stacc_rate_limited_requests_total",
+        "The number of requests rejected by the rate limiter.",
+        &["rule"]
+    )
+    .expect("FAILED TO REGISTER THE RATE LIMITED REQUESTS METRIC");
+    /// How long requests to upstream APIs (Socrata, ip-api) take, by upstream.
+    pub static ref UPSTREAM_REQUEST_DURATION: HistogramVec = register_histogram_vec!(
+        "stacc_upstream_request_duration_seconds",
+        "How long requests to upstream APIs take.",
+        &["upstream"]
+    )
+    .expect("FAILED TO REGISTER THE UPSTREAM REQUEST DURATION METRIC");
+    /// The number of failed requests to upstream APIs, by upstream.
+    pub static ref UPSTREAM_REQUEST_FAILURES: IntCounterVec = register_int_counter_vec!(
+        "stacc_upstream_request_failures_total",
+        "The number of failed requests to upstream APIs.",
+        &["upstream"]
+    )
+    .expect("FAILED TO REGISTER THE UPSTREAM REQUEST FAILURES METRIC");
+    /// The number of visitor events dropped because the visitor log was full.
+    pub static ref VISITOR_EVENTS_DROPPED: IntCounter = register_int_counter!(
+        "stacc_visitor_events_dropped_total",
+        "The number of visitor events dropped because the visitor log was full."
+    )
+    .expect("FAILED TO REGISTER THE VISITOR EVENTS DROPPED METRIC");
+    /// The number of visits, split into `new` and `returning` visitors.
+    pub static ref VISITS: IntCounterVec = register_int_counter_vec!(
+        "stacc_visits_total",
+        "The number of visits from new and returning visitors.",
+        &["visitor"]
+    )
+    .expect("FAILED TO REGISTER THE VISITS METRIC");
+}
+
+/// Time a request to an upstream API, recording a failure if it returns an error.
+pub async fn time_upstream<T, E>(
+    upstream: &str,
+    request: impl Future<Output = Result<T, E>>,
+) -> Result<T, E> {
+    let timer = UPSTREAM_REQUEST_DURATION
+        .with_label_values(&[upstream])
+        .start_timer();
+    let result = request.await;
+    timer.observe_duration();
+
+    if result.is_err() {
+        UPSTREAM_REQUEST_FAILURES
+            .with_label_values(&[upstream])
+            .inc();
+    }
+
+    result
+}
+
+/// Records MongoDB operation timings. This is registered as the MongoDB client's command event
+/// handler, so every operation on every collection is timed without touching the call sites.
+#[derive(Debug, Default)]
+pub struct MongoCommandMetrics {
+    /// The collection each in-flight command is operating on, keyed by the command's request ID.
+    in_flight: Mutex<HashMap<i32, String>>,
+}
+
+impl MongoCommandMetrics {
+    /// Take the collection recorded for a finished command.
+    fn finish(&self, request_id: i32) -> String {
+        self.in_flight
+            .lock()
+            .ok()
+            .and_then(|mut in_flight| in_flight.remove(&request_id))
+            .unwrap_or_else(|| "none".to_string())
+    }
+}
+
+impl CommandEventHandler for MongoCommandMetrics {
+    fn handle_command_started_event(&self, event: CommandStartedEvent) {
+        // Collection commands name the collection as the value of the command itself, e.g.
+        // `{ find: "posts", ... }`. Commands such as `ping` do not target a collection.
+        let collection = match event.command.get(&event.command_name) {
+            Some(Bson::String(collection)) => collection.clone(),
+            _ => "none".to_string(),
+        };
+
+        if let Ok(mut in_flight) = self.in_flight.lock() {
+            in_flight.insert(event.request_id, collection);
+        }
+    }
+
+    fn handle_command_succeeded_event(&self, event: CommandSucceededEvent) {
+        let collection = self.finish(event.request_id);
+
+        MONGODB_OPERATION_DURATION
+            .with_label_values(&[&collection, &event.command_name])
+            .observe(event.duration.as_secs_f64());
+    }
+
+    fn handle_command_failed_event(&self, event: CommandFailedEvent) {
+        let collection = self.finish(event.request_id);
+
+        MONGODB_OPERATION_DURATION
+            .with_label_values(&[&collection, &event.command_name])
+            .observe(event.duration.as_secs_f64());
+        MONGODB_OPERATION_FAILURES
+            .with_label_values(&[&collection, &event.command_name])
+            .inc();
+    }
+}
+
+/// Middleware that records the count and latency of every request. Requests are labelled with the
+/// route pattern they matched (e.g. `/api/blog/post/{post_id}`) rather than the raw path, so that
+/// the number of label values stays bounded.
+pub struct RequestMetrics;
+
+impl<S, B> Transform<S, ServiceRequest> for RequestMetrics
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = RequestMetricsMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RequestMetricsMiddleware {
+            service: Rc::new(service),
+        }))
+    }
+}
+
+/// The service created by the `RequestMetrics` middleware.
+pub struct RequestMetricsMiddleware<S> {
+    /// The wrapped service.
+    service: Rc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for RequestMetricsMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, request: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+
+        Box::pin(async move {
+            let start = Instant::now();
+            let method = request.method().to_string();
+
+            let response = service.call(request).await?;
+
+            // The route is only known once the request has been routed.
+            let route = response
+                .request()
+                .match_pattern()
+                .unwrap_or_else(|| "unmatched".to_string());
+            let status = response.status().as_u16().to_string();
+
+            HTTP_REQUESTS
+                .with_label_values(&[&method, &route, &status])
+                .inc();
+            HTTP_REQUEST_DURATION
+                .with_label_values(&[&method, &route])
+                .observe(start.elapsed().as_secs_f64());
+
+            Ok(response)
+        })
+    }
+}
@@ -0,0 +1,318 @@
+//! Contains the middleware that rate limits every request by IP address.
+
+use std::{
+    collections::HashMap,
+    future::{ready, Ready},
+    net::IpAddr,
+    rc::Rc,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use actix_web::{
+    body::EitherBody,
+    dev::{forward_ready, ResourceDef, Service, ServiceRequest, ServiceResponse, Transform},
+    http::{
+        header::{self, HeaderValue},
+        Method,
+    },
+    Error, ResponseError,
+};
+use futures_util::future::LocalBoxFuture;
+use ipnet::IpNet;
+use lazy_static::lazy_static;
+use tracing::info;
+
+use crate::{
+    errors::StaccResponseError,
+    middleware::{self, metrics},
+    utils::{
+        config::{Limit, RateLimitConfig, RouteLimit},
+        logging,
+    },
+};
+
+lazy_static! {
+    /// The limits for routes that write to MongoDB, send email, or call upstream APIs. Limits from
+    /// the config are checked first, so these may be overridden.
+    static ref BUILT_IN_ROUTE_LIMITS: Vec<(Method, &'static str, Limit)> = vec![
+        (Method::GET, "/api/chiraq", Limit { requests: 10, seconds: 60 }),
+        (Method::POST, "/api/blog/post/{post_id}/comments", Limit { requests: 1, seconds: 30 }),
+        (Method::POST, "/api/stories", Limit { requests: 1, seconds: 30 }),
+        (Method::POST, "/api/subscribe", Limit { requests: 1, seconds: 30 }),
+        (Method::POST, "/api/visits", Limit { requests: 10, seconds: 60 }),
+        (Method::POST, "/api/webmention", Limit { requests: 10, seconds: 60 }),
+    ];
+}
+
+/// How often buckets that have refilled completely are dropped, so that the map of buckets does not
+/// grow forever.
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// A limit that applies to a single route.
+#[derive(Debug)]
+struct Rule {
+    /// The limit for this route.
+    limit: Limit,
+    /// The HTTP method this limit applies to. Applies to every method if this is not set.
+    method: Option<Method>,
+    /// The pattern matching the route's path.
+    pattern: ResourceDef,
+}
+
+impl Rule {
+    /// Check whether this rule applies to a request.
+    fn matches(&self, method: &Method, path: &str) -> bool {
+        self.method
+            .as_ref()
+            .is_none_or(|rule_method| rule_method == method)
+            && self.pattern.is_match(path)
+    }
+
+    /// The name of this rule, for logs and metrics.
+    fn name(&self) -> String {
+        match &self.method {
+            Some(method) => format!("{method} {}", self.pattern.pattern().unwrap_or_default()),
+            None => self.pattern.pattern().unwrap_or_default().to_string(),
+        }
+    }
+}
+
+/// A token bucket for a single rule and IP address.
+#[derive(Debug)]
+struct Bucket {
+    /// The number of requests that may currently be made.
+    tokens: f64,
+    /// When the number of tokens was last updated.
+    updated: Instant,
+}
+
+/// The buckets for every rule and IP address, and when they were last cleaned up.
+#[derive(Debug)]
+struct Buckets {
+    /// Maps each rule (by index, with `None` being the default limit) and IP address to its bucket.
+    /// Clients whose address could not be found share a bucket.
+    buckets: HashMap<(Option<usize>, Option<IpAddr>), Bucket>,
+    /// When buckets that have refilled completely were last dropped.
+    cleaned: Instant,
+}
+
+/// The state shared by every worker's copy of the middleware.
+#[derive(Debug)]
+struct RateLimitState {
+    /// The ranges that are never rate limited.
+    allowlist: Vec<IpNet>,
+    /// The token buckets.
+    buckets: Mutex<Buckets>,
+    /// The limit for every route that does not have its own.
+    default: Limit,
+    /// Whether requests are rate limited at all.
+    enabled: bool,
+    /// The limits for individual routes, in the order they are checked.
+    rules: Vec<Rule>,
+}
+
+impl RateLimitState {
+    /// Take a token from the bucket for the request's route and IP address. Returns how long the
+    /// client must wait if there are none left.
+    fn check(
+        &self,
+        method: &Method,
+        path: &str,
+        ip_address: Option<IpAddr>,
+    ) -> Result<(), RateLimited> {
+        let rule = self
+            .rules
+            .iter()
+            .position(|rule| rule.matches(method, path));
+        let limit = rule.map_or(self.default, |index| self.rules[index].limit);
+
+        let now = Instant::now();
+        let mut buckets = self
+            .buckets
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        if now.duration_since(buckets.cleaned) >= CLEANUP_INTERVAL {
+            buckets.cleaned = now;
+            buckets.buckets.retain(|(rule, _ip_address), bucket| {
+                let limit = rule.map_or(self.default, |index| self.rules[index].limit);
+
+                now.duration_since(bucket.updated).as_secs() < u64::from(limit.seconds)
+            });
+        }
+
+        let capacity = f64::from(limit.requests);
+        let refill_rate = capacity / f64::from(limit.seconds);
+
+        let bucket = buckets.buckets.entry((rule, ip_address)).or_insert(Bucket {
+            tokens: capacity,
+            updated: now,
+        });
+
+        let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * refill_rate).min(capacity);
+        bucket.updated = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+
+            return Ok(());
+        }
+
+        Err(RateLimited {
+            retry_after: ((1.0 - bucket.tokens) / refill_rate).ceil() as u64,
+            rule: rule.map_or_else(|| "default".to_string(), |index| self.rules[index].name()),
+        })
+    }
+
+    /// Check whether an IP address is never rate limited.
+    fn is_allowlisted(&self, ip_address: Option<IpAddr>) -> bool {
+        ip_address.is_some_and(|ip_address| {
+            self.allowlist
+                .iter()
+                .any(|network| network.contains(&ip_address))
+        })
+    }
+}
+
+/// A request that was rate limited.
+struct RateLimited {
+    /// How many seconds the client must wait before trying again.
+    retry_after: u64,
+    /// The name of the rule that limited the request.
+    rule: String,
+}
+
+/// Middleware that rate limits every request with a token bucket per route and IP address. Each
+/// route's bucket holds as many requests as its limit allows, and refills over the limit's window.
+/// Requests over the limit are rejected with `429 Too Many Requests` and a `Retry-After` header.
+#[derive(Clone)]
+pub struct RateLimit {
+    /// The state shared by every worker.
+    state: Arc<RateLimitState>,
+}
+
+impl RateLimit {
+    /// Create a new `RateLimit` from the config. Route limits from the config are checked before
+    /// the built-in ones.
+    pub fn new(config: &RateLimitConfig) -> Self {
+        let built_in = BUILT_IN_ROUTE_LIMITS
+            .iter()
+            .map(|(method, pattern, limit)| RouteLimit {
+                limit: *limit,
+                method: Some(method.clone()),
+                pattern: pattern.to_string(),
+            });
+
+        let rules = config
+            .routes
+            .iter()
+            .cloned()
+            .chain(built_in)
+            .map(|route_limit| Rule {
+                limit: route_limit.limit,
+                method: route_limit.method,
+                pattern: ResourceDef::new(route_limit.pattern),
+            })
+            .collect();
+
+        Self {
+            state: Arc::new(RateLimitState {
+                allowlist: config.allowlist.clone(),
+                buckets: Mutex::new(Buckets {
+                    buckets: HashMap::new(),
+                    cleaned: Instant::now(),
+                }),
+                default: config.default,
+                enabled: config.enabled,
+                rules,
+            }),
+        }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RateLimit
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = RateLimitMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RateLimitMiddleware {
+            service: Rc::new(service),
+            state: Arc::clone(&self.state),
+        }))
+    }
+}
+
+/// The service created by the `RateLimit` middleware.
+pub struct RateLimitMiddleware<S> {
+    /// The wrapped service.
+    service: Rc<S>,
+    /// The state shared by every worker.
+    state: Arc<RateLimitState>,
+}
+
+impl<S, B> Service<ServiceRequest> for RateLimitMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, request: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+
+        if !self.state.enabled {
+            return Box::pin(async move { Ok(service.call(request).await?.map_into_left_body()) });
+        }
+
+        let ip_address = middleware::get_real_ip(request.request());
+
+        let checked = if self.state.is_allowlisted(ip_address) {
+            Ok(())
+        } else {
+            self.state
+                .check(request.method(), request.path(), ip_address)
+        };
+
+        match checked {
+            Ok(()) => {
+                Box::pin(async move { Ok(service.call(request).await?.map_into_left_body()) })
+            }
+            Err(RateLimited { retry_after, rule }) => {
+                let ip_address =
+                    ip_address.map_or("UNKNOWN".to_string(), |ip_address| ip_address.to_string());
+                info!(
+                    ip_address = logging::ip_address(&ip_address),
+                    retry_after, rule, "rate limited a request"
+                );
+                metrics::RATE_LIMITED_REQUESTS
+                    .with_label_values(&[&rule])
+                    .inc();
+
+                let mut response = StaccResponseError::RateLimited {
+                    error: format!("Slow down. Try again in {retry_after} seconds."),
+                }
+                .error_response();
+                response
+                    .headers_mut()
+                    .insert(header::RETRY_AFTER, HeaderValue::from(retry_after));
+
+                Box::pin(ready(Ok(request
+                    .into_response(response)
+                    .map_into_right_body())))
+            }
+        }
+    }
+}
@@ -0,0 +1,122 @@
+//! Contains the middleware that gives every request an ID and logs it once it has been handled.
+
+use std::{
+    future::{ready, Ready},
+    rc::Rc,
+    time::Instant,
+};
+
+use actix_web::{
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    http::header::{HeaderName, HeaderValue},
+    Error,
+};
+use futures_util::future::LocalBoxFuture;
+use tracing::{field, info, info_span, Instrument, Span};
+use uuid::Uuid;
+
+/// The header a request ID is read from, and returned in.
+const REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+/// The longest request ID that will be accepted from a client.
+const MAX_REQUEST_ID_LENGTH: usize = 128;
+
+/// Middleware that gives every request an ID. The ID is taken from the `X-Request-Id` header if the
+/// client (or a proxy) sent a sensible one, and generated otherwise. Everything logged while the
+/// request is handled, including by other middleware, is attached to a span carrying the ID, and the
+/// ID is returned in the response's `X-Request-Id` header.
+pub struct RequestId;
+
+impl<S, B> Transform<S, ServiceRequest> for RequestId
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = RequestIdMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RequestIdMiddleware {
+            service: Rc::new(service),
+        }))
+    }
+}
+
+/// The service created by the `RequestId` middleware.
+pub struct RequestIdMiddleware<S> {
+    /// The wrapped service.
+    service: Rc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for RequestIdMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, request: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+
+        let request_id = request
+            .headers()
+            .get(&REQUEST_ID_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .filter(|value| is_valid_request_id(value))
+            .map_or_else(|| Uuid::new_v4().to_string(), str::to_string);
+        // The route is recorded once the request has been routed. The raw path is not logged, as it
+        // may contain tokens.
+        let span = info_span!(
+            "request",
+            method = %request.method(),
+            request_id = %request_id,
+            route = field::Empty,
+        );
+
+        Box::pin(
+            async move {
+                let start = Instant::now();
+
+                let mut response = service.call(request).await?;
+
+                Span::current().record(
+                    "route",
+                    response
+                        .request()
+                        .match_pattern()
+                        .as_deref()
+                        .unwrap_or("unmatched"),
+                );
+                info!(
+                    latency_ms = start.elapsed().as_millis() as u64,
+                    status = response.status().as_u16(),
+                    "handled request"
+                );
+
+                if let Ok(value) = HeaderValue::from_str(&request_id) {
+                    response.headers_mut().insert(REQUEST_ID_HEADER, value);
+                }
+
+                Ok(response)
+            }
+            .instrument(span),
+        )
+    }
+}
+
+/// Check that a request ID sent by a client is safe to log and return. Anything else is replaced
+/// with a generated ID.
+fn is_valid_request_id(request_id: &str) -> bool {
+    !request_id.is_empty()
+        && request_id.len() <= MAX_REQUEST_ID_LENGTH
+        && request_id
+            .chars()
+            .all(|character| character.is_ascii_alphanumeric() || "-_.:".contains(character))
+}
@@ -0,0 +1,37 @@
+//! Contains the security headers sent with every API response.
+//!
+//! The site itself is served as static files by the reverse proxy, which sends its own policy
+//! (see `docs/security-headers.md`). Both policies report violations to `/api/csp-report`.
+
+use actix_web::{
+    http::header::{
+        CONTENT_SECURITY_POLICY, REFERRER_POLICY, STRICT_TRANSPORT_SECURITY,
+        X_CONTENT_TYPE_OPTIONS, X_FRAME_OPTIONS,
+    },
+    middleware::DefaultHeaders,
+};
+
+/// The API only returns JSON and media, so nothing in a response may load or run anything, and no
+/// response may be framed.
+const CONTENT_SECURITY_POLICY_VALUE: &str = "default-src 'none'; base-uri 'none'; \
+    form-action 'none'; frame-ancestors 'none'; report-uri /api/csp-report; report-to csp";
+
+/// Tell browsers to only use HTTPS for the next two years. Browsers ignore this header on plain
+/// HTTP responses, so it does no harm in development.
+const STRICT_TRANSPORT_SECURITY_VALUE: &str = "max-age=63072000; includeSubDomains";
+
+/// Turn off browser features the site never uses.
+const PERMISSIONS_POLICY_VALUE: &str = "accelerometer=(), camera=(), geolocation=(), \
+    gyroscope=(), magnetometer=(), microphone=(), payment=(), usb=()";
+
+/// Middleware that adds security headers to every response that does not already set them.
+pub fn security_headers() -> DefaultHeaders {
+    DefaultHeaders::new()
+        .add((CONTENT_SECURITY_POLICY, CONTENT_SECURITY_POLICY_VALUE))
+        .add(("Permissions-Policy", PERMISSIONS_POLICY_VALUE))
+        .add((REFERRER_POLICY, "no-referrer"))
+        .add(("Reporting-Endpoints", "csp=\"/api/csp-report\""))
+        .add((STRICT_TRANSPORT_SECURITY, STRICT_TRANSPORT_SECURITY_VALUE))
+        .add((X_CONTENT_TYPE_OPTIONS, "nosniff"))
+        .add((X_FRAME_OPTIONS, "DENY"))
+}
@@ -0,0 +1,407 @@
+//! Contains the middleware that logs visitors, and the writer that records them in MongoDB.
+//!
+//! Requests never wait on MongoDB. Visits and post views are sent through a bounded channel to a
+//! single writer, which records them in batches, combining every event from the same IP address
+//! into one write.
+
+use std::{
+    collections::HashMap,
+    future::{ready, Ready},
+    rc::Rc,
+    time::Duration,
+};
+
+use actix_web::{
+    dev::{forward_ready, ResourceDef, Service, ServiceRequest, ServiceResponse, Transform},
+    http::Method,
+    web::Data,
+    Error, HttpRequest,
+};
+use chrono::Utc;
+use lazy_static::lazy_static;
+use mongodb::bson::{doc, Document};
+use reqwest::Client;
+use tokio::sync::mpsc::{self, error::TrySendError, Receiver, Sender};
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info, warn};
+
+use crate::{
+    errors::StaccError,
+    middleware::{self, metrics},
+    models::visitor::{IPData, Visitor},
+    utils::{config::VisitorsConfig, logging, mongo::Mongo},
+};
+
+lazy_static! {
+    /// All fields that will be included in the `ip-api.com` response.
+    static ref IP_API_FIELDS: Vec<&'static str> = vec![
+        "as",
+        "city",
+        "continent",
+        "country",
+        "countryCode",
+        "currency",
+        "hosting",
+        "isp",
+        "lat",
+        "lon",
+        "message",
+        "mobile",
+        "org",
+        "proxy",
+        "query",
+        "region",
+        "regionName",
+        "reverse",
+        "status",
+        "timezone",
+        "zip",
+    ];
+    /// The client used for `ip-api.com` lookups. Lookups run inside the `VisitorWriter`, so a slow
+    /// one holds up every other visitor, and they are given up on quickly.
+    static ref IP_API_CLIENT: Client = Client::builder()
+        .connect_timeout(Duration::from_secs(1))
+        .timeout(Duration::from_secs(2))
+        .build()
+        .expect("UNABLE TO BUILD THE IP-API CLIENT");
+}
+
+/// The number of events that may wait for the writer. Events are dropped once it is full.
+const QUEUE_SIZE: usize = 4096;
+
+/// The largest number of events the writer records at once.
+const MAX_BATCH_SIZE: usize = 256;
+
+/// Something a visitor did that is worth recording.
+#[derive(Debug)]
+struct VisitorEvent {
+    /// The visitor's IP address.
+    ip_address: String,
+    /// The post that was viewed, or `None` if this is a visit.
+    post_id: Option<String>,
+}
+
+/// Everything one visitor did within a batch.
+#[derive(Debug, Default)]
+struct VisitorActivity {
+    /// The number of visits.
+    refreshes: i32,
+    /// Maps each post viewed to the number of times it was viewed.
+    visited_posts: HashMap<String, i32>,
+}
+
+/// The sending half of the visitor log, which is shared by the middleware and the handlers.
+#[derive(Clone, Debug)]
+pub struct VisitorLog {
+    /// The channel to the writer.
+    sender: Sender<VisitorEvent>,
+    /// Cancelled when the API shuts down, which tells the writer to finish the events that are
+    /// still queued and stop.
+    shutdown: CancellationToken,
+}
+
+impl VisitorLog {
+    /// Create a new `VisitorLog`, along with the writer that must be run in the background for
+    /// anything to be recorded.
+    pub fn new(mongo: Data<Mongo>) -> (Self, VisitorWriter) {
+        let (sender, receiver) = mpsc::channel(QUEUE_SIZE);
+        let shutdown = CancellationToken::new();
+
+        (
+            Self {
+                sender,
+                shutdown: shutdown.clone(),
+            },
+            VisitorWriter {
+                mongo,
+                receiver,
+                shutdown,
+            },
+        )
+    }
+
+    /// Record a post view. The post's view count is incremented by the handler itself, so this
+    /// only adds the post to the visitor's `visited_posts`.
+    pub fn post_view(&self, post_id: &str, request: &HttpRequest) {
+        metrics::POST_VIEWS.with_label_values(&[post_id]).inc();
+
+        self.send(request, Some(post_id.to_string()));
+    }
+
+    /// Record a visit. See `VisitorWriter` for what is recorded.
+    pub fn visit(&self, request: &HttpRequest) {
+        self.send(request, None);
+    }
+
+    /// Tell the writer to finish the events that are still queued and stop. Events sent after this
+    /// are dropped.
+    pub fn shutdown(&self) {
+        self.shutdown.cancel();
+    }
+
+    /// Queue an event for the writer without waiting. The event is dropped if the queue is full.
+    fn send(&self, request: &HttpRequest, post_id: Option<String>) {
+        let Some(ip_address) = middleware::get_real_ip(request) else {
+            warn!("failed to get the visitor's IP address");
+            return;
+        };
+
+        let event = VisitorEvent {
+            ip_address: ip_address.to_string(),
+            post_id,
+        };
+
+        match self.sender.try_send(event) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_event)) => {
+                metrics::VISITOR_EVENTS_DROPPED.inc();
+                warn!("the visitor log is full, dropping a visitor event");
+            }
+            Err(TrySendError::Closed(_event)) => {
+                metrics::VISITOR_EVENTS_DROPPED.inc();
+            }
+        }
+    }
+}
+
+/// Records visitor events in MongoDB. For each visitor in a batch, `refresh_count` and the counts
+/// in `visited_posts` are incremented in a single write. New visitors are looked up with
+/// `ip-api.com` and inserted.
+pub struct VisitorWriter {
+    /// The database that visitors are recorded in.
+    mongo: Data<Mongo>,
+    /// The receiving half of the channel.
+    receiver: Receiver<VisitorEvent>,
+    /// Cancelled when the API shuts down.
+    shutdown: CancellationToken,
+}
+
+impl VisitorWriter {
+    /// Record events until the API shuts down and every queued event has been recorded.
+    pub async fn run(mut self) {
+        let mut closed = false;
+
+        loop {
+            let event = tokio::select! {
+                event = self.receiver.recv() => event,
+                _ = self.shutdown.cancelled(), if !closed => {
+                    // Stop accepting events, but keep receiving the ones already queued.
+                    self.receiver.close();
+                    closed = true;
+
+                    continue;
+                }
+            };
+            let Some(event) = event else {
+                break;
+            };
+
+            // Events queue up while a batch is being written, so busy periods are written in
+            // fewer, larger batches.
+            let mut batch = vec![event];
+            while batch.len() < MAX_BATCH_SIZE {
+                match self.receiver.try_recv() {
+                    Ok(event) => batch.push(event),
+                    Err(_) => break,
+                }
+            }
+
+            self.record_batch(batch).await;
+        }
+
+        info!("stopped logging visitors");
+    }
+
+    /// Combine a batch of events by visitor and record each visitor's activity.
+    async fn record_batch(&self, batch: Vec<VisitorEvent>) {
+        let mut visitors: HashMap<String, VisitorActivity> = HashMap::new();
+
+        for event in batch {
+            let activity = visitors.entry(event.ip_address).or_default();
+
+            match event.post_id {
+                Some(post_id) => *activity.visited_posts.entry(post_id).or_default() += 1,
+                None => activity.refreshes += 1,
+            }
+        }
+
+        for (ip_address, activity) in visitors {
+            if let Err(error) = self.record_visitor(&ip_address, activity).await {
+                error!(%error, "failed to log visitor data");
+            }
+        }
+    }
+
+    /// Add a visitor's activity to their document, or create one if this is a new visitor.
+    async fn record_visitor(
+        &self,
+        ip_address: &str,
+        activity: VisitorActivity,
+    ) -> Result<(), StaccError> {
+        let mut increments = Document::new();
+        if activity.refreshes > 0 {
+            increments.insert("refresh_count", activity.refreshes);
+        }
+        for (post_id, views) in &activity.visited_posts {
+            increments.insert(format!("visited_posts.{post_id}"), views);
+        }
+
+        let mut update = doc! { "$inc": increments };
+        if activity.refreshes > 0 {
+            update.insert(
+                "$set",
+                doc! { "last_visit_date": Utc::now().format("%Y-%m-%d %H:%M:%S").to_string() },
+            );
+        }
+
+        let result = self
+            .mongo
+            .visitor_collection
+            .update_one(doc! { "ip_address": ip_address }, update, None)
+            .await?;
+
+        if result.matched_count > 0 {
+            metrics::VISITS
+                .with_label_values(&["returning"])
+                .inc_by(activity.refreshes as u64);
+
+            return Ok(());
+        }
+
+        metrics::VISITS.with_label_values(&["new"]).inc();
+        metrics::VISITS
+            .with_label_values(&["returning"])
+            .inc_by(activity.refreshes.saturating_sub(1) as u64);
+
+        // This is the only task that writes visitors, so nothing else can insert this visitor
+        // while their IP data is being looked up. Lookups are skipped once the API is shutting
+        // down, so that the queue is drained quickly.
+        let mut visitor = Visitor::new(ip_address.to_string());
+        if !self.shutdown.is_cancelled() {
+            visitor.ip_data = get_ip_data(ip_address).await.ok();
+        }
+        visitor.refresh_count = activity.refreshes;
+        visitor.visited_posts = activity.visited_posts;
+
+        self.mongo
+            .visitor_collection
+            .insert_one(visitor, None)
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// Query `ip-api.com` for IP metadata.
+async fn get_ip_data(ip: &str) -> Result<IPData, StaccError> {
+    info!(
+        ip_address = logging::ip_address(ip),
+        "logging a new visitor"
+    );
+
+    let endpoint = "http://ip-api.com/json/";
+
+    let request_endpoint = &format!("{endpoint}{ip}?fields={}", IP_API_FIELDS.join(","));
+    info!(
+        ip_address = logging::ip_address(ip),
+        "sending request to ip-api"
+    );
+
+    let ip_data = metrics::time_upstream("ip-api", async {
+        IP_API_CLIENT
+            .get(request_endpoint)
+            .send()
+            .await?
+            .json::<IPData>()
+            .await
+    })
+    .await?;
+
+    info!(
+        ip_address = logging::ip_address(ip),
+        "received ip data from ip-api"
+    );
+
+    Ok(ip_data)
+}
+
+/// Middleware that logs a visit for every `GET` request to one of the configured routes. Visits
+/// are queued for the `VisitorWriter`, so responses never wait on them.
+pub struct LogVisitors {
+    /// Whether visitors are logged at all.
+    enabled: bool,
+    /// The patterns of the routes that visitors are logged for.
+    routes: Rc<Vec<ResourceDef>>,
+    /// The visitor log that visits are sent to.
+    visitor_log: Data<VisitorLog>,
+}
+
+impl LogVisitors {
+    /// Create a new `LogVisitors` from the config.
+    pub fn new(config: &VisitorsConfig, visitor_log: Data<VisitorLog>) -> Self {
+        Self {
+            enabled: config.enabled,
+            routes: Rc::new(config.routes.iter().map(ResourceDef::new).collect()),
+            visitor_log,
+        }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for LogVisitors
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = LogVisitorsMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(LogVisitorsMiddleware {
+            enabled: self.enabled,
+            routes: Rc::clone(&self.routes),
+            service,
+            visitor_log: self.visitor_log.clone(),
+        }))
+    }
+}
+
+/// The service created by the `LogVisitors` middleware.
+pub struct LogVisitorsMiddleware<S> {
+    /// Whether visitors are logged at all.
+    enabled: bool,
+    /// The patterns of the routes that visitors are logged for.
+    routes: Rc<Vec<ResourceDef>>,
+    /// The wrapped service.
+    service: S,
+    /// The visitor log that visits are sent to.
+    visitor_log: Data<VisitorLog>,
+}
+
+impl<S, B> Service<ServiceRequest> for LogVisitorsMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = S::Future;
+
+    forward_ready!(service);
+
+    fn call(&self, request: ServiceRequest) -> Self::Future {
+        if self.enabled
+            && request.method() == Method::GET
+            && self
+                .routes
+                .iter()
+                .any(|route| route.is_match(request.path()))
+        {
+            self.visitor_log.visit(request.request());
+        }
+
+        self.service.call(request)
+    }
+}
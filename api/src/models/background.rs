@@ -0,0 +1,81 @@
+//! Contains models for background GIFs.
+
+use serde::{Deserialize, Serialize};
+
+/// Contains a background GIF and its curation metadata.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct BackgroundGIF {
+    /// The background's ID. Backgrounds stored before IDs were introduced are backfilled with
+    /// their MongoDB `_id` at startup.
+    #[serde(default)]
+    pub background_id: String,
+    /// The GIF's dominant color as a `#rrggbb` hex code. This is shown behind the GIF while it
+    /// loads.
+    pub dominant_color: Option<String>,
+    /// Whether this background may be chosen at random.
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// The Imgur link to the background GIF.
+    pub link: String,
+    /// Where the GIF is from, e.g. the film or the artist.
+    pub source: Option<String>,
+    /// A link to the GIF's source.
+    pub source_link: Option<String>,
+    /// Tags describing the GIF.
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// The GIF's title.
+    pub title: Option<String>,
+    /// How likely this background is to be chosen, relative to the other enabled backgrounds.
+    #[serde(default = "default_weight")]
+    pub weight: u32,
+}
+
+/// Contains a background GIF submitted through the admin API.
+#[derive(Debug, Deserialize)]
+pub struct NewBackground {
+    /// The GIF's dominant color as a `#rrggbb` hex code.
+    pub dominant_color: Option<String>,
+    /// Whether this background may be chosen at random.
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// The link to the background GIF.
+    pub link: String,
+    /// Where the GIF is from.
+    pub source: Option<String>,
+    /// A link to the GIF's source.
+    pub source_link: Option<String>,
+    /// Tags describing the GIF.
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// The GIF's title.
+    pub title: Option<String>,
+    /// How likely this background is to be chosen.
+    #[serde(default = "default_weight")]
+    pub weight: u32,
+}
+
+/// Contains the query parameters for a background served by the media proxy.
+#[derive(Debug, Deserialize)]
+pub struct MediaQuery {
+    /// The format to serve the background in: `gif`, `mp4`, or `webp`. If this is not set, WebP is
+    /// served to browsers that accept it and GIF to everything else.
+    pub format: Option<String>,
+}
+
+/// This struct holds a `Vec<BackgroundGIF>`.
+#[derive(Debug, Serialize)]
+pub struct AllBackgrounds {
+    /// Contains every background, enabled or not.
+    pub backgrounds: Vec<BackgroundGIF>,
+}
+
+/// Backgrounds are enabled unless they have been disabled.
+fn default_enabled() -> bool {
+    true
+}
+
+/// Backgrounds without a weight are as likely to be chosen as any other.
+fn default_weight() -> u32 {
+    1
+}
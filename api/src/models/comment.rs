@@ -0,0 +1,42 @@
+//! Contains models for post comments.
+
+use serde::{Deserialize, Serialize};
+
+use super::moderation::ModerationStatus;
+
+/// Contains a reader's comment on a post.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Comment {
+    /// The name the reader chose to comment under.
+    pub author: String,
+    /// The body of the comment.
+    pub body: String,
+    /// The comment's ID.
+    pub comment_id: String,
+    /// The created timestamp.
+    pub created: String,
+    /// The ID of the post this comment belongs to.
+    pub post_id: String,
+    /// The moderation status of this comment.
+    pub status: ModerationStatus,
+}
+
+/// Contains a comment submitted by a reader.
+#[derive(Debug, Deserialize)]
+pub struct NewComment {
+    /// The name the reader chose to comment under.
+    pub author: String,
+    /// The body of the comment.
+    pub body: String,
+    /// A honeypot field that is hidden from readers on the frontend. Bots that fill in every field
+    /// will populate this, so any comment that includes it is silently dropped.
+    #[serde(default)]
+    pub website: String,
+}
+
+/// This struct holds a `Vec<Comment>`.
+#[derive(Debug, Serialize)]
+pub struct AllComments {
+    /// Contains comments from oldest to newest.
+    pub comments: Vec<Comment>,
+}
@@ -0,0 +1,119 @@
+//! Contains models for Content Security Policy violation reports.
+
+use serde::Deserialize;
+
+/// A violation report body, in either format browsers send them in.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum CspReports {
+    /// A single report sent to a `report-uri`, as `application/csp-report`.
+    ReportUri {
+        /// The report.
+        #[serde(rename = "csp-report")]
+        csp_report: ReportUriReport,
+    },
+    /// A batch of reports sent to a `report-to` endpoint by the Reporting API, as
+    /// `application/reports+json`. Reports of other types may be included.
+    ReportingApi(Vec<ReportingApiReport>),
+}
+
+/// A violation report sent to a `report-uri`. Every field is optional, as browsers differ in
+/// which ones they send.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct ReportUriReport {
+    /// The resource that was blocked, e.g. a URL, `inline`, or `eval`.
+    pub blocked_uri: Option<String>,
+    /// Whether the policy was enforced or only reported.
+    pub disposition: Option<String>,
+    /// The page the violation happened on.
+    pub document_uri: Option<String>,
+    /// The directive that was violated, without its value.
+    pub effective_directive: Option<String>,
+    /// The line of the source file the violation happened on.
+    pub line_number: Option<u64>,
+    /// The script the violation happened in.
+    pub source_file: Option<String>,
+    /// The directive that was violated, with its value. Older browsers only send this.
+    pub violated_directive: Option<String>,
+}
+
+/// A single report sent by the Reporting API.
+#[derive(Debug, Deserialize)]
+pub struct ReportingApiReport {
+    /// The report's contents.
+    #[serde(default)]
+    pub body: ReportingApiBody,
+    /// The type of report. Violation reports are `csp-violation`.
+    #[serde(rename = "type")]
+    pub report_type: String,
+}
+
+/// The contents of a `csp-violation` report sent by the Reporting API.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ReportingApiBody {
+    /// The resource that was blocked, e.g. a URL, `inline`, or `eval`.
+    #[serde(rename = "blockedURL")]
+    pub blocked_url: Option<String>,
+    /// Whether the policy was enforced or only reported.
+    pub disposition: Option<String>,
+    /// The page the violation happened on.
+    #[serde(rename = "documentURL")]
+    pub document_url: Option<String>,
+    /// The directive that was violated.
+    pub effective_directive: Option<String>,
+    /// The line of the source file the violation happened on.
+    pub line_number: Option<u64>,
+    /// The script the violation happened in.
+    pub source_file: Option<String>,
+}
+
+/// A violation, whichever format it was reported in.
+#[derive(Debug)]
+pub struct CspViolation {
+    /// The resource that was blocked.
+    pub blocked_uri: Option<String>,
+    /// The directive that was violated, without its value.
+    pub directive: Option<String>,
+    /// Whether the policy was enforced or only reported.
+    pub disposition: Option<String>,
+    /// The page the violation happened on.
+    pub document_uri: Option<String>,
+    /// The line of the source file the violation happened on.
+    pub line_number: Option<u64>,
+    /// The script the violation happened in.
+    pub source_file: Option<String>,
+}
+
+impl CspReports {
+    /// Get every violation in the reports. Reports that are not violations are skipped.
+    pub fn into_violations(self) -> Vec<CspViolation> {
+        match self {
+            Self::ReportUri { csp_report } => vec![CspViolation {
+                blocked_uri: csp_report.blocked_uri,
+                directive: csp_report.effective_directive.or_else(|| {
+                    csp_report.violated_directive.and_then(|directive| {
+                        directive.split_whitespace().next().map(str::to_string)
+                    })
+                }),
+                disposition: csp_report.disposition,
+                document_uri: csp_report.document_uri,
+                line_number: csp_report.line_number,
+                source_file: csp_report.source_file,
+            }],
+            Self::ReportingApi(reports) => reports
+                .into_iter()
+                .filter(|report| report.report_type == "csp-violation")
+                .map(|report| CspViolation {
+                    blocked_uri: report.body.blocked_url,
+                    directive: report.body.effective_directive,
+                    disposition: report.body.disposition,
+                    document_uri: report.body.document_url,
+                    line_number: report.body.line_number,
+                    source_file: report.body.source_file,
+                })
+                .collect(),
+        }
+    }
+}
@@ -3,13 +3,6 @@
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
-/// Contains the Imgur link to the background GIF.
-#[derive(Debug, Deserialize, Serialize)]
-pub struct BackgroundGIF {
-    /// The Imgur link to the background GIF.
-    pub link: String,
-}
-
 /// Contains JSON data returned from Chicago map-related APIs.
 #[derive(Debug, Deserialize, Serialize)]
 pub struct ChicagoMapData {
@@ -19,13 +12,6 @@ pub struct ChicagoMapData {
     pub violence_data: Value,
 }
 
-/// Contains the story for the 404 page.
-#[derive(Debug, Deserialize, Serialize)]
-pub struct Story {
-    /// The body of the story.
-    pub story: String,
-}
-
 /// Serialize an API response message and status code.
 #[derive(Debug, Serialize)]
 pub struct Response {
@@ -0,0 +1,44 @@
+//! Contains models for the health, readiness, and version endpoints.
+
+use serde::{Deserialize, Serialize};
+
+/// The result of checking a single dependency of the API.
+#[derive(Debug, Serialize)]
+pub struct DependencyCheck {
+    /// Why the check failed, either `unavailable` or `timed out`. The dependency's own error is only
+    /// logged.
+    pub error: Option<String>,
+    /// How long the check took, in milliseconds.
+    pub latency_ms: u128,
+    /// The name of the dependency, e.g. `mongodb`.
+    pub name: String,
+    /// Whether the dependency is usable.
+    pub ok: bool,
+}
+
+/// Query parameters accepted by the readiness endpoint.
+#[derive(Debug, Deserialize)]
+pub struct ReadinessQuery {
+    /// Also check that the Socrata API is reachable. Defaults to `false`, since the API can still
+    /// serve everything but the Chicago map without it.
+    #[serde(default)]
+    pub socrata: bool,
+}
+
+/// Contains the results of every readiness check.
+#[derive(Debug, Serialize)]
+pub struct Readiness {
+    /// The result of each individual check.
+    pub checks: Vec<DependencyCheck>,
+    /// Whether every check passed.
+    pub ready: bool,
+}
+
+/// Contains the version of the running API.
+#[derive(Debug, Serialize)]
+pub struct Version {
+    /// The git SHA the API was built from, or `unknown`.
+    pub git_sha: String,
+    /// The crate version from `Cargo.toml`.
+    pub version: String,
+}
@@ -1,5 +1,15 @@
 //! Contains models for all data within the API.
 
+pub mod background;
+pub mod comment;
+pub mod csp;
 pub mod data;
+pub mod health;
+pub mod moderation;
 pub mod post;
+pub mod problem;
+pub mod revision;
+pub mod story;
+pub mod subscriber;
 pub mod visitor;
+pub mod webmention;
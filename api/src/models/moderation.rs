@@ -0,0 +1,26 @@
+//! Contains models shared by every kind of reader submission that goes through moderation.
+
+use serde::{Deserialize, Serialize};
+
+/// Contains all moderation states that a reader-submitted document may be in.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ModerationStatus {
+    /// The document has been approved and is publicly visible.
+    Approved,
+    /// The document is waiting in the moderation queue.
+    Pending,
+    /// The document has been rejected and will never be shown.
+    Rejected,
+}
+
+impl ModerationStatus {
+    /// Get the string that is stored in MongoDB for this variant.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Approved => "approved",
+            Self::Pending => "pending",
+            Self::Rejected => "rejected",
+        }
+    }
+}
@@ -31,3 +31,20 @@ pub struct AllPosts {
     /// Contains all posts from newest to oldest.
     pub posts: Vec<PostData>,
 }
+
+/// Contains all fields required to publish a new post.
+#[derive(Debug, Deserialize)]
+pub struct NewPost {
+    /// The body of the post.
+    pub body: String,
+    /// The post's ID. This is used in the post's URL.
+    pub post_id: String,
+    /// The preview image link.
+    pub preview_image_link: String,
+    /// The preview summary.
+    pub preview_summary: String,
+    /// The title of the post.
+    pub title: String,
+    /// The topic of the post.
+    pub topic: String,
+}
@@ -0,0 +1,21 @@
+//! Contains the RFC 7807 problem details body that every API error is returned as.
+
+use serde::Serialize;
+
+/// An RFC 7807 `application/problem+json` body.
+#[derive(Debug, Serialize)]
+pub struct Problem {
+    /// A stable, machine-readable code for the kind of error, e.g. `not-found`. Clients should
+    /// match on this rather than on `title` or `detail`.
+    pub code: &'static str,
+    /// A human-readable explanation of this occurrence of the error.
+    pub detail: String,
+    /// The HTTP status code.
+    pub status: u16,
+    /// A short, human-readable summary of the kind of error. This is the same for every error with
+    /// the same `code`.
+    pub title: &'static str,
+    /// A URI identifying the kind of error, built from `code`.
+    #[serde(rename = "type")]
+    pub problem_type: String,
+}
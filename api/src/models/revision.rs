@@ -0,0 +1,77 @@
+//! Contains models for post revisions.
+
+use serde::{Deserialize, Serialize};
+
+/// Contains a snapshot of a post's title and body at a particular point in time.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct PostRevision {
+    /// The body of the post at this revision.
+    pub body: String,
+    /// The timestamp at which this revision was created.
+    pub created: String,
+    /// The ID of the post this revision belongs to.
+    pub post_id: String,
+    /// The revision number. The original post is always revision `0`.
+    pub revision_number: i32,
+    /// The title of the post at this revision.
+    pub title: String,
+}
+
+/// This struct holds a `Vec<PostRevision>` containing all revisions for a single post.
+#[derive(Debug, Serialize)]
+pub struct PostRevisions {
+    /// Contains all revisions from oldest to newest.
+    pub revisions: Vec<PostRevision>,
+}
+
+/// Contains the fields that may be changed when editing a post.
+#[derive(Debug, Deserialize)]
+pub struct PostEdit {
+    /// The new body of the post.
+    pub body: Option<String>,
+    /// The new title of the post.
+    pub title: Option<String>,
+}
+
+/// Contains the query parameters for diffing two revisions.
+#[derive(Debug, Deserialize)]
+pub struct DiffQuery {
+    /// The revision number to diff from.
+    pub from: i32,
+    /// The revision number to diff to.
+    pub to: i32,
+}
+
+/// Contains all variants for the type of change a single line underwent between revisions.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineChange {
+    /// The line was removed.
+    Delete,
+    /// The line is unchanged.
+    Equal,
+    /// The line was added.
+    Insert,
+}
+
+/// A single line within a diff.
+#[derive(Debug, Serialize)]
+pub struct DiffLine {
+    /// The type of change this line underwent.
+    pub change: LineChange,
+    /// The content of the line, without its trailing newline.
+    pub line: String,
+}
+
+/// Contains the line diffs for the title and body between two revisions.
+#[derive(Debug, Serialize)]
+pub struct RevisionDiff {
+    /// The line diff for the post's body.
+    pub body: Vec<DiffLine>,
+    /// The revision number that was diffed from.
+    pub from: i32,
+    /// The line diff for the post's title.
+    pub title: Vec<DiffLine>,
+    /// The revision number that was diffed to.
+    pub to: i32,
+}
@@ -0,0 +1,67 @@
+//! Contains models for 404 page stories.
+
+use serde::{Deserialize, Serialize};
+
+use super::moderation::ModerationStatus;
+
+/// Contains a story for the 404 page.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Story {
+    /// The name the reader submitted the story under. Stories that were added directly to MongoDB
+    /// do not have an author.
+    pub author: Option<String>,
+    /// The created timestamp. Stories that were added directly to MongoDB do not have one.
+    pub created: Option<String>,
+    /// The moderation status of this story. Stories that were added directly to MongoDB are
+    /// approved.
+    #[serde(default = "default_status")]
+    pub status: ModerationStatus,
+    /// The body of the story.
+    pub story: String,
+    /// The story's ID.
+    #[serde(default)]
+    pub story_id: String,
+}
+
+/// Contains a story submitted by a reader.
+#[derive(Debug, Deserialize)]
+pub struct NewStory {
+    /// The name the reader chose to submit the story under.
+    pub author: String,
+    /// The body of the story.
+    pub story: String,
+    /// A honeypot field that is hidden from readers on the frontend. Bots that fill in every field
+    /// will populate this, so any story that includes it is silently dropped.
+    #[serde(default)]
+    pub website: String,
+}
+
+/// This struct holds a `Vec<Story>`.
+#[derive(Debug, Serialize)]
+pub struct AllStories {
+    /// Contains stories from oldest to newest.
+    pub stories: Vec<Story>,
+}
+
+/// Contains the query parameters for a page of the story archive.
+#[derive(Debug, Deserialize)]
+pub struct StoryPageQuery {
+    /// The page number, starting at 1. Defaults to the first page.
+    pub page: Option<u64>,
+}
+
+/// Contains a page of the story archive.
+#[derive(Debug, Serialize)]
+pub struct StoryPage {
+    /// The page number, starting at 1.
+    pub page: u64,
+    /// Contains the stories on this page from newest to oldest.
+    pub stories: Vec<Story>,
+    /// The total number of pages in the archive.
+    pub total_pages: u64,
+}
+
+/// Stories without a moderation status predate story submissions and were added by hand.
+fn default_status() -> ModerationStatus {
+    ModerationStatus::Approved
+}
@@ -0,0 +1,34 @@
+//! Contains models for newsletter subscribers.
+
+use serde::{Deserialize, Serialize};
+
+/// Contains a newsletter subscriber.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Subscriber {
+    /// The token sent in the confirmation email. This is cleared once the subscription has been
+    /// confirmed.
+    pub confirmation_token: Option<String>,
+    /// Whether the subscriber has clicked the link in the confirmation email.
+    pub confirmed: bool,
+    /// The subscriber's email address.
+    pub email: String,
+    /// The timestamp of the subscription request.
+    pub subscribed: String,
+}
+
+/// Contains a subscription request submitted by a reader.
+#[derive(Debug, Deserialize)]
+pub struct NewSubscriber {
+    /// The reader's email address.
+    pub email: String,
+}
+
+/// Contains an unsubscribe request. The signature is generated when the newsletter is sent and
+/// proves that the request came from a link in an email.
+#[derive(Debug, Deserialize)]
+pub struct Unsubscribe {
+    /// The subscriber's email address.
+    pub email: String,
+    /// The signature of the email address.
+    pub signature: String,
+}
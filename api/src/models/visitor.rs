@@ -94,3 +94,15 @@ pub struct IPData {
     /// The ZIP associated with the IP address.
     pub zip: String,
 }
+
+/// Views queued by the frontend's service worker while a reader was offline, sent once they are
+/// back online.
+#[derive(Debug, Deserialize)]
+pub struct VisitBeacon {
+    /// The ID of every post that was read from the cache, once per view.
+    #[serde(default)]
+    pub post_ids: Vec<String>,
+    /// The number of requests served from the cache that would have been logged as visits.
+    #[serde(default)]
+    pub visits: u32,
+}
@@ -0,0 +1,52 @@
+//! Contains models for Webmentions.
+
+use serde::{Deserialize, Serialize};
+
+/// Contains all types of Webmentions a post may receive.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MentionType {
+    /// The source page likes the post (`u-like-of`).
+    Like,
+    /// The source page links to the post without any other semantics.
+    Mention,
+    /// The source page is a reply to the post (`u-in-reply-to`).
+    Reply,
+}
+
+/// Contains a verified Webmention received for a post.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Webmention {
+    /// The name of the source page's author, if it could be found.
+    pub author_name: Option<String>,
+    /// The text content of the source page's entry. Only set for replies.
+    pub content: Option<String>,
+    /// The type of this mention.
+    pub mention_type: MentionType,
+    /// The ID of the post that was mentioned.
+    pub post_id: String,
+    /// The URL of the page that mentions the post.
+    pub source: String,
+    /// The title of the source page, if it has one.
+    pub source_title: Option<String>,
+    /// The URL of the post that was mentioned.
+    pub target: String,
+    /// The timestamp of the most recent successful verification.
+    pub verified: String,
+}
+
+/// Contains the form fields of an incoming Webmention request.
+#[derive(Debug, Deserialize)]
+pub struct IncomingWebmention {
+    /// The URL of the page that mentions the target.
+    pub source: String,
+    /// The URL of the page that was mentioned.
+    pub target: String,
+}
+
+/// This struct holds a `Vec<Webmention>`.
+#[derive(Debug, Serialize)]
+pub struct AllWebmentions {
+    /// Contains all verified mentions from oldest to newest.
+    pub webmentions: Vec<Webmention>,
+}
@@ -0,0 +1,268 @@
+//! Contains all routes pertaining to background GIF management.
+
+use actix_web::{
+    delete, get, post, put,
+    web::{Data, Json, Path},
+    HttpRequest, HttpResponse,
+};
+use futures_util::stream::StreamExt;
+use lazy_static::lazy_static;
+use mongodb::{
+    bson::doc,
+    options::{FindOneAndUpdateOptions, FindOptions, ReturnDocument},
+};
+use url::Url;
+use uuid::Uuid;
+
+use crate::{
+    errors::StaccResponseError,
+    models::background::{AllBackgrounds, BackgroundGIF, NewBackground},
+    utils::{auth, config::Config, mongo::Mongo, pool::RandomPool},
+};
+
+lazy_static! {
+    /// The maximum number of characters allowed in a background's title or source.
+    static ref MAX_TEXT_LENGTH: usize = 200;
+    /// The maximum number of tags a background may have.
+    static ref MAX_TAGS: usize = 20;
+    /// The maximum weight a background may have.
+    static ref MAX_WEIGHT: u32 = 1000;
+}
+
+/// Get every background, including disabled ones.
+///
+/// This is an admin-only endpoint.
+#[get("/backgrounds")]
+pub async fn get_backgrounds(
+    config: Data<Config>,
+    mongo: Data<Mongo>,
+    request: HttpRequest,
+) -> Result<HttpResponse, StaccResponseError> {
+    auth::authorize_admin(&config, &request)?;
+
+    let mut backgrounds: Vec<BackgroundGIF> = Vec::new();
+
+    let mut cursor = mongo
+        .backgrounds_collection
+        .find(
+            doc! {},
+            FindOptions::builder().sort(doc! { "title": 1 }).build(),
+        )
+        .await
+        .map_err(StaccResponseError::from)?;
+
+    while let Some(document) = cursor.next().await {
+        backgrounds.push(document.map_err(StaccResponseError::from)?);
+    }
+
+    Ok(HttpResponse::Ok().json(AllBackgrounds { backgrounds }))
+}
+
+/// Add a new background.
+///
+/// This is an admin-only endpoint.
+#[post("/backgrounds")]
+pub async fn add_background(
+    background_pool: Data<RandomPool<BackgroundGIF>>,
+    config: Data<Config>,
+    mongo: Data<Mongo>,
+    new_background: Json<NewBackground>,
+    request: HttpRequest,
+) -> Result<HttpResponse, StaccResponseError> {
+    auth::authorize_admin(&config, &request)?;
+
+    let background = validate_background(new_background.into_inner())?;
+
+    mongo
+        .backgrounds_collection
+        .insert_one(&background, None)
+        .await
+        .map_err(StaccResponseError::from)?;
+    background_pool.invalidate();
+
+    Ok(HttpResponse::Created().json(background))
+}
+
+/// Remove a background and return it.
+///
+/// This is an admin-only endpoint.
+#[delete("/backgrounds/{background_id}")]
+pub async fn remove_background(
+    background_id: Path<String>,
+    background_pool: Data<RandomPool<BackgroundGIF>>,
+    config: Data<Config>,
+    mongo: Data<Mongo>,
+    request: HttpRequest,
+) -> Result<HttpResponse, StaccResponseError> {
+    auth::authorize_admin(&config, &request)?;
+
+    let background = mongo
+        .backgrounds_collection
+        .find_one_and_delete(doc! { "background_id": background_id.into_inner() }, None)
+        .await
+        .map_err(StaccResponseError::from)?
+        .ok_or_else(background_not_found)?;
+    background_pool.invalidate();
+
+    Ok(HttpResponse::Ok().json(background))
+}
+
+/// Enable a background so that it may be chosen at random.
+///
+/// This is an admin-only endpoint.
+#[put("/backgrounds/{background_id}/enable")]
+pub async fn enable_background(
+    background_id: Path<String>,
+    background_pool: Data<RandomPool<BackgroundGIF>>,
+    config: Data<Config>,
+    mongo: Data<Mongo>,
+    request: HttpRequest,
+) -> Result<HttpResponse, StaccResponseError> {
+    auth::authorize_admin(&config, &request)?;
+
+    set_enabled(&background_id, &background_pool, true, &mongo).await
+}
+
+/// Disable a background so that it is never chosen, without removing it.
+///
+/// This is an admin-only endpoint.
+#[put("/backgrounds/{background_id}/disable")]
+pub async fn disable_background(
+    background_id: Path<String>,
+    background_pool: Data<RandomPool<BackgroundGIF>>,
+    config: Data<Config>,
+    mongo: Data<Mongo>,
+    request: HttpRequest,
+) -> Result<HttpResponse, StaccResponseError> {
+    auth::authorize_admin(&config, &request)?;
+
+    set_enabled(&background_id, &background_pool, false, &mongo).await
+}
+
+/// The error returned when a background does not exist.
+fn background_not_found() -> StaccResponseError {
+    StaccResponseError::NotFound {
+        error: "Background not found!".to_string(),
+    }
+}
+
+/// Set whether a background may be chosen and return the updated background.
+async fn set_enabled(
+    background_id: &str,
+    background_pool: &RandomPool<BackgroundGIF>,
+    enabled: bool,
+    mongo: &Data<Mongo>,
+) -> Result<HttpResponse, StaccResponseError> {
+    let background = mongo
+        .backgrounds_collection
+        .find_one_and_update(
+            doc! { "background_id": background_id },
+            doc! { "$set": { "enabled": enabled } },
+            FindOneAndUpdateOptions::builder()
+                .return_document(ReturnDocument::After)
+                .build(),
+        )
+        .await
+        .map_err(StaccResponseError::from)?
+        .ok_or_else(background_not_found)?;
+    background_pool.invalidate();
+
+    Ok(HttpResponse::Ok().json(background))
+}
+
+/// Check that a link is an absolute `http` or `https` URL.
+fn is_http_link(link: &str) -> bool {
+    Url::parse(link).is_ok_and(|url| matches!(url.scheme(), "http" | "https"))
+}
+
+/// Check that a color is a `#rrggbb` hex code.
+fn is_hex_color(color: &str) -> bool {
+    color.len() == 7
+        && color.starts_with('#')
+        && color[1..]
+            .chars()
+            .all(|character| character.is_ascii_hexdigit())
+}
+
+/// Trim a background's optional text field, treating a blank value as missing.
+fn trim_text(text: Option<String>) -> Option<String> {
+    text.map(|text| text.trim().to_string())
+        .filter(|text| !text.is_empty())
+}
+
+/// Validate and normalize a submitted background, assigning it a new ID.
+fn validate_background(new_background: NewBackground) -> Result<BackgroundGIF, StaccResponseError> {
+    let link = new_background.link.trim().to_string();
+    if !is_http_link(&link) {
+        return Err(StaccResponseError::BadInput {
+            error: "The link must be an http or https URL.".to_string(),
+        });
+    }
+
+    let source_link = trim_text(new_background.source_link);
+    if source_link
+        .as_deref()
+        .is_some_and(|link| !is_http_link(link))
+    {
+        return Err(StaccResponseError::BadInput {
+            error: "The source link must be an http or https URL.".to_string(),
+        });
+    }
+
+    let dominant_color = trim_text(new_background.dominant_color).map(|color| color.to_lowercase());
+    if dominant_color
+        .as_deref()
+        .is_some_and(|color| !is_hex_color(color))
+    {
+        return Err(StaccResponseError::BadInput {
+            error: "The dominant color must be a #rrggbb hex code.".to_string(),
+        });
+    }
+
+    let source = trim_text(new_background.source);
+    let title = trim_text(new_background.title);
+    if [&source, &title]
+        .into_iter()
+        .flatten()
+        .any(|text| text.chars().count() > *MAX_TEXT_LENGTH)
+    {
+        return Err(StaccResponseError::BadInput {
+            error: format!(
+                "Titles and sources may not exceed {} characters.",
+                *MAX_TEXT_LENGTH
+            ),
+        });
+    }
+
+    let mut tags: Vec<String> = new_background
+        .tags
+        .iter()
+        .map(|tag| tag.trim().to_lowercase())
+        .filter(|tag| !tag.is_empty())
+        .collect();
+    tags.sort();
+    tags.dedup();
+    if tags.len() > *MAX_TAGS {
+        return Err(StaccResponseError::BadInput {
+            error: format!("Backgrounds may not have more than {} tags.", *MAX_TAGS),
+        });
+    }
+
+    if new_background.weight > *MAX_WEIGHT {
+        return Err(StaccResponseError::BadInput {
+            error: format!("Weights may not exceed {}.", *MAX_WEIGHT),
+        });
+    }
+
+    Ok(BackgroundGIF {
+        background_id: Uuid::new_v4().to_string(),
+        dominant_color,
+        enabled: new_background.enabled,
+        link,
+        source,
+        source_link,
+        tags,
+        title,
+        weight: new_background.weight,
+    })
+}
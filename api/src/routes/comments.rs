@@ -0,0 +1,200 @@
+//! Contains all routes pertaining to post comments.
+
+use actix_web::{
+    get, post, put,
+    web::{Data, Json, Path},
+    HttpRequest, HttpResponse,
+};
+use chrono::Utc;
+use futures_util::stream::StreamExt;
+use mongodb::{
+    bson::{doc, Document},
+    options::{FindOneAndUpdateOptions, FindOptions, ReturnDocument},
+};
+use uuid::Uuid;
+
+use crate::{
+    errors::StaccResponseError,
+    models::{
+        comment::{AllComments, Comment, NewComment},
+        moderation::ModerationStatus,
+    },
+    utils::{
+        auth,
+        config::Config,
+        moderation::{self, Submission, SubmissionKind},
+        mongo::Mongo,
+    },
+};
+
+/// Get all approved comments for a single post, ordered from oldest to newest.
+#[get("/post/{post_id}/comments")]
+pub async fn get_post_comments(
+    mongo: Data<Mongo>,
+    post_id: Path<String>,
+) -> Result<HttpResponse, StaccResponseError> {
+    let comments = find_comments(
+        &mongo,
+        doc! {
+            "post_id": post_id.into_inner(),
+            "status": ModerationStatus::Approved.as_str()
+        },
+    )
+    .await?;
+
+    Ok(HttpResponse::Ok().json(AllComments { comments }))
+}
+
+/// Submit a new comment on a post. The comment is placed in the moderation queue and will not be
+/// visible until it is approved.
+#[post("/post/{post_id}/comments")]
+pub async fn submit_comment(
+    mongo: Data<Mongo>,
+    new_comment: Json<NewComment>,
+    post_id: Path<String>,
+    request: HttpRequest,
+) -> Result<HttpResponse, StaccResponseError> {
+    let post_id = post_id.into_inner();
+    let new_comment = new_comment.into_inner();
+
+    let Some(Submission { author, body }) = moderation::check_submission(
+        SubmissionKind::Comment,
+        &request,
+        &new_comment.author,
+        &new_comment.body,
+        &new_comment.website,
+    )?
+    else {
+        return Ok(moderation::submitted(SubmissionKind::Comment));
+    };
+
+    if mongo
+        .posts_collection
+        .find_one(doc! { "post_id": &post_id }, None)
+        .await
+        .map_err(StaccResponseError::from)?
+        .is_none()
+    {
+        return Err(StaccResponseError::NotFound {
+            error: "Post not found!".to_string(),
+        });
+    }
+
+    mongo
+        .comments_collection
+        .insert_one(
+            Comment {
+                author,
+                body,
+                comment_id: Uuid::new_v4().to_string(),
+                created: Utc::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+                post_id,
+                status: ModerationStatus::Pending,
+            },
+            None,
+        )
+        .await
+        .map_err(StaccResponseError::from)?;
+
+    Ok(moderation::submitted(SubmissionKind::Comment))
+}
+
+/// Get all comments waiting in the moderation queue, ordered from oldest to newest.
+///
+/// This is an admin-only endpoint.
+#[get("/comments")]
+pub async fn get_moderation_queue(
+    config: Data<Config>,
+    mongo: Data<Mongo>,
+    request: HttpRequest,
+) -> Result<HttpResponse, StaccResponseError> {
+    auth::authorize_admin(&config, &request)?;
+
+    let comments = find_comments(
+        &mongo,
+        doc! { "status": ModerationStatus::Pending.as_str() },
+    )
+    .await?;
+
+    Ok(HttpResponse::Ok().json(AllComments { comments }))
+}
+
+/// Approve a comment, making it publicly visible under its post.
+///
+/// This is an admin-only endpoint.
+#[put("/comments/{comment_id}/approve")]
+pub async fn approve_comment(
+    comment_id: Path<String>,
+    config: Data<Config>,
+    mongo: Data<Mongo>,
+    request: HttpRequest,
+) -> Result<HttpResponse, StaccResponseError> {
+    auth::authorize_admin(&config, &request)?;
+
+    moderate_comment(&mongo, &comment_id, ModerationStatus::Approved).await
+}
+
+/// Reject a comment so that it is never shown.
+///
+/// This is an admin-only endpoint.
+#[put("/comments/{comment_id}/reject")]
+pub async fn reject_comment(
+    comment_id: Path<String>,
+    config: Data<Config>,
+    mongo: Data<Mongo>,
+    request: HttpRequest,
+) -> Result<HttpResponse, StaccResponseError> {
+    auth::authorize_admin(&config, &request)?;
+
+    moderate_comment(&mongo, &comment_id, ModerationStatus::Rejected).await
+}
+
+/// Find all comments matching the filter, ordered from oldest to newest.
+async fn find_comments(
+    mongo: &Data<Mongo>,
+    filter: Document,
+) -> Result<Vec<Comment>, StaccResponseError> {
+    let mut comments: Vec<Comment> = Vec::new();
+
+    let mut cursor = mongo
+        .comments_collection
+        .find(
+            filter,
+            FindOptions::builder().sort(doc! { "created": 1 }).build(),
+        )
+        .await
+        .map_err(StaccResponseError::from)?;
+
+    while let Some(document) = cursor.next().await {
+        comments.push(document.map_err(StaccResponseError::from)?);
+    }
+
+    Ok(comments)
+}
+
+/// Set the moderation status of a comment and return the updated comment.
+async fn moderate_comment(
+    mongo: &Data<Mongo>,
+    comment_id: &str,
+    status: ModerationStatus,
+) -> Result<HttpResponse, StaccResponseError> {
+    mongo
+        .comments_collection
+        .find_one_and_update(
+            doc! { "comment_id": comment_id },
+            doc! { "$set": { "status": status.as_str() } },
+            FindOneAndUpdateOptions::builder()
+                .return_document(ReturnDocument::After)
+                .build(),
+        )
+        .await
+        .map_err(StaccResponseError::from)?
+        .map_or_else(
+            || {
+                Err(StaccResponseError::NotFound {
+                    error: "Comment not found!".to_string(),
+                })
+            },
+            |comment| Ok(HttpResponse::Ok().json(comment)),
+        )
+}
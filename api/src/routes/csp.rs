@@ -0,0 +1,74 @@
+//! Contains the Content Security Policy violation report route.
+
+use actix_web::{post, web::Bytes, HttpResponse};
+use tracing::warn;
+
+use crate::{errors::StaccResponseError, middleware::metrics, models::csp::CspReports};
+
+/// The largest report body that will be read, in bytes.
+const MAX_REPORT_BYTES: usize = 64 * 1024;
+
+/// The directives violations are counted by. Anything else is counted as `other`, so that clients
+/// cannot create new metric labels.
+const KNOWN_DIRECTIVES: &[&str] = &[
+    "base-uri",
+    "child-src",
+    "connect-src",
+    "default-src",
+    "font-src",
+    "form-action",
+    "frame-ancestors",
+    "frame-src",
+    "img-src",
+    "manifest-src",
+    "media-src",
+    "object-src",
+    "script-src",
+    "script-src-attr",
+    "script-src-elem",
+    "style-src",
+    "style-src-attr",
+    "style-src-elem",
+    "worker-src",
+];
+
+/// Receive Content Security Policy violation reports from browsers. Both `report-uri` reports and
+/// Reporting API batches are accepted. Violations are logged and counted, but not stored.
+#[post("/csp-report")]
+pub async fn receive_csp_report(body: Bytes) -> Result<HttpResponse, StaccResponseError> {
+    if body.len() > MAX_REPORT_BYTES {
+        return Err(StaccResponseError::BadInput {
+            error: format!("Reports may not exceed {MAX_REPORT_BYTES} bytes."),
+        });
+    }
+
+    // Browsers send reports as `application/csp-report` or `application/reports+json`, which the
+    // JSON extractor does not accept, so the body is parsed here instead.
+    let reports =
+        serde_json::from_slice::<CspReports>(&body).map_err(|_| StaccResponseError::BadInput {
+            error: "That is not a Content Security Policy violation report.".to_string(),
+        })?;
+
+    for violation in reports.into_violations() {
+        let directive = violation
+            .directive
+            .as_deref()
+            .filter(|directive| KNOWN_DIRECTIVES.contains(directive))
+            .unwrap_or("other");
+        metrics::CSP_VIOLATIONS
+            .with_label_values(&[directive])
+            .inc();
+
+        warn!(
+            blocked_uri = violation.blocked_uri,
+            directive = violation.directive,
+            disposition = violation.disposition,
+            document_uri = violation.document_uri,
+            line_number = violation.line_number,
+            source_file = violation.source_file,
+            "received a content security policy violation report"
+        );
+    }
+
+    Ok(HttpResponse::NoContent().finish())
+}
@@ -0,0 +1,100 @@
+//! Contains routes for checking whether the API is alive and ready to serve requests. These do not
+//! log visitor data, since they are polled by Docker and the deploy scripts.
+
+use std::{future::Future, time::Instant};
+
+use actix_web::{
+    get,
+    web::{Data, Query},
+    HttpResponse,
+};
+use tokio::time::{self, Duration};
+use tracing::warn;
+
+use crate::{
+    errors::StaccError,
+    models::{
+        data::Response,
+        health::{DependencyCheck, Readiness, ReadinessQuery, Version},
+    },
+    utils::{chicago, config::Config, mongo::Mongo},
+};
+
+/// How long a single readiness check may take before it is considered failed.
+const CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Liveness check. Responds as long as the server is accepting requests.
+#[get("/health")]
+pub async fn health() -> HttpResponse {
+    HttpResponse::Ok().json(Response {
+        message: "OK".to_string(),
+        status_code: 200,
+    })
+}
+
+/// Readiness check. Pings MongoDB, and optionally the Socrata API with `?socrata=true`. Responds
+/// with `503 Service Unavailable` if any check fails.
+#[get("/ready")]
+pub async fn ready(
+    config: Data<Config>,
+    mongo: Data<Mongo>,
+    query: Query<ReadinessQuery>,
+) -> HttpResponse {
+    let mut checks = vec![run_check("mongodb", mongo.ping()).await];
+
+    if query.socrata {
+        checks.push(run_check("socrata", chicago::ping_socrata(&config.socrata.app_token)).await);
+    }
+
+    let ready = checks.iter().all(|check| check.ok);
+    let readiness = Readiness { checks, ready };
+
+    if ready {
+        HttpResponse::Ok().json(readiness)
+    } else {
+        HttpResponse::ServiceUnavailable().json(readiness)
+    }
+}
+
+/// Get the crate version and the git SHA the API was built from.
+#[get("/version")]
+pub async fn version() -> HttpResponse {
+    HttpResponse::Ok().json(Version {
+        git_sha: env!("GIT_SHA").to_string(),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+    })
+}
+
+/// Time a single readiness check, failing it if it takes longer than `CHECK_TIMEOUT`. Failures are
+/// logged in full, and only described as `unavailable` or `timed out` in the response.
+async fn run_check(
+    name: &str,
+    check: impl Future<Output = Result<(), StaccError>>,
+) -> DependencyCheck {
+    let start = Instant::now();
+
+    // `/api/ready` is public, and driver errors may name hosts or credentials, so they are only
+    // logged.
+    let error = match time::timeout(CHECK_TIMEOUT, check).await {
+        Ok(Ok(())) => None,
+        Ok(Err(error)) => {
+            warn!(dependency = name, %error, "readiness check failed");
+            Some("unavailable")
+        }
+        Err(_) => {
+            warn!(
+                dependency = name,
+                timeout_secs = CHECK_TIMEOUT.as_secs(),
+                "readiness check timed out"
+            );
+            Some("timed out")
+        }
+    };
+
+    DependencyCheck {
+        latency_ms: start.elapsed().as_millis(),
+        name: name.to_string(),
+        ok: error.is_none(),
+        error: error.map(str::to_string),
+    }
+}
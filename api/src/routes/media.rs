@@ -0,0 +1,133 @@
+//! Contains the background media proxy route.
+
+use actix_files::NamedFile;
+use actix_web::{
+    get,
+    http::header::{self, HeaderValue},
+    web::{Data, Path, Query},
+    HttpRequest, HttpResponse,
+};
+use mongodb::bson::doc;
+use sha2::{Digest, Sha256};
+use tracing::warn;
+
+use crate::{
+    errors::StaccResponseError,
+    models::background::MediaQuery,
+    routes::misc::fallback_background,
+    utils::{
+        media::{MediaCache, MediaFormat},
+        mongo::Mongo,
+    },
+};
+
+/// Cached backgrounds never change, as a background's link cannot be edited once it is added.
+const CACHE_CONTROL: &str = "public, max-age=31536000, immutable";
+
+/// The `fallback` ID is an alias for whichever GIF the fallback background currently links to, so
+/// browsers only keep it for a day.
+const FALLBACK_CACHE_CONTROL: &str = "public, max-age=86400";
+
+/// Serve a background from the media cache, downloading it first if it is not cached yet. WebP is
+/// served to browsers that accept it if conversions are enabled, unless a `format` is requested.
+#[get("/media/{background_id}")]
+pub async fn get_media(
+    background_id: Path<String>,
+    media_cache: Data<MediaCache>,
+    mongo: Data<Mongo>,
+    query: Query<MediaQuery>,
+    request: HttpRequest,
+) -> Result<HttpResponse, StaccResponseError> {
+    let background_id = background_id.into_inner();
+
+    // The ID becomes part of a file name, so only IDs that look like the ones the API creates are
+    // accepted.
+    if background_id.is_empty()
+        || !background_id
+            .chars()
+            .all(|character| character.is_ascii_alphanumeric() || character == '-')
+    {
+        return Err(media_not_found());
+    }
+
+    let is_fallback = background_id == "fallback";
+    let background = if is_fallback {
+        fallback_background()
+    } else {
+        mongo
+            .backgrounds_collection
+            .find_one(doc! { "background_id": &background_id }, None)
+            .await
+            .map_err(StaccResponseError::from)?
+            .ok_or_else(media_not_found)?
+    };
+
+    let format = match query.format.as_deref() {
+        Some(format) => MediaFormat::from_extension(format)
+            .filter(|format| *format == MediaFormat::Gif || media_cache.can_convert())
+            .ok_or_else(|| StaccResponseError::BadInput {
+                error: format!("Backgrounds are not available as `{format}`."),
+            })?,
+        None if media_cache.can_convert() && accepts_webp(&request) => MediaFormat::Webp,
+        None => MediaFormat::Gif,
+    };
+
+    // The media cache outlives deploys, so the fallback is cached under its link, which changes
+    // whenever the fallback does.
+    let cache_id = if is_fallback {
+        format!(
+            "fallback-{}",
+            &hex::encode(Sha256::digest(background.link.as_bytes()))[..16]
+        )
+    } else {
+        background_id.clone()
+    };
+
+    let path = match media_cache.get(&cache_id, &background.link, format).await {
+        Ok(path) => path,
+        // WebP is only an optimization, so fall back to the GIF if the conversion failed.
+        Err(error) if query.format.is_none() && format == MediaFormat::Webp => {
+            warn!(%error, background_id, "failed to convert a background to webp");
+
+            media_cache
+                .get(&cache_id, &background.link, MediaFormat::Gif)
+                .await
+                .map_err(|error| StaccResponseError::upstream("The background's host", error))?
+        }
+        Err(error) => return Err(StaccResponseError::upstream("The background's host", error)),
+    };
+
+    let mut response = NamedFile::open_async(path)
+        .await
+        .map_err(|error| StaccResponseError::upstream("The media cache", error.into()))?
+        .into_response(&request);
+
+    let headers = response.headers_mut();
+    headers.insert(
+        header::CACHE_CONTROL,
+        HeaderValue::from_static(if is_fallback {
+            FALLBACK_CACHE_CONTROL
+        } else {
+            CACHE_CONTROL
+        }),
+    );
+    headers.insert(header::VARY, HeaderValue::from_static("Accept"));
+
+    Ok(response)
+}
+
+/// Check whether the browser accepts WebP images.
+fn accepts_webp(request: &HttpRequest) -> bool {
+    request
+        .headers()
+        .get(header::ACCEPT)
+        .and_then(|accept| accept.to_str().ok())
+        .is_some_and(|accept| accept.contains("image/webp"))
+}
+
+/// The error returned when a background does not exist.
+fn media_not_found() -> StaccResponseError {
+    StaccResponseError::NotFound {
+        error: "Background not found!".to_string(),
+    }
+}
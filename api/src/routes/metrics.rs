@@ -0,0 +1,35 @@
+//! Contains the route that exposes the API's Prometheus metrics.
+
+use actix_web::{get, web::Data, HttpRequest, HttpResponse};
+use prometheus::{Encoder, TextEncoder};
+use tracing::error;
+
+use crate::{
+    errors::StaccResponseError,
+    utils::{auth, config::Config},
+};
+
+/// Get every metric in the Prometheus text format. The metrics include per-route traffic, post
+/// views, and upstream failures, so this is admin-only; point Prometheus' `authorization`
+/// credentials at the admin token.
+#[get("/metrics")]
+pub async fn metrics(
+    config: Data<Config>,
+    request: HttpRequest,
+) -> Result<HttpResponse, StaccResponseError> {
+    auth::authorize_admin(&config, &request)?;
+
+    let encoder = TextEncoder::new();
+
+    let mut buffer = String::new();
+    match encoder.encode_utf8(&prometheus::gather(), &mut buffer) {
+        Ok(()) => Ok(HttpResponse::Ok()
+            .content_type(encoder.format_type())
+            .body(buffer)),
+        Err(error) => {
+            error!(%error, "failed to encode metrics");
+
+            Ok(HttpResponse::InternalServerError().finish())
+        }
+    }
+}
@@ -1,7 +1,5 @@
 //! Contains miscellaneous routes for the API.
 
-use std::time::Duration;
-
 use actix_web::{
     cookie::{Cookie, SameSite},
     get,
@@ -9,25 +7,21 @@ use actix_web::{
     HttpRequest, HttpResponse,
 };
 use lazy_static::lazy_static;
-use log::error;
-use mongodb::{bson::doc, options::FindOneOptions};
-use rand::Rng;
-use tokio::time;
+use rand::distributions::{Distribution, WeightedIndex};
+use tracing::warn;
 
 use crate::{
     errors::StaccResponseError,
-    middleware,
-    models::data::{BackgroundGIF, Story},
-    utils::{chicago::get_vhnfs_shotspotter_data, environment::EnvironmentVariables, mongo::Mongo},
+    models::background::BackgroundGIF,
+    utils::{chicago::get_vhnfs_shotspotter_data, config::Config, mongo::Mongo, pool::RandomPool},
 };
 
 lazy_static! {
-    /// The default fallback GIF if selecting a random GIF from MongoDB fails.
+    /// The default fallback GIF if selecting a random GIF from MongoDB fails, or if there are no
+    /// enabled backgrounds.
     /// This GIF is Takumi doing a heel-toe downshift in one of the best cars ever made -- the
     /// Subaru WRX 🥴.
     static ref FALLBACK_GIF: &'static str = "https://imgur.com/FgJDNsx.gif";
-    /// The default fallback story if selecting a random story from MongoDB fails.
-    static ref FALLBACK_STORY: &'static str = "If you don’t like the road you’re walking, pave another one. Except for this one.";
 
     /// The API endpoint for the ShotSpotter Alerts data.
     static ref SHOTSPOTTER_ENDPOINT: &'static str = "https://data.cityofchicago.org/resource/3h7q-7mdb.json";
@@ -35,128 +29,99 @@ lazy_static! {
     static ref VHNFS_ENDPOINT: &'static str = "https://data.cityofchicago.org/resource/gumc-mgzr.json";
 }
 
-/// Create a cookie that stores the background GIF link. Returns `Some(Cookie)` if able to retrieve
-/// the `EnvironmentVariables::StaccDomain` value, otherwise returns `None`.
-fn create_background_cookie<'cookie>(background_gif: String) -> Option<Cookie<'cookie>> {
-    EnvironmentVariables::StaccDomain
-        .env_var()
-        .ok()
-        .map_or_else(
-            || None,
-            |domain| {
-                Some(
-                    Cookie::build("background", background_gif)
-                        .domain(domain)
-                        .expires(None)
-                        .path("/")
-                        .same_site(SameSite::Strict)
-                        .finish(),
-                )
-            },
-        )
+/// Create a cookie that stores the background GIF link.
+fn create_background_cookie<'cookie>(domain: String, background_gif: String) -> Cookie<'cookie> {
+    Cookie::build("background", background_gif)
+        .domain(domain)
+        .expires(None)
+        .path("/")
+        .same_site(SameSite::Strict)
+        .finish()
 }
 
-/// Get the background GIF by choosing a random link stored in the backgrounds collection.
-#[get("/background")]
-pub async fn get_background_gif(
-    mongo: Data<Mongo>,
-    request: HttpRequest,
-) -> Result<HttpResponse, StaccResponseError> {
-    if let Err(error) = middleware::log_visitor_data(&mongo, &request).await {
-        error!("{}", error);
+/// Get the background that is used when there are no backgrounds to choose from.
+pub fn fallback_background() -> BackgroundGIF {
+    BackgroundGIF {
+        background_id: "fallback".to_string(),
+        dominant_color: None,
+        enabled: true,
+        link: FALLBACK_GIF.to_string(),
+        source: Some("Initial D".to_string()),
+        source_link: None,
+        tags: Vec::new(),
+        title: Some("Takumi's heel-toe downshift".to_string()),
+        weight: 1,
     }
+}
 
-    // Delay execution for one second to allow the static GIF to render on the
-    // frontend. Shit's just too fast man.
-    time::sleep(Duration::from_secs(1)).await;
-
-    let document_count = mongo
-        .backgrounds_collection
-        .count_documents(doc! {}, None)
-        .await
-        .map_err(|error| StaccResponseError::MongoDBError {
-            error: error.to_string(),
-        })?;
-
-    let mut rng = rand::thread_rng();
-    let random_index = rng.gen_range(0..document_count);
-
-    let background_gif = mongo
-        .backgrounds_collection
-        .find_one(
-            doc! {},
-            FindOneOptions::builder().skip(random_index).build(),
-        )
-        .await
-        .ok()
-        .flatten()
-        .map_or_else(
-            || BackgroundGIF {
-                link: FALLBACK_GIF.to_string(),
-            },
-            |gif| gif,
-        );
+/// Get the link to a background on the media proxy.
+fn media_link(background_id: &str) -> String {
+    format!("/api/media/{background_id}")
+}
 
-    match create_background_cookie(background_gif.link.clone()) {
-        Some(cookie) => Ok(HttpResponse::Ok().cookie(cookie).json(background_gif)),
-        None => Ok(HttpResponse::Ok().json(background_gif)),
-    }
+/// Choose a random background, where each background's chance of being chosen is proportional to
+/// its weight. The reader's previous background is skipped if there is anything else to choose
+/// from. Returns `None` if there are no backgrounds with a weight.
+fn choose_background(
+    backgrounds: &[BackgroundGIF],
+    previous_link: Option<&str>,
+) -> Option<BackgroundGIF> {
+    let weights = |skip_previous: bool| {
+        WeightedIndex::new(backgrounds.iter().map(|background| {
+            if skip_previous
+                && previous_link == Some(media_link(&background.background_id).as_str())
+            {
+                0
+            } else {
+                background.weight
+            }
+        }))
+    };
+
+    let weights = weights(true).or_else(|_| weights(false)).ok()?;
+
+    Some(backgrounds[weights.sample(&mut rand::thread_rng())].clone())
 }
 
-/// Get the data that will be plotted on the Chicago map on the `violence` page.
-#[get("/chiraq")]
-pub async fn chiraq(
+/// Get the background GIF by choosing a random enabled background, weighted by each background's
+/// `weight`.
+#[get("/background")]
+pub async fn get_background_gif(
+    background_pool: Data<RandomPool<BackgroundGIF>>,
+    config: Data<Config>,
     mongo: Data<Mongo>,
     request: HttpRequest,
 ) -> Result<HttpResponse, StaccResponseError> {
-    if let Err(error) = middleware::log_visitor_data(&mongo, &request).await {
-        error!("{}", error);
-    }
-
-    get_vhnfs_shotspotter_data()
+    let previous_link = request.cookie("background");
+    let background_gif = background_pool
+        .documents(&mongo.backgrounds_collection)
         .await
-        .map(|chicago_map_data| HttpResponse::Ok().json(chicago_map_data))
-        .map_err(|error| StaccResponseError::ChicagoAPIError {
-            error: error.to_string(),
+        .map_err(|error| warn!(%error, "failed to load backgrounds"))
+        .ok()
+        .and_then(|backgrounds| {
+            choose_background(&backgrounds, previous_link.as_ref().map(Cookie::value))
         })
+        .unwrap_or_else(fallback_background);
+
+    // Visitors are sent to the media proxy rather than to wherever the GIF is hosted.
+    let background_gif = BackgroundGIF {
+        link: media_link(&background_gif.background_id),
+        ..background_gif
+    };
+
+    Ok(HttpResponse::Ok()
+        .cookie(create_background_cookie(
+            config.server.domain.clone(),
+            background_gif.link.clone(),
+        ))
+        .json(background_gif))
 }
 
-/// Get a 404 page story by choosing a random story stored in the stories collection.
-#[get("/story")]
-pub async fn story(
-    mongo: Data<Mongo>,
-    request: HttpRequest,
-) -> Result<HttpResponse, StaccResponseError> {
-    if let Err(error) = middleware::log_visitor_data(&mongo, &request).await {
-        error!("{}", error);
-    }
-
-    let document_count = mongo
-        .stories_collection
-        .count_documents(doc! {}, None)
-        .await
-        .map_err(|error| StaccResponseError::MongoDBError {
-            error: error.to_string(),
-        })?;
-
-    let mut rng = rand::thread_rng();
-    let random_index = rng.gen_range(0..document_count);
-
-    let story = mongo
-        .stories_collection
-        .find_one(
-            doc! {},
-            FindOneOptions::builder().skip(random_index).build(),
-        )
+/// Get the data that will be plotted on the Chicago map on the `violence` page.
+#[get("/chiraq")]
+pub async fn chiraq(config: Data<Config>) -> Result<HttpResponse, StaccResponseError> {
+    get_vhnfs_shotspotter_data(&config.socrata.app_token)
         .await
-        .ok()
-        .flatten()
-        .map_or_else(
-            || Story {
-                story: FALLBACK_STORY.to_string(),
-            },
-            |story| story,
-        );
-
-    Ok(HttpResponse::Ok().json(story))
+        .map(|chicago_map_data| HttpResponse::Ok().json(chicago_map_data))
+        .map_err(|error| StaccResponseError::upstream("Socrata", error))
 }
@@ -1,4 +1,15 @@
 //! Contains all routes for the API.
 
+pub mod backgrounds;
+pub mod comments;
+pub mod csp;
+pub mod health;
+pub mod media;
+pub mod metrics;
 pub mod misc;
 pub mod posts;
+pub mod revisions;
+pub mod stories;
+pub mod subscriptions;
+pub mod visitors;
+pub mod webmentions;
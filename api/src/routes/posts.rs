@@ -1,31 +1,40 @@
 //! Contains all routes pertaining to posts.
 
 use actix_web::{
-    get,
-    web::{Data, Path},
+    get, post, put,
+    web::{Data, Json, Path},
     HttpRequest, HttpResponse,
 };
+use chrono::Utc;
 use futures_util::stream::StreamExt;
-use log::error;
-use mongodb::bson::doc;
+use mongodb::{
+    bson::doc,
+    options::{FindOneAndUpdateOptions, FindOneOptions, ReturnDocument},
+};
 
 use crate::{
     errors::StaccResponseError,
-    middleware,
-    models::post::{AllPosts, PostData},
-    utils::mongo::Mongo,
+    middleware::visitors::VisitorLog,
+    models::{
+        post::{AllPosts, NewPost, PostData},
+        revision::{PostEdit, PostRevision},
+    },
+    utils::{
+        auth,
+        config::Config,
+        mailer::{self, Mailer},
+        mongo::{self, Mongo},
+        tasks::BackgroundTasks,
+        webmention::{self, WebmentionClient},
+    },
 };
 
+/// How many times to try recording a revision before giving up on edits made at the same time.
+const REVISION_ATTEMPTS: usize = 5;
+
 /// Get all posts from MongoDB.
 #[get("/posts")]
-pub async fn get_all_posts(
-    mongo: Data<Mongo>,
-    request: HttpRequest,
-) -> Result<HttpResponse, StaccResponseError> {
-    if let Err(error) = middleware::log_visitor_data(&mongo, &request).await {
-        error!("{}", error);
-    }
-
+pub async fn get_all_posts(mongo: Data<Mongo>) -> Result<HttpResponse, StaccResponseError> {
     let mut posts: Vec<PostData> = Vec::new();
 
     match mongo.posts_collection.find(doc! {}, None).await {
@@ -35,19 +44,11 @@ pub async fn get_all_posts(
                     Ok(post) => {
                         posts.push(post);
                     }
-                    Err(error) => {
-                        return Err(StaccResponseError::MongoDBError {
-                            error: error.to_string(),
-                        })
-                    }
+                    Err(error) => return Err(StaccResponseError::from(error)),
                 }
             }
         }
-        Err(error) => {
-            return Err(StaccResponseError::MongoDBError {
-                error: error.to_string(),
-            })
-        }
+        Err(error) => return Err(StaccResponseError::from(error)),
     }
 
     Ok(HttpResponse::Ok().json(AllPosts { posts }))
@@ -59,11 +60,8 @@ pub async fn get_single_post(
     mongo: Data<Mongo>,
     post_id: Path<String>,
     request: HttpRequest,
+    visitor_log: Data<VisitorLog>,
 ) -> Result<HttpResponse, StaccResponseError> {
-    if let Err(error) = middleware::log_visitor_data(&mongo, &request).await {
-        error!("{}", error);
-    }
-
     let post_id = post_id.into_inner();
 
     let find_result = mongo
@@ -77,17 +75,201 @@ pub async fn get_single_post(
 
     match find_result {
         Ok(Some(post)) => {
-            if let Err(error) = middleware::log_post_view(&mongo, &post_id, &request).await {
-                error!("{}", error);
-            }
+            visitor_log.post_view(&post_id, &request);
 
             Ok(HttpResponse::Ok().json(post))
         }
-        Ok(None) => Err(StaccResponseError::MongoDBSearchError {
+        Ok(None) => Err(StaccResponseError::NotFound {
             error: "Post not found!".to_string(),
         }),
-        Err(error) => Err(StaccResponseError::MongoDBError {
-            error: error.to_string(),
-        }),
+        Err(error) => Err(StaccResponseError::from(error)),
+    }
+}
+
+/// Publish a new post. Once the post has been published, Webmentions are sent to every link in the
+/// post's body and every confirmed subscriber is emailed.
+///
+/// This is an admin-only endpoint.
+#[post("/posts")]
+pub async fn publish_post(
+    config: Data<Config>,
+    mailer: Data<Mailer>,
+    mongo: Data<Mongo>,
+    new_post: Json<NewPost>,
+    request: HttpRequest,
+    tasks: Data<BackgroundTasks>,
+    webmention_client: Data<WebmentionClient>,
+) -> Result<HttpResponse, StaccResponseError> {
+    auth::authorize_admin(&config, &request)?;
+
+    let new_post = new_post.into_inner();
+
+    if new_post.post_id.is_empty() || new_post.post_id.contains('/') {
+        return Err(StaccResponseError::BadInput {
+            error: "Post IDs must be non-empty and may not contain slashes.".to_string(),
+        });
+    }
+
+    if mongo
+        .posts_collection
+        .find_one(doc! { "post_id": &new_post.post_id }, None)
+        .await
+        .map_err(StaccResponseError::from)?
+        .is_some()
+    {
+        return Err(StaccResponseError::BadInput {
+            error: format!("A post with the ID {} already exists.", new_post.post_id),
+        });
     }
+
+    let post = PostData {
+        body: new_post.body,
+        created: Utc::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        edited: None,
+        post_id: new_post.post_id,
+        preview_image_link: new_post.preview_image_link,
+        preview_summary: new_post.preview_summary,
+        title: new_post.title,
+        topic: new_post.topic,
+        view_count: 0,
+    };
+
+    mongo
+        .posts_collection
+        .insert_one(&post, None)
+        .await
+        .map_err(StaccResponseError::from)?;
+
+    let source = webmention::post_url(&config, &post.post_id);
+    let body = post.body.clone();
+    tasks.spawn(async move {
+        webmention::send_webmentions(&webmention_client, &source, &body).await;
+    });
+
+    let response = HttpResponse::Created().json(&post);
+
+    tasks.spawn(mailer::send_newsletter(config, mongo, mailer, post));
+
+    Ok(response)
+}
+
+/// Edit a post's title and/or body. Every change is recorded in the revisions collection. The
+/// original post is recorded as revision `0` the first time it is edited.
+///
+/// This is an admin-only endpoint.
+#[put("/post/{post_id}")]
+pub async fn edit_post(
+    config: Data<Config>,
+    mongo: Data<Mongo>,
+    post_id: Path<String>,
+    post_edit: Json<PostEdit>,
+    request: HttpRequest,
+) -> Result<HttpResponse, StaccResponseError> {
+    auth::authorize_admin(&config, &request)?;
+
+    let post_id = post_id.into_inner();
+    let post_edit = post_edit.into_inner();
+
+    let post = mongo
+        .posts_collection
+        .find_one(doc! { "post_id": &post_id }, None)
+        .await
+        .map_err(StaccResponseError::from)?
+        .ok_or_else(|| StaccResponseError::NotFound {
+            error: "Post not found!".to_string(),
+        })?;
+
+    let title = post_edit.title.unwrap_or_else(|| post.title.clone());
+    let body = post_edit.body.unwrap_or_else(|| post.body.clone());
+
+    if title == post.title && body == post.body {
+        return Ok(HttpResponse::Ok().json(post));
+    }
+
+    let edited = Utc::now().format("%Y-%m-%d %H:%M:%S").to_string();
+
+    record_revision(&mongo, &post, &title, &body, &edited).await?;
+
+    mongo
+        .posts_collection
+        .find_one_and_update(
+            doc! { "post_id": &post_id },
+            doc! { "$set": { "body": body, "edited": edited, "title": title } },
+            FindOneAndUpdateOptions::builder()
+                .return_document(ReturnDocument::After)
+                .build(),
+        )
+        .await
+        .map_err(StaccResponseError::from)?
+        .map_or_else(
+            || {
+                Err(StaccResponseError::NotFound {
+                    error: "Post not found!".to_string(),
+                })
+            },
+            |post| Ok(HttpResponse::Ok().json(post)),
+        )
+}
+
+/// Record an edit as the post's next revision, recording the original post as revision `0` first
+/// if it never has been. The next number is found by reading the latest revision, so an edit made
+/// at the same time may take it first. The unique index on each post's revision numbers rejects
+/// the second insert, and the number is read again.
+async fn record_revision(
+    mongo: &Mongo,
+    post: &PostData,
+    title: &str,
+    body: &str,
+    edited: &str,
+) -> Result<(), StaccResponseError> {
+    for _ in 0..REVISION_ATTEMPTS {
+        let latest_revision = mongo
+            .revisions_collection
+            .find_one(
+                doc! { "post_id": &post.post_id },
+                FindOneOptions::builder()
+                    .sort(doc! { "revision_number": -1 })
+                    .build(),
+            )
+            .await
+            .map_err(StaccResponseError::from)?;
+
+        let revision_number = match latest_revision {
+            Some(revision) => revision.revision_number + 1,
+            None => {
+                // The original post has never been recorded, so store it before the first edit.
+                let original = PostRevision {
+                    body: post.body.clone(),
+                    created: post.created.clone(),
+                    post_id: post.post_id.clone(),
+                    revision_number: 0,
+                    title: post.title.clone(),
+                };
+
+                match mongo.revisions_collection.insert_one(original, None).await {
+                    Ok(_) => 1,
+                    Err(error) if mongo::is_duplicate_key(&error) => continue,
+                    Err(error) => return Err(StaccResponseError::from(error)),
+                }
+            }
+        };
+
+        let revision = PostRevision {
+            body: body.to_string(),
+            created: edited.to_string(),
+            post_id: post.post_id.clone(),
+            revision_number,
+            title: title.to_string(),
+        };
+
+        match mongo.revisions_collection.insert_one(revision, None).await {
+            Ok(_) => return Ok(()),
+            Err(error) if mongo::is_duplicate_key(&error) => continue,
+            Err(error) => return Err(StaccResponseError::from(error)),
+        }
+    }
+
+    Err(StaccResponseError::DatabaseError {
+        error: "The post is being edited by another request. Try again.".to_string(),
+    })
 }
@@ -0,0 +1,80 @@
+//! Contains all routes pertaining to post revisions.
+
+use actix_web::{
+    get,
+    web::{Data, Path, Query},
+    HttpResponse,
+};
+use futures_util::stream::StreamExt;
+use mongodb::{bson::doc, options::FindOptions};
+
+use crate::{
+    errors::StaccResponseError,
+    models::revision::{DiffQuery, PostRevision, PostRevisions, RevisionDiff},
+    utils::{diff, mongo::Mongo},
+};
+
+/// Get all revisions for a single post, ordered from oldest to newest.
+#[get("/post/{post_id}/revisions")]
+pub async fn get_post_revisions(
+    mongo: Data<Mongo>,
+    post_id: Path<String>,
+) -> Result<HttpResponse, StaccResponseError> {
+    let mut revisions: Vec<PostRevision> = Vec::new();
+
+    let mut cursor = mongo
+        .revisions_collection
+        .find(
+            doc! { "post_id": post_id.into_inner() },
+            FindOptions::builder()
+                .sort(doc! { "revision_number": 1 })
+                .build(),
+        )
+        .await
+        .map_err(StaccResponseError::from)?;
+
+    while let Some(document) = cursor.next().await {
+        revisions.push(document.map_err(StaccResponseError::from)?);
+    }
+
+    Ok(HttpResponse::Ok().json(PostRevisions { revisions }))
+}
+
+/// Get the line diff between two revisions of a single post.
+#[get("/post/{post_id}/revisions/diff")]
+pub async fn get_revision_diff(
+    mongo: Data<Mongo>,
+    post_id: Path<String>,
+    diff_query: Query<DiffQuery>,
+) -> Result<HttpResponse, StaccResponseError> {
+    let post_id = post_id.into_inner();
+
+    let old_revision = find_revision(&mongo, &post_id, diff_query.from).await?;
+    let new_revision = find_revision(&mongo, &post_id, diff_query.to).await?;
+
+    Ok(HttpResponse::Ok().json(RevisionDiff {
+        body: diff::line_diff(&old_revision.body, &new_revision.body),
+        from: old_revision.revision_number,
+        title: diff::line_diff(&old_revision.title, &new_revision.title),
+        to: new_revision.revision_number,
+    }))
+}
+
+/// Find a single revision of a post by its revision number.
+async fn find_revision(
+    mongo: &Data<Mongo>,
+    post_id: &str,
+    revision_number: i32,
+) -> Result<PostRevision, StaccResponseError> {
+    mongo
+        .revisions_collection
+        .find_one(
+            doc! { "post_id": post_id, "revision_number": revision_number },
+            None,
+        )
+        .await
+        .map_err(StaccResponseError::from)?
+        .ok_or_else(|| StaccResponseError::NotFound {
+            error: format!("Revision {revision_number} not found!"),
+        })
+}
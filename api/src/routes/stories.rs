@@ -0,0 +1,278 @@
+//! Contains all routes pertaining to 404 page stories.
+
+use actix_web::{
+    get, post, put,
+    web::{Data, Json, Path, Query},
+    HttpRequest, HttpResponse,
+};
+use chrono::Utc;
+use futures_util::stream::StreamExt;
+use lazy_static::lazy_static;
+use mongodb::{
+    bson::{doc, Document},
+    options::{FindOneAndUpdateOptions, FindOptions, ReturnDocument},
+};
+use rand::seq::SliceRandom;
+use uuid::Uuid;
+
+use crate::{
+    errors::StaccResponseError,
+    models::{
+        moderation::ModerationStatus,
+        story::{AllStories, NewStory, Story, StoryPage, StoryPageQuery},
+    },
+    utils::{
+        auth,
+        config::Config,
+        moderation::{self, Submission, SubmissionKind},
+        mongo::Mongo,
+        pool::RandomPool,
+    },
+};
+
+lazy_static! {
+    /// The default fallback story if there are no stories in MongoDB.
+    static ref FALLBACK_STORY: &'static str = "If you don’t like the road you’re walking, pave another one. Except for this one.";
+    /// The number of stories on each page of the story archive.
+    static ref STORIES_PER_PAGE: u64 = 10;
+}
+
+/// The filter matching every story that may be shown. Stories stored before story submissions
+/// existed have no status, and are approved.
+pub fn approved_stories() -> Document {
+    doc! { "status": { "$nin": ["pending", "rejected"] } }
+}
+
+/// The story shown when there are no stories in MongoDB.
+fn fallback_story() -> Story {
+    Story {
+        author: None,
+        created: None,
+        status: ModerationStatus::Approved,
+        story: FALLBACK_STORY.to_string(),
+        story_id: "fallback".to_string(),
+    }
+}
+
+/// Get a 404 page story by choosing a random approved story stored in the stories collection.
+#[get("/story")]
+pub async fn random_story(
+    mongo: Data<Mongo>,
+    story_pool: Data<RandomPool<Story>>,
+) -> Result<HttpResponse, StaccResponseError> {
+    let story = story_pool
+        .documents(&mongo.stories_collection)
+        .await
+        .map_err(StaccResponseError::from)?
+        .choose(&mut rand::thread_rng())
+        .cloned()
+        .unwrap_or_else(fallback_story);
+
+    Ok(HttpResponse::Ok().json(story))
+}
+
+/// Get a single approved story by its ID. This is what a story's share link loads.
+#[get("/story/{story_id}")]
+pub async fn get_story(
+    mongo: Data<Mongo>,
+    story_id: Path<String>,
+) -> Result<HttpResponse, StaccResponseError> {
+    let story_id = story_id.into_inner();
+    if story_id == "fallback" {
+        return Ok(HttpResponse::Ok().json(fallback_story()));
+    }
+
+    let mut filter = approved_stories();
+    filter.insert("story_id", story_id);
+
+    mongo
+        .stories_collection
+        .find_one(filter, None)
+        .await
+        .map_err(StaccResponseError::from)?
+        .map_or_else(
+            || {
+                Err(StaccResponseError::NotFound {
+                    error: "Story not found!".to_string(),
+                })
+            },
+            |story| Ok(HttpResponse::Ok().json(story)),
+        )
+}
+
+/// Get a page of the story archive, which contains every approved story from newest to oldest.
+#[get("/stories")]
+pub async fn get_stories(
+    mongo: Data<Mongo>,
+    query: Query<StoryPageQuery>,
+) -> Result<HttpResponse, StaccResponseError> {
+    let page = query.page.unwrap_or(1);
+    if page == 0 {
+        return Err(StaccResponseError::BadInput {
+            error: "Pages start at 1.".to_string(),
+        });
+    }
+
+    let story_count = mongo
+        .stories_collection
+        .count_documents(approved_stories(), None)
+        .await
+        .map_err(StaccResponseError::from)?;
+    let total_pages = story_count.div_ceil(*STORIES_PER_PAGE).max(1);
+
+    let mut stories: Vec<Story> = Vec::new();
+
+    // Sorting by `_id` keeps stories added by hand, which have no created timestamp, in the order
+    // they were added.
+    let mut cursor = mongo
+        .stories_collection
+        .find(
+            approved_stories(),
+            FindOptions::builder()
+                .limit(*STORIES_PER_PAGE as i64)
+                .skip((page - 1).saturating_mul(*STORIES_PER_PAGE))
+                .sort(doc! { "_id": -1 })
+                .build(),
+        )
+        .await
+        .map_err(StaccResponseError::from)?;
+
+    while let Some(document) = cursor.next().await {
+        stories.push(document.map_err(StaccResponseError::from)?);
+    }
+
+    Ok(HttpResponse::Ok().json(StoryPage {
+        page,
+        stories,
+        total_pages,
+    }))
+}
+
+/// Submit a new story for the 404 page. The story is placed in the moderation queue and will not
+/// be shown until it is approved.
+#[post("/stories")]
+pub async fn submit_story(
+    mongo: Data<Mongo>,
+    new_story: Json<NewStory>,
+    request: HttpRequest,
+) -> Result<HttpResponse, StaccResponseError> {
+    let new_story = new_story.into_inner();
+
+    let Some(Submission {
+        author,
+        body: story,
+    }) = moderation::check_submission(
+        SubmissionKind::Story,
+        &request,
+        &new_story.author,
+        &new_story.story,
+        &new_story.website,
+    )?
+    else {
+        return Ok(moderation::submitted(SubmissionKind::Story));
+    };
+
+    mongo
+        .stories_collection
+        .insert_one(
+            Story {
+                author: Some(author),
+                created: Some(Utc::now().format("%Y-%m-%d %H:%M:%S").to_string()),
+                status: ModerationStatus::Pending,
+                story,
+                story_id: Uuid::new_v4().to_string(),
+            },
+            None,
+        )
+        .await
+        .map_err(StaccResponseError::from)?;
+
+    Ok(moderation::submitted(SubmissionKind::Story))
+}
+
+/// Get all stories waiting in the moderation queue, ordered from oldest to newest.
+///
+/// This is an admin-only endpoint.
+#[get("/stories")]
+pub async fn get_story_queue(
+    config: Data<Config>,
+    mongo: Data<Mongo>,
+    request: HttpRequest,
+) -> Result<HttpResponse, StaccResponseError> {
+    auth::authorize_admin(&config, &request)?;
+
+    let mut stories: Vec<Story> = Vec::new();
+
+    let mut cursor = mongo
+        .stories_collection
+        .find(
+            doc! { "status": ModerationStatus::Pending.as_str() },
+            FindOptions::builder().sort(doc! { "created": 1 }).build(),
+        )
+        .await
+        .map_err(StaccResponseError::from)?;
+
+    while let Some(document) = cursor.next().await {
+        stories.push(document.map_err(StaccResponseError::from)?);
+    }
+
+    Ok(HttpResponse::Ok().json(AllStories { stories }))
+}
+
+/// Approve a story, adding it to the stories shown on the 404 page.
+///
+/// This is an admin-only endpoint.
+#[put("/stories/{story_id}/approve")]
+pub async fn approve_story(
+    config: Data<Config>,
+    mongo: Data<Mongo>,
+    request: HttpRequest,
+    story_id: Path<String>,
+    story_pool: Data<RandomPool<Story>>,
+) -> Result<HttpResponse, StaccResponseError> {
+    auth::authorize_admin(&config, &request)?;
+
+    moderate_story(&mongo, ModerationStatus::Approved, &story_id, &story_pool).await
+}
+
+/// Reject a story so that it is never shown.
+///
+/// This is an admin-only endpoint.
+#[put("/stories/{story_id}/reject")]
+pub async fn reject_story(
+    config: Data<Config>,
+    mongo: Data<Mongo>,
+    request: HttpRequest,
+    story_id: Path<String>,
+    story_pool: Data<RandomPool<Story>>,
+) -> Result<HttpResponse, StaccResponseError> {
+    auth::authorize_admin(&config, &request)?;
+
+    moderate_story(&mongo, ModerationStatus::Rejected, &story_id, &story_pool).await
+}
+
+/// Set the moderation status of a story and return the updated story.
+async fn moderate_story(
+    mongo: &Data<Mongo>,
+    status: ModerationStatus,
+    story_id: &str,
+    story_pool: &RandomPool<Story>,
+) -> Result<HttpResponse, StaccResponseError> {
+    let story = mongo
+        .stories_collection
+        .find_one_and_update(
+            doc! { "story_id": story_id },
+            doc! { "$set": { "status": status.as_str() } },
+            FindOneAndUpdateOptions::builder()
+                .return_document(ReturnDocument::After)
+                .build(),
+        )
+        .await
+        .map_err(StaccResponseError::from)?
+        .ok_or_else(|| StaccResponseError::NotFound {
+            error: "Story not found!".to_string(),
+        })?;
+    story_pool.invalidate();
+
+    Ok(HttpResponse::Ok().json(story))
+}
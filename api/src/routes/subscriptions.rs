@@ -0,0 +1,150 @@
+//! Contains all routes pertaining to newsletter subscriptions.
+
+use actix_web::{
+    get, post,
+    web::{Data, Json, Path},
+    HttpResponse,
+};
+use chrono::Utc;
+use mongodb::{bson::doc, options::UpdateOptions};
+use tracing::error;
+use uuid::Uuid;
+
+use crate::{
+    errors::StaccResponseError,
+    models::{
+        data::Response,
+        subscriber::{NewSubscriber, Unsubscribe},
+    },
+    utils::{config::Config, mailer::Mailer, mongo::Mongo, signature, tasks::BackgroundTasks},
+};
+
+/// Subscribe to new posts. A confirmation email is sent, and the subscription does not take effect
+/// until the link within it is visited. The response is identical whether or not the email
+/// address is already subscribed.
+#[post("/subscribe")]
+pub async fn subscribe(
+    mailer: Data<Mailer>,
+    mongo: Data<Mongo>,
+    new_subscriber: Json<NewSubscriber>,
+    tasks: Data<BackgroundTasks>,
+) -> Result<HttpResponse, StaccResponseError> {
+    let email = new_subscriber.into_inner().email.trim().to_lowercase();
+    if !is_valid_email(&email) {
+        return Err(StaccResponseError::BadInput {
+            error: "That does not look like an email address.".to_string(),
+        });
+    }
+
+    let check_inbox = Response {
+        message: "Check your inbox to confirm your subscription.".to_string(),
+        status_code: 202,
+    };
+
+    let existing_subscriber = mongo
+        .subscribers_collection
+        .find_one(doc! { "email": &email }, None)
+        .await
+        .map_err(StaccResponseError::from)?;
+    if existing_subscriber.is_some_and(|subscriber| subscriber.confirmed) {
+        return Ok(HttpResponse::Accepted().json(check_inbox));
+    }
+
+    // Unconfirmed subscribers receive a fresh token so that old confirmation links stop working.
+    let confirmation_token = Uuid::new_v4().simple().to_string();
+    mongo
+        .subscribers_collection
+        .update_one(
+            doc! { "email": &email },
+            doc! {
+                "$set": {
+                    "confirmation_token": &confirmation_token,
+                    "confirmed": false,
+                    "subscribed": Utc::now().format("%Y-%m-%d %H:%M:%S").to_string()
+                }
+            },
+            UpdateOptions::builder().upsert(true).build(),
+        )
+        .await
+        .map_err(StaccResponseError::from)?;
+
+    tasks.spawn(async move {
+        if let Err(error) = mailer.send_confirmation(&email, &confirmation_token).await {
+            error!(%error, "failed to send a confirmation email");
+        }
+    });
+
+    Ok(HttpResponse::Accepted().json(check_inbox))
+}
+
+/// Confirm a subscription with the token from the confirmation email.
+#[get("/confirm/{token}")]
+pub async fn confirm_subscription(
+    mongo: Data<Mongo>,
+    token: Path<String>,
+) -> Result<HttpResponse, StaccResponseError> {
+    mongo
+        .subscribers_collection
+        .find_one_and_update(
+            doc! { "confirmation_token": token.into_inner() },
+            doc! { "$set": { "confirmation_token": null, "confirmed": true } },
+            None,
+        )
+        .await
+        .map_err(StaccResponseError::from)?
+        .map_or_else(
+            || {
+                Err(StaccResponseError::NotFound {
+                    error: "This confirmation link is invalid or has already been used."
+                        .to_string(),
+                })
+            },
+            |_| {
+                Ok(HttpResponse::Ok().json(Response {
+                    message: "Subscription confirmed.".to_string(),
+                    status_code: 200,
+                }))
+            },
+        )
+}
+
+/// Unsubscribe from new posts. The signature must match the one in the unsubscribe link that was
+/// included in the newsletter email.
+#[post("/unsubscribe")]
+pub async fn unsubscribe(
+    config: Data<Config>,
+    mongo: Data<Mongo>,
+    unsubscribe: Json<Unsubscribe>,
+) -> Result<HttpResponse, StaccResponseError> {
+    let Unsubscribe { email, signature } = unsubscribe.into_inner();
+
+    let is_valid = signature::verify(&config.server.subscription_secret, &email, &signature)
+        .map_err(|error| StaccResponseError::BadInput {
+            error: error.to_string(),
+        })?;
+    if !is_valid {
+        return Err(StaccResponseError::BadInput {
+            error: "This unsubscribe link is invalid.".to_string(),
+        });
+    }
+
+    mongo
+        .subscribers_collection
+        .delete_one(doc! { "email": &email }, None)
+        .await
+        .map_err(StaccResponseError::from)?;
+
+    Ok(HttpResponse::Ok().json(Response {
+        message: "You have been unsubscribed.".to_string(),
+        status_code: 200,
+    }))
+}
+
+/// A loose sanity check for email addresses. The confirmation email is the real test.
+fn is_valid_email(email: &str) -> bool {
+    email.len() <= 254
+        && !email.chars().any(char::is_whitespace)
+        && email
+            .split_once('@')
+            .is_some_and(|(local, domain)| !local.is_empty() && domain.contains('.'))
+}
@@ -0,0 +1,125 @@
+//! Contains the visitor beacon route.
+
+use std::collections::BTreeSet;
+
+use actix_web::{
+    post,
+    web::{Data, Json},
+    HttpRequest, HttpResponse,
+};
+use mongodb::bson::doc;
+
+use crate::{
+    errors::StaccResponseError,
+    middleware::visitors::VisitorLog,
+    models::visitor::VisitBeacon,
+    utils::{config::Config, mongo::Mongo},
+};
+
+/// The most post IDs a single beacon may carry.
+const MAX_BEACON_POSTS: usize = 50;
+
+/// The most visits a single beacon counts. Beacons are sent as soon as a reader is back online, so
+/// they rarely carry more than a few.
+const MAX_BEACON_VISITS: u32 = 10;
+
+/// Record the visits and post views that the frontend's service worker served from its cache while
+/// a reader was offline. Those requests never reached the API, so the `LogVisitors` middleware and
+/// the post handlers never saw them. Visits are only recorded if the middleware would have logged
+/// them, i.e. if visitor logging is enabled. Each post counts as viewed at most once per beacon.
+#[post("/visits")]
+pub async fn receive_visit_beacon(
+    beacon: Json<VisitBeacon>,
+    config: Data<Config>,
+    mongo: Data<Mongo>,
+    request: HttpRequest,
+    visitor_log: Data<VisitorLog>,
+) -> Result<HttpResponse, StaccResponseError> {
+    let (post_ids, visits) = beacon_events(beacon.into_inner())?;
+
+    if config.visitors.enabled {
+        for _ in 0..visits {
+            visitor_log.visit(&request);
+        }
+    }
+
+    for post_id in post_ids {
+        let result = mongo
+            .posts_collection
+            .update_one(
+                doc! { "post_id": &post_id },
+                doc! { "$inc": { "view_count": 1 } },
+                None,
+            )
+            .await
+            .map_err(StaccResponseError::from)?;
+
+        // Posts that were deleted since they were cached are skipped, so that clients cannot add
+        // arbitrary post IDs to a visitor's `visited_posts`.
+        if result.matched_count > 0 {
+            visitor_log.post_view(&post_id, &request);
+        }
+    }
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Find the posts a beacon counts as viewed, and the number of visits it counts. Repeated post IDs
+/// are only counted once, and visits past `MAX_BEACON_VISITS` are not counted.
+fn beacon_events(beacon: VisitBeacon) -> Result<(BTreeSet<String>, u32), StaccResponseError> {
+    let VisitBeacon { post_ids, visits } = beacon;
+
+    if post_ids.len() > MAX_BEACON_POSTS {
+        return Err(StaccResponseError::BadInput {
+            error: format!("A beacon may not carry more than {MAX_BEACON_POSTS} post IDs."),
+        });
+    }
+
+    Ok((
+        post_ids.into_iter().collect(),
+        visits.min(MAX_BEACON_VISITS),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Build a beacon carrying the given post IDs and visits.
+    fn beacon(post_ids: &[&str], visits: u32) -> VisitBeacon {
+        VisitBeacon {
+            post_ids: post_ids.iter().map(|post_id| post_id.to_string()).collect(),
+            visits,
+        }
+    }
+
+    #[test]
+    fn repeated_post_ids_count_once() {
+        let (post_ids, visits) = beacon_events(beacon(&["a", "b", "a", "a", "b"], 5)).unwrap();
+
+        assert_eq!(post_ids.into_iter().collect::<Vec<_>>(), ["a", "b"]);
+        assert_eq!(visits, 5);
+    }
+
+    #[test]
+    fn a_post_repeated_up_to_the_limit_counts_once() {
+        let (post_ids, _) = beacon_events(beacon(&["a"; MAX_BEACON_POSTS], 0)).unwrap();
+
+        assert_eq!(post_ids.len(), 1);
+    }
+
+    #[test]
+    fn visits_are_capped() {
+        let (_, visits) = beacon_events(beacon(&[], u32::MAX)).unwrap();
+
+        assert_eq!(visits, MAX_BEACON_VISITS);
+    }
+
+    #[test]
+    fn too_many_post_ids_are_rejected() {
+        assert!(matches!(
+            beacon_events(beacon(&["a"; MAX_BEACON_POSTS + 1], 0)),
+            Err(StaccResponseError::BadInput { .. })
+        ));
+    }
+}
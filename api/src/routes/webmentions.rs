@@ -0,0 +1,182 @@
+//! Contains all routes pertaining to Webmentions.
+
+use actix_web::{
+    get, post,
+    web::{Data, Form, Path},
+    HttpResponse,
+};
+use chrono::Utc;
+use futures_util::stream::StreamExt;
+use mongodb::{
+    bson::doc,
+    options::{FindOptions, ReplaceOptions},
+};
+use reqwest::Url;
+use tracing::{error, info};
+
+use crate::{
+    errors::StaccResponseError,
+    models::{
+        data::Response,
+        webmention::{AllWebmentions, IncomingWebmention, Webmention},
+    },
+    utils::{
+        config::Config,
+        mongo::Mongo,
+        tasks::BackgroundTasks,
+        webmention::{self, WebmentionClient},
+    },
+};
+
+/// Receive a Webmention. The source page is verified asynchronously, so this only checks that the
+/// target is a post on this site before accepting the request.
+#[post("/webmention")]
+pub async fn receive_webmention(
+    config: Data<Config>,
+    incoming_webmention: Form<IncomingWebmention>,
+    mongo: Data<Mongo>,
+    tasks: Data<BackgroundTasks>,
+    webmention_client: Data<WebmentionClient>,
+) -> Result<HttpResponse, StaccResponseError> {
+    let IncomingWebmention { source, target } = incoming_webmention.into_inner();
+
+    let source_url = parse_http_url(&source)?;
+    let target_url = parse_http_url(&target)?;
+    if source_url == target_url {
+        return Err(StaccResponseError::BadInput {
+            error: "The source and target must be different URLs.".to_string(),
+        });
+    }
+
+    let post_id = target_url
+        .path()
+        .trim_end_matches('/')
+        .strip_prefix("/blog/post/")
+        .filter(|post_id| !post_id.is_empty() && !post_id.contains('/'))
+        .map(|post_id| post_id.to_string())
+        .ok_or_else(|| StaccResponseError::BadInput {
+            error: "The target is not a post on this site.".to_string(),
+        })?;
+
+    // Only accept targets that point at this site's URL for the post.
+    if Url::parse(&webmention::post_url(&config, &post_id))
+        .ok()
+        .map(|post_url| post_url.host_str() != target_url.host_str())
+        .unwrap_or(true)
+    {
+        return Err(StaccResponseError::BadInput {
+            error: "The target is not a post on this site.".to_string(),
+        });
+    }
+
+    if mongo
+        .posts_collection
+        .find_one(doc! { "post_id": &post_id }, None)
+        .await
+        .map_err(StaccResponseError::from)?
+        .is_none()
+    {
+        return Err(StaccResponseError::NotFound {
+            error: "Post not found!".to_string(),
+        });
+    }
+
+    tasks.spawn(verify_webmention(
+        mongo,
+        webmention_client,
+        post_id,
+        source,
+        target,
+    ));
+
+    Ok(HttpResponse::Accepted().json(Response {
+        message: "Webmention accepted for verification.".to_string(),
+        status_code: 202,
+    }))
+}
+
+/// Get all verified Webmentions for a single post, ordered from oldest to newest.
+#[get("/post/{post_id}/webmentions")]
+pub async fn get_post_webmentions(
+    mongo: Data<Mongo>,
+    post_id: Path<String>,
+) -> Result<HttpResponse, StaccResponseError> {
+    let mut webmentions: Vec<Webmention> = Vec::new();
+
+    let mut cursor = mongo
+        .webmentions_collection
+        .find(
+            doc! { "post_id": post_id.into_inner() },
+            FindOptions::builder().sort(doc! { "verified": 1 }).build(),
+        )
+        .await
+        .map_err(StaccResponseError::from)?;
+
+    while let Some(document) = cursor.next().await {
+        webmentions.push(document.map_err(StaccResponseError::from)?);
+    }
+
+    Ok(HttpResponse::Ok().json(AllWebmentions { webmentions }))
+}
+
+/// Parse a URL and make sure it uses the HTTP(S) scheme.
+fn parse_http_url(url: &str) -> Result<Url, StaccResponseError> {
+    Url::parse(url)
+        .ok()
+        .filter(|url| matches!(url.scheme(), "http" | "https"))
+        .ok_or_else(|| StaccResponseError::BadInput {
+            error: format!("{url} is not a valid HTTP(S) URL."),
+        })
+}
+
+/// Verify that the source links to the target and store the mention. Mentions whose source no
+/// longer links to the target are removed.
+async fn verify_webmention(
+    mongo: Data<Mongo>,
+    webmention_client: Data<WebmentionClient>,
+    post_id: String,
+    source: String,
+    target: String,
+) {
+    let filter = doc! { "source": &source, "target": &target };
+
+    match webmention_client.verify(&source, &target).await {
+        Ok(Some(verified_source)) => {
+            info!(source, "verified webmention");
+
+            let webmention = Webmention {
+                author_name: verified_source.author_name,
+                content: verified_source.content,
+                mention_type: verified_source.mention_type,
+                post_id,
+                source: source.clone(),
+                source_title: verified_source.source_title,
+                target,
+                verified: Utc::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            };
+
+            if let Err(error) = mongo
+                .webmentions_collection
+                .replace_one(
+                    filter,
+                    webmention,
+                    ReplaceOptions::builder().upsert(true).build(),
+                )
+                .await
+            {
+                error!(source, %error, "failed to store webmention");
+            }
+        }
+        Ok(None) => {
+            info!(
+                source,
+                target, "source does not link to target, removing any existing webmention"
+            );
+
+            if let Err(error) = mongo.webmentions_collection.delete_one(filter, None).await {
+                error!(source, %error, "failed to remove webmention");
+            }
+        }
+        Err(error) => error!(source, %error, "failed to verify webmention"),
+    }
+}
@@ -0,0 +1,43 @@
+//! Contains utilities for authorizing requests made to admin-only endpoints.
+
+use actix_web::{http::header, HttpRequest};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::errors::StaccResponseError;
+
+use super::config::Config;
+
+/// Verify that the request carries the admin token in its `Authorization: Bearer <TOKEN>` header.
+pub fn authorize_admin(config: &Config, request: &HttpRequest) -> Result<(), StaccResponseError> {
+    let admin_token = &config.server.admin_token;
+
+    let provided_token = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+
+    match provided_token {
+        Some(token) if !admin_token.is_empty() && is_admin_token(admin_token, token) => Ok(()),
+        _ => Err(StaccResponseError::Unauthorized {
+            error: "Invalid or missing admin token.".to_string(),
+        }),
+    }
+}
+
+/// Check whether a token is the admin token without leaking how much of it matched. Both are
+/// run through HMAC-SHA256 keyed with the admin token, and the results are compared in constant
+/// time with `verify_slice`, so neither the first differing byte nor the token's length shows in
+/// how long the check takes.
+fn is_admin_token(admin_token: &str, token: &str) -> bool {
+    let Ok(mut mac) = Hmac::<Sha256>::new_from_slice(admin_token.as_bytes()) else {
+        return false;
+    };
+    let mut expected = mac.clone();
+
+    expected.update(admin_token.as_bytes());
+    mac.update(token.as_bytes());
+
+    mac.verify_slice(&expected.finalize().into_bytes()).is_ok()
+}
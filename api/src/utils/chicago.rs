@@ -4,9 +4,7 @@ use lazy_static::lazy_static;
 use reqwest::Client;
 use serde_json::Value;
 
-use crate::{errors::StaccError, models::data::ChicagoMapData};
-
-use super::environment::EnvironmentVariables;
+use crate::{errors::StaccError, middleware::metrics, models::data::ChicagoMapData};
 
 lazy_static! {
     /// A `reqwest` `Client` that is reused for Chicago API requests.
@@ -19,30 +17,11 @@ lazy_static! {
 
 /// Get data for Victims of Homicides and Non-Fatal Shootings and Shotspotter Alert data from the
 /// Chicago APIs.
-pub async fn get_vhnfs_shotspotter_data() -> Result<ChicagoMapData, StaccError> {
-    let violence_data: Value = serde_json::from_str(
-        &REQUEST_CLIENT
-            .get(VHNFS_ENDPOINT.to_string())
-            .header(
-                "X-App-Token",
-                EnvironmentVariables::SocrataAppToken.env_var()?,
-            )
-            .send()
-            .await?
-            .text()
-            .await?,
-    )?;
+pub async fn get_vhnfs_shotspotter_data(app_token: &str) -> Result<ChicagoMapData, StaccError> {
+    let violence_data: Value =
+        serde_json::from_str(&get_socrata_data(VHNFS_ENDPOINT.to_string(), app_token).await?)?;
     let shotspotter_data: Value = serde_json::from_str(
-        &REQUEST_CLIENT
-            .get(SHOTSPOTTER_ENDPOINT.to_string())
-            .header(
-                "X-App-Token",
-                EnvironmentVariables::SocrataAppToken.env_var()?,
-            )
-            .send()
-            .await?
-            .text()
-            .await?,
+        &get_socrata_data(SHOTSPOTTER_ENDPOINT.to_string(), app_token).await?,
     )?;
 
     let chicago_map_data = ChicagoMapData {
@@ -52,3 +31,34 @@ pub async fn get_vhnfs_shotspotter_data() -> Result<ChicagoMapData, StaccError>
 
     Ok(chicago_map_data)
 }
+
+/// Check that the Socrata API is reachable and accepts the app token by requesting a single row.
+pub async fn ping_socrata(app_token: &str) -> Result<(), StaccError> {
+    metrics::time_upstream("socrata", async {
+        REQUEST_CLIENT
+            .get(SHOTSPOTTER_ENDPOINT.to_string())
+            .header("X-App-Token", app_token)
+            .query(&[("$limit", "1")])
+            .send()
+            .await?
+            .error_for_status()
+    })
+    .await?;
+
+    Ok(())
+}
+
+/// Get the raw response body from a Socrata endpoint.
+async fn get_socrata_data(endpoint: String, app_token: &str) -> Result<String, StaccError> {
+    Ok(metrics::time_upstream("socrata", async {
+        REQUEST_CLIENT
+            .get(endpoint)
+            .header("X-App-Token", app_token)
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await
+    })
+    .await?)
+}
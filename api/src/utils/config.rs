@@ -0,0 +1,1413 @@
+//! Contains the API's configuration, which is loaded once at startup.
+//!
+//! Every setting may come from three places. From lowest to highest precedence:
+//!
+//! 1. The TOML config file (`config.toml` by default, see `--config`).
+//! 2. An environment variable, e.g. `MONGO_URI`.
+//! 3. A CLI flag, e.g. `--mongo-uri`.
+
+use std::{env, fs, io::ErrorKind, path::PathBuf, str::FromStr};
+
+use actix_web::http::Method;
+use ansi_term::Color;
+use clap::{Arg, ArgMatches, Command};
+use ipnet::IpNet;
+use lettre::message::Mailbox;
+use reqwest::Url;
+use toml::{Table, Value};
+use tracing_subscriber::EnvFilter;
+
+use crate::errors::StaccError;
+
+/// A setting that may be provided by the config file, an environment variable, or a CLI flag.
+struct Setting {
+    /// The setting's key in the config file, written as `section.key`.
+    key: &'static str,
+    /// The environment variable that overrides the config file.
+    env: &'static str,
+    /// The CLI flag that overrides the environment variable.
+    flag: &'static str,
+}
+
+/// The routes that visitors are logged for by default, which are the ones that pages of the site
+/// load.
+const DEFAULT_VISITOR_ROUTES: &[&str] = &[
+    "/api/background",
+    "/api/blog/post/{post_id}",
+    "/api/blog/post/{post_id}/comments",
+    "/api/blog/post/{post_id}/revisions",
+    "/api/blog/post/{post_id}/revisions/diff",
+    "/api/blog/post/{post_id}/webmentions",
+    "/api/blog/posts",
+    "/api/chiraq",
+    "/api/stories",
+    "/api/story",
+    "/api/story/{story_id}",
+];
+
+/// Every setting the API understands. This is the only list of settings, so the config file, the
+/// environment variables and the CLI flags cannot drift apart.
+const SETTINGS: &[Setting] = &[
+    Setting {
+        key: "collections.backgrounds",
+        env: "STACC_BACKGROUNDS_COLLECTION_NAME",
+        flag: "backgrounds-collection",
+    },
+    Setting {
+        key: "collections.comments",
+        env: "STACC_COMMENTS_COLLECTION_NAME",
+        flag: "comments-collection",
+    },
+    Setting {
+        key: "collections.posts",
+        env: "STACC_POSTS_COLLECTION_NAME",
+        flag: "posts-collection",
+    },
+    Setting {
+        key: "collections.revisions",
+        env: "STACC_REVISIONS_COLLECTION_NAME",
+        flag: "revisions-collection",
+    },
+    Setting {
+        key: "collections.stories",
+        env: "STACC_STORIES_COLLECTION_NAME",
+        flag: "stories-collection",
+    },
+    Setting {
+        key: "collections.subscribers",
+        env: "STACC_SUBSCRIBERS_COLLECTION_NAME",
+        flag: "subscribers-collection",
+    },
+    Setting {
+        key: "collections.visitors",
+        env: "STACC_VISITORS_COLLECTION_NAME",
+        flag: "visitors-collection",
+    },
+    Setting {
+        key: "collections.webmentions",
+        env: "STACC_WEBMENTIONS_COLLECTION_NAME",
+        flag: "webmentions-collection",
+    },
+    Setting {
+        key: "logging.format",
+        env: "STACC_LOG_FORMAT",
+        flag: "log-format",
+    },
+    Setting {
+        key: "logging.level",
+        env: "STACC_LOG_LEVEL",
+        flag: "log-level",
+    },
+    Setting {
+        key: "logging.redact_ip_addresses",
+        env: "STACC_REDACT_IP_ADDRESSES",
+        flag: "redact-ip-addresses",
+    },
+    Setting {
+        key: "media.cache_dir",
+        env: "STACC_MEDIA_CACHE_DIR",
+        flag: "media-cache-dir",
+    },
+    Setting {
+        key: "media.ffmpeg",
+        env: "STACC_FFMPEG",
+        flag: "ffmpeg",
+    },
+    Setting {
+        key: "mongo.database",
+        env: "STACC_DATABASE",
+        flag: "mongo-database",
+    },
+    Setting {
+        key: "mongo.password",
+        env: "MONGO_PASSWORD",
+        flag: "mongo-password",
+    },
+    Setting {
+        key: "mongo.uri",
+        env: "MONGO_URI",
+        flag: "mongo-uri",
+    },
+    Setting {
+        key: "mongo.user",
+        env: "MONGO_USER",
+        flag: "mongo-user",
+    },
+    Setting {
+        key: "proxy.header",
+        env: "STACC_PROXY_HEADER",
+        flag: "proxy-header",
+    },
+    Setting {
+        key: "proxy.trusted",
+        env: "STACC_TRUSTED_PROXIES",
+        flag: "trusted-proxies",
+    },
+    Setting {
+        key: "rate_limit.allowlist",
+        env: "STACC_RATE_LIMIT_ALLOWLIST",
+        flag: "rate-limit-allowlist",
+    },
+    Setting {
+        key: "rate_limit.default",
+        env: "STACC_RATE_LIMIT_DEFAULT",
+        flag: "rate-limit-default",
+    },
+    Setting {
+        key: "rate_limit.enabled",
+        env: "STACC_RATE_LIMIT_ENABLED",
+        flag: "rate-limit-enabled",
+    },
+    Setting {
+        key: "rate_limit.routes",
+        env: "STACC_RATE_LIMIT_ROUTES",
+        flag: "rate-limit-routes",
+    },
+    Setting {
+        key: "server.admin_token",
+        env: "STACC_ADMIN_TOKEN",
+        flag: "admin-token",
+    },
+    Setting {
+        key: "server.domain",
+        env: "STACC_DOMAIN",
+        flag: "domain",
+    },
+    Setting {
+        key: "server.port",
+        env: "STACC_API_PORT_NUMBER",
+        flag: "port",
+    },
+    Setting {
+        key: "server.subscription_secret",
+        env: "STACC_SUBSCRIPTION_SECRET",
+        flag: "subscription-secret",
+    },
+    Setting {
+        key: "smtp.from_address",
+        env: "SMTP_FROM_ADDRESS",
+        flag: "smtp-from-address",
+    },
+    Setting {
+        key: "smtp.host",
+        env: "SMTP_HOST",
+        flag: "smtp-host",
+    },
+    Setting {
+        key: "smtp.password",
+        env: "SMTP_PASSWORD",
+        flag: "smtp-password",
+    },
+    Setting {
+        key: "smtp.port",
+        env: "SMTP_PORT",
+        flag: "smtp-port",
+    },
+    Setting {
+        key: "smtp.starttls",
+        env: "SMTP_STARTTLS",
+        flag: "smtp-starttls",
+    },
+    Setting {
+        key: "smtp.username",
+        env: "SMTP_USERNAME",
+        flag: "smtp-username",
+    },
+    Setting {
+        key: "socrata.app_token",
+        env: "SOCRATA_APP_TOKEN",
+        flag: "socrata-app-token",
+    },
+    Setting {
+        key: "visitors.enabled",
+        env: "STACC_VISITORS_ENABLED",
+        flag: "visitors-enabled",
+    },
+    Setting {
+        key: "visitors.routes",
+        env: "STACC_VISITORS_ROUTES",
+        flag: "visitors-routes",
+    },
+    Setting {
+        key: "webmention.proxy",
+        env: "STACC_WEBMENTION_PROXY",
+        flag: "webmention-proxy",
+    },
+];
+
+/// The names of the MongoDB collections.
+#[derive(Clone, Debug)]
+pub struct CollectionsConfig {
+    /// The name of the collection that contains all backgrounds.
+    pub backgrounds: String,
+    /// The name of the collection that contains all comments.
+    pub comments: String,
+    /// The name of the collection that contains all posts.
+    pub posts: String,
+    /// The name of the collection that contains all post revisions.
+    pub revisions: String,
+    /// The name of the collection that contains all stories.
+    pub stories: String,
+    /// The name of the collection that contains all newsletter subscribers.
+    pub subscribers: String,
+    /// The name of the collection that contains all visitors.
+    pub visitors: String,
+    /// The name of the collection that contains all Webmentions.
+    pub webmentions: String,
+}
+
+/// How log lines are written.
+#[derive(Clone, Copy, Debug)]
+pub enum LogFormat {
+    /// One JSON object per line.
+    Json,
+    /// Human-readable text.
+    Text,
+}
+
+/// Settings for the API's logs.
+#[derive(Clone, Debug)]
+pub struct LoggingConfig {
+    /// How log lines are written. Defaults to `text`.
+    pub format: LogFormat,
+    /// Which events are logged, written as `RUST_LOG`-style directives, e.g. `info` or
+    /// `info,api=debug`. Defaults to `info`.
+    pub level: String,
+    /// Whether IP addresses are replaced with `[redacted]` in logs. Defaults to `false`.
+    pub redact_ip_addresses: bool,
+}
+
+/// Settings for the background media proxy.
+#[derive(Clone, Debug)]
+pub struct MediaConfig {
+    /// The directory that proxied media is cached in. Defaults to `media-cache`.
+    pub cache_dir: PathBuf,
+    /// An optional path to `ffmpeg`, which is used to convert GIFs to WebP and MP4. Only the
+    /// original GIFs are served if this is not set.
+    pub ffmpeg: Option<String>,
+}
+
+/// The MongoDB connection settings.
+#[derive(Clone, Debug)]
+pub struct MongoConfig {
+    /// The name of the database in MongoDB.
+    pub database: String,
+    /// The MongoDB password.
+    pub password: String,
+    /// The MongoDB host (and optionally port and options), ie. everything after the `@` in the
+    /// connection string.
+    pub uri: String,
+    /// The MongoDB user.
+    pub user: String,
+}
+
+impl MongoConfig {
+    /// The full MongoDB connection string.
+    pub fn connection_string(&self) -> String {
+        format!("mongodb://{}:{}@{}", self.user, self.password, self.uri)
+    }
+}
+
+/// The header that trusted proxies record the addresses they forward requests for in.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ForwardedHeader {
+    /// The standard `Forwarded` header, e.g. `Forwarded: for=192.0.2.60;proto=https`.
+    Forwarded,
+    /// The `X-Forwarded-For` header, e.g. `X-Forwarded-For: 192.0.2.60, 198.51.100.17`.
+    XForwardedFor,
+}
+
+/// Settings for finding the real IP address of clients behind reverse proxies.
+#[derive(Clone, Debug)]
+pub struct ProxyConfig {
+    /// The header the trusted proxies write to. Only this header is read, as a client may send
+    /// the other one with any address it likes. Defaults to `x-forwarded-for`.
+    pub header: ForwardedHeader,
+    /// The ranges, in CIDR notation, that the API trusts to report the address they forwarded a
+    /// request for. Defaults to `127.0.0.0/8, ::1/128`.
+    pub trusted: Vec<IpNet>,
+}
+
+/// A token bucket rate limit, written as `<REQUESTS>/<SECONDS>`. Up to `requests` requests may be
+/// made at once, and the allowance refills at `requests` per `seconds`.
+#[derive(Clone, Copy, Debug)]
+pub struct Limit {
+    /// The number of requests allowed per window, which is also the largest burst allowed.
+    pub requests: u32,
+    /// The length of the window in seconds.
+    pub seconds: u32,
+}
+
+impl FromStr for Limit {
+    type Err = ();
+
+    fn from_str(limit: &str) -> Result<Self, Self::Err> {
+        let (requests, seconds) = limit.trim().split_once('/').ok_or(())?;
+        let requests = requests.trim().parse::<u32>().map_err(|_| ())?;
+        let seconds = seconds.trim().parse::<u32>().map_err(|_| ())?;
+
+        if requests == 0 || seconds == 0 {
+            return Err(());
+        }
+
+        Ok(Self { requests, seconds })
+    }
+}
+
+/// A rate limit for a single route, written as `[METHOD ]<PATTERN>=<LIMIT>`, e.g.
+/// `POST /api/blog/post/{post_id}/comments=1/30`. Patterns use the same syntax as the routes
+/// themselves.
+#[derive(Clone, Debug)]
+pub struct RouteLimit {
+    /// The limit for this route.
+    pub limit: Limit,
+    /// The HTTP method this limit applies to. Applies to every method if this is not set.
+    pub method: Option<Method>,
+    /// The route pattern this limit applies to.
+    pub pattern: String,
+}
+
+impl FromStr for RouteLimit {
+    type Err = ();
+
+    fn from_str(route_limit: &str) -> Result<Self, Self::Err> {
+        let (route, limit) = route_limit.trim().rsplit_once('=').ok_or(())?;
+        let (method, pattern) = match route.trim().split_once(' ') {
+            Some((method, pattern)) => (
+                Some(Method::from_str(&method.trim().to_uppercase()).map_err(|_| ())?),
+                pattern.trim(),
+            ),
+            None => (None, route.trim()),
+        };
+
+        if !pattern.starts_with('/') {
+            return Err(());
+        }
+
+        Ok(Self {
+            limit: limit.parse()?,
+            method,
+            pattern: pattern.to_string(),
+        })
+    }
+}
+
+/// Settings for the per-IP rate limiter.
+#[derive(Clone, Debug)]
+pub struct RateLimitConfig {
+    /// The ranges, in CIDR notation, that are never rate limited. Defaults to `127.0.0.0/8,
+    /// ::1/128`, so that health checks from the same host are never limited.
+    pub allowlist: Vec<IpNet>,
+    /// The limit for every route that does not have its own. Defaults to `120/60`.
+    pub default: Limit,
+    /// Whether requests are rate limited at all. Defaults to `true`.
+    pub enabled: bool,
+    /// Limits for individual routes. These are checked before the built-in route limits, so they
+    /// may override them.
+    pub routes: Vec<RouteLimit>,
+}
+
+/// Settings for the API server itself.
+#[derive(Clone, Debug)]
+pub struct ServerConfig {
+    /// The token that must be provided to use admin-only endpoints.
+    pub admin_token: String,
+    /// The domain of the site.
+    pub domain: String,
+    /// The port number the API runs on.
+    pub port: u16,
+    /// The secret used to sign unsubscribe links.
+    pub subscription_secret: String,
+}
+
+/// The SMTP server newsletter emails are sent through.
+#[derive(Clone, Debug)]
+pub struct SmtpConfig {
+    /// The address newsletter emails are sent from.
+    pub from_address: Mailbox,
+    /// The hostname of the SMTP server.
+    pub host: String,
+    /// An optional password for the SMTP server.
+    pub password: Option<String>,
+    /// The port number of the SMTP server.
+    pub port: u16,
+    /// Whether to require STARTTLS when connecting to the SMTP server. Defaults to `true`.
+    pub starttls: bool,
+    /// An optional username for the SMTP server.
+    pub username: Option<String>,
+}
+
+/// The Socrata API settings for Chicago map-related data.
+#[derive(Clone, Debug)]
+pub struct SocrataConfig {
+    /// The Socrata app token.
+    pub app_token: String,
+}
+
+/// Settings for visitor logging.
+#[derive(Clone, Debug)]
+pub struct VisitorsConfig {
+    /// Whether visitors are logged at all. Defaults to `true`.
+    pub enabled: bool,
+    /// The patterns of the routes that visitors are logged for. Only `GET` requests are logged.
+    /// Defaults to every route that a page of the site loads.
+    pub routes: Vec<String>,
+}
+
+/// Settings for sending and verifying Webmentions.
+#[derive(Clone, Debug)]
+pub struct WebmentionConfig {
+    /// An optional proxy that all Webmention requests are routed through.
+    pub proxy: Option<String>,
+}
+
+/// The API's configuration.
+#[derive(Clone, Debug)]
+pub struct Config {
+    /// The names of the MongoDB collections.
+    pub collections: CollectionsConfig,
+    /// Settings for the API's logs.
+    pub logging: LoggingConfig,
+    /// Settings for the background media proxy.
+    pub media: MediaConfig,
+    /// The MongoDB connection settings.
+    pub mongo: MongoConfig,
+    /// Settings for finding the real IP address of clients behind reverse proxies.
+    pub proxy: ProxyConfig,
+    /// Settings for the per-IP rate limiter.
+    pub rate_limit: RateLimitConfig,
+    /// Settings for the API server itself.
+    pub server: ServerConfig,
+    /// The SMTP server newsletter emails are sent through.
+    pub smtp: SmtpConfig,
+    /// The Socrata API settings.
+    pub socrata: SocrataConfig,
+    /// Settings for visitor logging.
+    pub visitors: VisitorsConfig,
+    /// Settings for sending and verifying Webmentions.
+    pub webmention: WebmentionConfig,
+}
+
+impl Config {
+    /// Load the configuration from the config file, environment variables, and CLI flags. Every
+    /// missing or invalid value is reported together.
+    pub fn load() -> Result<Self, StaccError> {
+        println!(
+            "{}",
+            Color::Yellow.bold().paint("❗️ LOADING CONFIGURATION...")
+        );
+
+        Self::from_sources(&command().get_matches(), &|name| env::var(name).ok())
+    }
+
+    /// Load the configuration from parsed CLI flags, environment variables looked up with `env`, and
+    /// the config file they point to.
+    fn from_sources(
+        matches: &ArgMatches,
+        env: &dyn Fn(&str) -> Option<String>,
+    ) -> Result<Self, StaccError> {
+        let mut loader = Loader::new(matches, env);
+
+        let collections = CollectionsConfig {
+            backgrounds: loader.collection("collections.backgrounds"),
+            comments: loader.collection("collections.comments"),
+            posts: loader.collection("collections.posts"),
+            revisions: loader.collection("collections.revisions"),
+            stories: loader.collection("collections.stories"),
+            subscribers: loader.collection("collections.subscribers"),
+            visitors: loader.collection("collections.visitors"),
+            webmentions: loader.collection("collections.webmentions"),
+        };
+        let logging = LoggingConfig {
+            format: match loader.one_of("logging.format", &["json", "text"], "text") {
+                "json" => LogFormat::Json,
+                _ => LogFormat::Text,
+            },
+            level: loader.log_level("logging.level", "info"),
+            redact_ip_addresses: loader.boolean("logging.redact_ip_addresses", false),
+        };
+        let media = MediaConfig {
+            cache_dir: PathBuf::from(
+                loader
+                    .optional("media.cache_dir")
+                    .unwrap_or("media-cache".to_string()),
+            ),
+            ffmpeg: loader.optional("media.ffmpeg"),
+        };
+        let mongo = MongoConfig {
+            database: loader.non_empty("mongo.database"),
+            password: loader.required("mongo.password"),
+            uri: loader.non_empty("mongo.uri"),
+            user: loader.required("mongo.user"),
+        };
+        loader.check_host("mongo.uri", &mongo.connection_string());
+        let proxy = ProxyConfig {
+            header: match loader.one_of(
+                "proxy.header",
+                &["forwarded", "x-forwarded-for"],
+                "x-forwarded-for",
+            ) {
+                "forwarded" => ForwardedHeader::Forwarded,
+                _ => ForwardedHeader::XForwardedFor,
+            },
+            trusted: loader.list(
+                "proxy.trusted",
+                "CIDR ranges such as `172.16.0.0/12`",
+                "127.0.0.0/8,::1/128",
+            ),
+        };
+        let rate_limit = RateLimitConfig {
+            allowlist: loader.list(
+                "rate_limit.allowlist",
+                "CIDR ranges such as `10.0.0.0/8`",
+                "127.0.0.0/8,::1/128",
+            ),
+            default: loader.parse_or(
+                "rate_limit.default",
+                "a limit such as `120/60`",
+                Limit {
+                    requests: 120,
+                    seconds: 60,
+                },
+            ),
+            enabled: loader.boolean("rate_limit.enabled", true),
+            routes: loader.list(
+                "rate_limit.routes",
+                "route limits such as `POST /api/stories=1/30`",
+                "",
+            ),
+        };
+        let server = ServerConfig {
+            admin_token: loader.non_empty("server.admin_token"),
+            domain: loader.non_empty("server.domain"),
+            port: loader.port("server.port"),
+            subscription_secret: loader.non_empty("server.subscription_secret"),
+        };
+        loader.check_host("server.domain", &site_url(&server.domain, "/"));
+        let from_address = loader.parse::<Mailbox>("smtp.from_address", "an email address");
+        let smtp_host = loader.non_empty("smtp.host");
+        let smtp_password = loader.optional("smtp.password");
+        let smtp_port = loader.port("smtp.port");
+        let smtp_starttls = loader.boolean("smtp.starttls", true);
+        let smtp_username = loader.optional("smtp.username");
+        let socrata = SocrataConfig {
+            app_token: loader.required("socrata.app_token"),
+        };
+        let visitors = VisitorsConfig {
+            enabled: loader.boolean("visitors.enabled", true),
+            routes: loader.list(
+                "visitors.routes",
+                "route patterns",
+                &DEFAULT_VISITOR_ROUTES.join(","),
+            ),
+        };
+        let webmention = WebmentionConfig {
+            proxy: loader.optional("webmention.proxy"),
+        };
+        if let Some(proxy) = &webmention.proxy {
+            loader.check_host("webmention.proxy", proxy);
+        }
+
+        let (Some(from_address), true) = (from_address, loader.problems.is_empty()) else {
+            for problem in &loader.problems {
+                println!("{} {problem}", Color::Red.bold().paint("⭕️‼️"));
+            }
+
+            return Err(StaccError::Config(loader.problems));
+        };
+
+        println!("{}", Color::Green.bold().paint("💯 CONFIGURATION LOADED"));
+
+        Ok(Self {
+            collections,
+            logging,
+            media,
+            mongo,
+            proxy,
+            rate_limit,
+            server,
+            smtp: SmtpConfig {
+                from_address,
+                host: smtp_host,
+                password: smtp_password,
+                port: smtp_port,
+                starttls: smtp_starttls,
+                username: smtp_username,
+            },
+            socrata,
+            visitors,
+            webmention,
+        })
+    }
+
+    /// Get the public URL of a page on the site, ie. `site_url("/blog")` returns
+    /// `https://<DOMAIN>/blog`.
+    pub fn site_url(&self, path: &str) -> String {
+        site_url(&self.server.domain, path)
+    }
+}
+
+/// Reads settings from each layer, recording every problem it finds along the way.
+struct Loader<'a> {
+    /// Looks up an environment variable.
+    env: &'a dyn Fn(&str) -> Option<String>,
+    /// The parsed config file.
+    file: Table,
+    /// The parsed CLI flags.
+    matches: &'a ArgMatches,
+    /// Every problem found so far.
+    problems: Vec<String>,
+}
+
+impl<'a> Loader<'a> {
+    /// Read the config file and prepare to load settings.
+    fn new(matches: &'a ArgMatches, env: &'a dyn Fn(&str) -> Option<String>) -> Self {
+        let mut problems = Vec::new();
+
+        let path = matches
+            .get_one::<PathBuf>("config")
+            .cloned()
+            .or_else(|| env("STACC_CONFIG").map(PathBuf::from))
+            .unwrap_or_else(|| PathBuf::from("config.toml"));
+        let file = match fs::read_to_string(&path) {
+            Ok(contents) => contents.parse::<Table>().unwrap_or_else(|error| {
+                problems.push(format!("Unable to parse {}: {error}", path.display()));
+                Table::new()
+            }),
+            Err(error) if error.kind() == ErrorKind::NotFound => {
+                println!(
+                    "📃 No config file found at {}. Using environment variables and flags.",
+                    path.display()
+                );
+                Table::new()
+            }
+            Err(error) => {
+                problems.push(format!("Unable to read {}: {error}", path.display()));
+                Table::new()
+            }
+        };
+
+        let mut loader = Self {
+            env,
+            file,
+            matches,
+            problems,
+        };
+        loader.check_unknown_keys();
+
+        loader
+    }
+
+    /// Report keys in the config file that do not match any setting. These are usually typos.
+    fn check_unknown_keys(&mut self) {
+        for (section, value) in &self.file {
+            let Value::Table(table) = value else {
+                self.problems
+                    .push(format!("`{section}` in the config file must be a table."));
+                continue;
+            };
+
+            for key in table.keys() {
+                let key = format!("{section}.{key}");
+                if !SETTINGS.iter().any(|setting| setting.key == key) {
+                    self.problems
+                        .push(format!("Unknown key `{key}` in the config file."));
+                }
+            }
+        }
+    }
+
+    /// Get a setting's raw value from the highest precedence layer that sets it.
+    fn value(&self, key: &str) -> Option<String> {
+        if let Some(value) = self.matches.get_one::<String>(key) {
+            return Some(value.clone());
+        }
+        if let Some(value) = SETTINGS
+            .iter()
+            .find(|setting| setting.key == key)
+            .and_then(|setting| (self.env)(setting.env))
+        {
+            return Some(value);
+        }
+
+        let (section, name) = key.split_once('.')?;
+        match self.file.get(section)?.get(name)? {
+            Value::String(value) => Some(value.clone()),
+            // Lists may be written as TOML arrays, or as comma-separated strings like the
+            // environment variables.
+            Value::Array(values) => Some(
+                values
+                    .iter()
+                    .map(|value| match value {
+                        Value::String(value) => value.clone(),
+                        value => value.to_string(),
+                    })
+                    .collect::<Vec<String>>()
+                    .join(","),
+            ),
+            value => Some(value.to_string()),
+        }
+    }
+
+    /// Get an optional setting. Empty values are treated as unset.
+    fn optional(&self, key: &str) -> Option<String> {
+        self.value(key).filter(|value| !value.is_empty())
+    }
+
+    /// Get a required setting.
+    fn required(&mut self, key: &str) -> String {
+        self.value(key).unwrap_or_else(|| {
+            self.problems.push(format!("{} is not set.", describe(key)));
+            String::new()
+        })
+    }
+
+    /// Get a required setting that may not be empty.
+    fn non_empty(&mut self, key: &str) -> String {
+        let value = self.required(key);
+        if value.trim().is_empty() && self.value(key).is_some() {
+            self.problems
+                .push(format!("{} may not be empty.", describe(key)));
+        }
+
+        value
+    }
+
+    /// Get a MongoDB collection name.
+    fn collection(&mut self, key: &str) -> String {
+        let value = self.non_empty(key);
+        if value.contains('$') || value.starts_with("system.") {
+            self.problems
+                .push(format!("{} is not a valid collection name.", describe(key)));
+        }
+
+        value
+    }
+
+    /// Get a port number between 1 and 65535.
+    fn port(&mut self, key: &str) -> u16 {
+        let Some(value) = self.value(key) else {
+            self.problems.push(format!("{} is not set.", describe(key)));
+            return 0;
+        };
+
+        match value.trim().parse::<u16>() {
+            Ok(port) if port > 0 => port,
+            _ => {
+                self.problems.push(format!(
+                    "{} must be a port number between 1 and 65535, got `{value}`.",
+                    describe(key)
+                ));
+                0
+            }
+        }
+    }
+
+    /// Get a `true`/`false` setting, falling back to a default if it is not set.
+    fn boolean(&mut self, key: &str, default: bool) -> bool {
+        match self.optional(key).as_deref().map(str::trim) {
+            None => default,
+            Some("true") => true,
+            Some("false") => false,
+            Some(value) => {
+                self.problems.push(format!(
+                    "{} must be `true` or `false`, got `{value}`.",
+                    describe(key)
+                ));
+                default
+            }
+        }
+    }
+
+    /// Get a setting that must be one of a few options, falling back to a default if it is not set.
+    fn one_of<'b>(&mut self, key: &str, options: &[&'b str], default: &'b str) -> &'b str {
+        let Some(value) = self.optional(key) else {
+            return default;
+        };
+
+        options
+            .iter()
+            .find(|option| **option == value.trim())
+            .copied()
+            .unwrap_or_else(|| {
+                self.problems.push(format!(
+                    "{} must be one of `{}`, got `{value}`.",
+                    describe(key),
+                    options.join("`, `")
+                ));
+                default
+            })
+    }
+
+    /// Get a set of log level directives, falling back to a default if it is not set.
+    fn log_level(&mut self, key: &str, default: &str) -> String {
+        let Some(value) = self.optional(key) else {
+            return default.to_string();
+        };
+
+        if let Err(error) = EnvFilter::try_new(&value) {
+            self.problems.push(format!(
+                "{} must be a log level such as `info` or `info,api=debug`: {error}.",
+                describe(key)
+            ));
+        }
+
+        value
+    }
+
+    /// Get a required setting and parse it. `expected` describes what the value should look like.
+    fn parse<T: FromStr>(&mut self, key: &str, expected: &str) -> Option<T> {
+        let value = self.required(key);
+
+        let parsed = value.parse::<T>().ok();
+        if parsed.is_none() && self.value(key).is_some() {
+            self.problems.push(format!(
+                "{} must be {expected}, got `{value}`.",
+                describe(key)
+            ));
+        }
+
+        parsed
+    }
+
+    /// Get an optional setting and parse it, falling back to a default if it is not set. `expected`
+    /// describes what the value should look like.
+    fn parse_or<T: FromStr>(&mut self, key: &str, expected: &str, default: T) -> T {
+        let Some(value) = self.optional(key) else {
+            return default;
+        };
+
+        value.parse::<T>().unwrap_or_else(|_| {
+            self.problems.push(format!(
+                "{} must be {expected}, got `{value}`.",
+                describe(key)
+            ));
+
+            default
+        })
+    }
+
+    /// Get a comma-separated list of values and parse each one, falling back to a default if it is
+    /// not set. `expected` describes what the values should look like.
+    fn list<T: FromStr>(&mut self, key: &str, expected: &str, default: &str) -> Vec<T> {
+        let value = self.optional(key).unwrap_or(default.to_string());
+
+        let (parsed, invalid): (Vec<_>, Vec<_>) = value
+            .split(',')
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+            .map(|item| (item, item.parse::<T>()))
+            .partition(|(_item, parsed)| parsed.is_ok());
+
+        if !invalid.is_empty() {
+            let invalid: Vec<&str> = invalid.into_iter().map(|(item, _parsed)| item).collect();
+            self.problems.push(format!(
+                "{} must be {expected}, got `{}`.",
+                describe(key),
+                invalid.join("`, `")
+            ));
+        }
+
+        parsed
+            .into_iter()
+            .filter_map(|(_item, parsed)| parsed.ok())
+            .collect()
+    }
+
+    /// Check that a URL (built from a setting) parses and has a host.
+    fn check_host(&mut self, key: &str, url: &str) {
+        if self.value(key).is_none() {
+            return;
+        }
+
+        if !Url::parse(url)
+            .ok()
+            .and_then(|url| url.host_str().map(|host| !host.is_empty()))
+            .unwrap_or(false)
+        {
+            self.problems
+                .push(format!("{} is not a valid URL or host.", describe(key)));
+        }
+    }
+}
+
+/// Get the public URL of a page on a domain. The domain may include the scheme, otherwise HTTPS is
+/// assumed.
+pub fn site_url(domain: &str, path: &str) -> String {
+    let domain = domain.trim_end_matches('/');
+
+    if domain.starts_with("http://") || domain.starts_with("https://") {
+        format!("{domain}{path}")
+    } else {
+        format!("https://{domain}{path}")
+    }
+}
+
+/// Describe a setting along with every way it can be set, for use in error messages.
+fn describe(key: &str) -> String {
+    SETTINGS
+        .iter()
+        .find(|setting| setting.key == key)
+        .map_or_else(
+            || format!("`{key}`"),
+            |setting| format!("`{key}` ({} / --{})", setting.env, setting.flag),
+        )
+}
+
+/// The command line interface. Each setting gets a flag. Environment variables are read by the
+/// `Loader` rather than by `clap`, so that tests can load a config without touching the process's
+/// environment.
+fn command() -> Command {
+    SETTINGS.iter().fold(
+        Command::new("api").about("The API for the stacc.").arg(
+            Arg::new("config")
+                .help("The path to the TOML config file. [env: STACC_CONFIG]")
+                .long("config")
+                .value_name("PATH")
+                .value_parser(clap::value_parser!(PathBuf)),
+        ),
+        |command, setting| {
+            command.arg(
+                Arg::new(setting.key)
+                    .help(format!(
+                        "Overrides `{}` in the config file. [env: {}]",
+                        setting.key, setting.env
+                    ))
+                    .long(setting.flag)
+                    .value_name("VALUE"),
+            )
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        process,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    use super::*;
+
+    /// Numbers each test's config file, so that tests running at the same time do not share one.
+    static CONFIG_FILES: AtomicUsize = AtomicUsize::new(0);
+
+    /// A config file that sets every required setting.
+    const VALID: &str = r#"
+        [collections]
+        backgrounds = "backgrounds"
+        comments = "comments"
+        posts = "posts"
+        revisions = "revisions"
+        stories = "stories"
+        subscribers = "subscribers"
+        visitors = "visitors"
+        webmentions = "webmentions"
+
+        [mongo]
+        database = "stacc"
+        password = "hunter2"
+        uri = "localhost:27017"
+        user = "stacc"
+
+        [server]
+        admin_token = "admin-token"
+        domain = "example.com"
+        port = 8080
+        subscription_secret = "subscription-secret"
+
+        [smtp]
+        from_address = "The Stacc <noreply@example.com>"
+        host = "smtp.example.com"
+        port = 587
+
+        [socrata]
+        app_token = "app-token"
+    "#;
+
+    /// Parse the CLI with a config file and flags, and pass the matches to `f` along with an
+    /// environment holding only `env_vars`. The process's own environment is never read or
+    /// changed, so it cannot leak into these tests or race with others.
+    fn with_matches<T>(
+        file: &str,
+        env_vars: &[(&str, &str)],
+        flags: &[&str],
+        f: impl FnOnce(&ArgMatches, &dyn Fn(&str) -> Option<String>) -> T,
+    ) -> T {
+        let path = env::temp_dir().join(format!(
+            "stacc-config-test-{}-{}.toml",
+            process::id(),
+            CONFIG_FILES.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::write(&path, file).unwrap();
+
+        let env_vars: HashMap<String, String> = env_vars
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        let matches = command().try_get_matches_from(
+            ["api", "--config", path.to_str().unwrap()]
+                .into_iter()
+                .chain(flags.iter().copied()),
+        );
+
+        let result = f(&matches.unwrap(), &|name| env_vars.get(name).cloned());
+        fs::remove_file(&path).unwrap();
+
+        result
+    }
+
+    /// Load the config, returning the problems found if it is invalid.
+    fn load(file: &str, env_vars: &[(&str, &str)], flags: &[&str]) -> Result<Config, Vec<String>> {
+        with_matches(file, env_vars, flags, Config::from_sources).map_err(|error| match error {
+            StaccError::Config(problems) => problems,
+            error => panic!("unexpected error: {error}"),
+        })
+    }
+
+    /// Load the config, expecting it to be invalid, and return the problems found.
+    fn problems(file: &str, env_vars: &[(&str, &str)]) -> Vec<String> {
+        load(file, env_vars, &[]).expect_err("the config should be invalid")
+    }
+
+    /// Check that exactly one problem was found, and that it contains `expected`.
+    fn assert_one_problem(problems: &[String], expected: &str) {
+        assert_eq!(problems.len(), 1, "{problems:?}");
+        assert!(problems[0].contains(expected), "{problems:?}");
+    }
+
+    #[test]
+    fn valid_file_loads_with_defaults() {
+        let config = load(VALID, &[], &[]).unwrap();
+
+        assert_eq!(config.server.port, 8080);
+        assert_eq!(config.smtp.port, 587);
+        assert_eq!(config.site_url("/blog"), "https://example.com/blog");
+        assert!(config.rate_limit.enabled);
+        assert_eq!(config.rate_limit.default.requests, 120);
+        assert_eq!(config.rate_limit.default.seconds, 60);
+        assert_eq!(config.proxy.header, ForwardedHeader::XForwardedFor);
+        assert_eq!(config.proxy.trusted.len(), 2);
+        assert_eq!(config.visitors.routes.len(), DEFAULT_VISITOR_ROUTES.len());
+        assert!(config.webmention.proxy.is_none());
+    }
+
+    #[test]
+    fn environment_overrides_file() {
+        let config = load(VALID, &[("STACC_API_PORT_NUMBER", "9090")], &[]).unwrap();
+
+        assert_eq!(config.server.port, 9090);
+    }
+
+    #[test]
+    fn flag_overrides_environment_and_file() {
+        let config = load(
+            VALID,
+            &[("STACC_API_PORT_NUMBER", "9090")],
+            &["--port", "7070"],
+        )
+        .unwrap();
+
+        assert_eq!(config.server.port, 7070);
+    }
+
+    #[test]
+    fn config_file_may_be_set_by_the_environment() {
+        with_matches(VALID, &[], &[], |matches, _env| {
+            let path = matches.get_one::<PathBuf>("config").unwrap().clone();
+            let env = |name: &str| (name == "STACC_CONFIG").then(|| path.display().to_string());
+            let matches = command().try_get_matches_from(["api"]).unwrap();
+
+            let config = Config::from_sources(&matches, &env).unwrap();
+
+            assert_eq!(config.server.port, 8080);
+        });
+    }
+
+    #[test]
+    fn environment_fills_in_what_the_file_leaves_out() {
+        let file = VALID.replace(r#"app_token = "app-token""#, "");
+
+        let config = load(&file, &[("SOCRATA_APP_TOKEN", "from-env")], &[]).unwrap();
+
+        assert_eq!(config.socrata.app_token, "from-env");
+    }
+
+    #[test]
+    fn empty_file_reports_every_required_setting() {
+        let problems = problems("", &[]);
+
+        let required = [
+            "collections.backgrounds",
+            "collections.comments",
+            "collections.posts",
+            "collections.revisions",
+            "collections.stories",
+            "collections.subscribers",
+            "collections.visitors",
+            "collections.webmentions",
+            "mongo.database",
+            "mongo.password",
+            "mongo.uri",
+            "mongo.user",
+            "server.admin_token",
+            "server.domain",
+            "server.port",
+            "server.subscription_secret",
+            "smtp.from_address",
+            "smtp.host",
+            "smtp.port",
+            "socrata.app_token",
+        ];
+        assert_eq!(problems.len(), required.len(), "{problems:?}");
+        for key in required {
+            assert!(
+                problems
+                    .iter()
+                    .any(|problem| problem.starts_with(&format!("`{key}`"))
+                        && problem.ends_with("is not set.")),
+                "{key} was not reported: {problems:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn every_problem_is_reported_together() {
+        let file = format!(
+            "{VALID}
+            [logging]
+            format = \"xml\"
+            colour = \"red\"
+
+            [rate_limit]
+            enabled = \"yes\"
+            default = \"lots\"
+            "
+        )
+        .replace("port = 8080", "port = 0")
+        .replace(r#"posts = "posts""#, r#"posts = "$posts""#);
+
+        let problems = problems(&file, &[("SMTP_FROM_ADDRESS", "not an email")]);
+
+        let expected = [
+            "Unknown key `logging.colour`",
+            "`collections.posts` (STACC_POSTS_COLLECTION_NAME / --posts-collection) is not a valid collection name.",
+            "`logging.format` (STACC_LOG_FORMAT / --log-format) must be one of `json`, `text`, got `xml`.",
+            "`rate_limit.default` (STACC_RATE_LIMIT_DEFAULT / --rate-limit-default) must be a limit such as `120/60`, got `lots`.",
+            "`rate_limit.enabled` (STACC_RATE_LIMIT_ENABLED / --rate-limit-enabled) must be `true` or `false`, got `yes`.",
+            "`server.port` (STACC_API_PORT_NUMBER / --port) must be a port number between 1 and 65535, got `0`.",
+            "`smtp.from_address` (SMTP_FROM_ADDRESS / --smtp-from-address) must be an email address, got `not an email`.",
+        ];
+        assert_eq!(problems.len(), expected.len(), "{problems:?}");
+        for expected in expected {
+            assert!(
+                problems.iter().any(|problem| problem.contains(expected)),
+                "{expected} was not reported: {problems:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn unparseable_file_is_reported() {
+        let problems = problems("[server", &[]);
+
+        assert!(problems[0].starts_with("Unable to parse"), "{problems:?}");
+    }
+
+    #[test]
+    fn ports_are_validated() {
+        for port in ["0", "65536", "-1", "http", ""] {
+            let problems = problems(VALID, &[("STACC_API_PORT_NUMBER", port)]);
+
+            assert_one_problem(&problems, "must be a port number between 1 and 65535");
+        }
+
+        for port in ["1", " 443 ", "65535"] {
+            let config = load(VALID, &[("STACC_API_PORT_NUMBER", port)], &[]).unwrap();
+
+            assert_eq!(config.server.port.to_string(), port.trim());
+        }
+    }
+
+    #[test]
+    fn missing_port_is_reported() {
+        let problems = problems(&VALID.replace("port = 8080", ""), &[]);
+
+        assert_one_problem(
+            &problems,
+            "`server.port` (STACC_API_PORT_NUMBER / --port) is not set.",
+        );
+    }
+
+    #[test]
+    fn collection_names_are_validated() {
+        for (name, expected) in [
+            ("$cmd", "is not a valid collection name."),
+            ("posts$", "is not a valid collection name."),
+            ("system.users", "is not a valid collection name."),
+            ("", "may not be empty."),
+            ("   ", "may not be empty."),
+        ] {
+            let problems = problems(VALID, &[("STACC_POSTS_COLLECTION_NAME", name)]);
+
+            assert_one_problem(&problems, expected);
+        }
+
+        let config = load(VALID, &[("STACC_POSTS_COLLECTION_NAME", "blog.posts")], &[]).unwrap();
+        assert_eq!(config.collections.posts, "blog.posts");
+    }
+
+    #[test]
+    fn hosts_are_validated() {
+        for (env_var, value) in [
+            ("STACC_DOMAIN", "https://[::1"),
+            ("STACC_DOMAIN", "exa mple.com"),
+            ("MONGO_URI", "/stacc"),
+            ("STACC_WEBMENTION_PROXY", "not a url"),
+        ] {
+            let problems = problems(VALID, &[(env_var, value)]);
+
+            assert_one_problem(&problems, "is not a valid URL or host.");
+        }
+
+        let config = load(
+            VALID,
+            &[
+                ("STACC_DOMAIN", "http://localhost:8080/"),
+                ("STACC_WEBMENTION_PROXY", "http://127.0.0.1:3128"),
+            ],
+            &[],
+        )
+        .unwrap();
+        assert_eq!(config.site_url("/blog"), "http://localhost:8080/blog");
+        assert_eq!(
+            config.webmention.proxy.as_deref(),
+            Some("http://127.0.0.1:3128")
+        );
+    }
+
+    #[test]
+    fn list_is_split_on_commas_and_trimmed() {
+        let (trusted, problems) = with_matches(
+            "[proxy]\ntrusted = \" 10.0.0.0/8 , fd00::/8,, \"",
+            &[],
+            &[],
+            |matches, env| {
+                let mut loader = Loader::new(matches, env);
+                let trusted: Vec<IpNet> = loader.list("proxy.trusted", "CIDR ranges", "");
+
+                (trusted, loader.problems)
+            },
+        );
+
+        assert_eq!(
+            trusted,
+            vec![
+                "10.0.0.0/8".parse::<IpNet>().unwrap(),
+                "fd00::/8".parse().unwrap()
+            ]
+        );
+        assert!(problems.is_empty(), "{problems:?}");
+    }
+
+    #[test]
+    fn list_may_be_a_toml_array() {
+        let (routes, problems) = with_matches(
+            "[rate_limit]\nroutes = [\"POST /api/stories=1/30\", \"/api/blog/posts=10/60\"]",
+            &[],
+            &[],
+            |matches, env| {
+                let mut loader = Loader::new(matches, env);
+                let routes: Vec<RouteLimit> = loader.list("rate_limit.routes", "route limits", "");
+
+                (routes, loader.problems)
+            },
+        );
+
+        assert!(problems.is_empty(), "{problems:?}");
+        assert_eq!(routes.len(), 2);
+        assert_eq!(routes[0].method, Some(Method::POST));
+        assert_eq!(routes[0].pattern, "/api/stories");
+        assert_eq!(routes[0].limit.requests, 1);
+        assert_eq!(routes[0].limit.seconds, 30);
+        assert_eq!(routes[1].method, None);
+        assert_eq!(routes[1].pattern, "/api/blog/posts");
+    }
+
+    #[test]
+    fn list_falls_back_to_its_default() {
+        let (trusted, problems) = with_matches("", &[], &[], |matches, env| {
+            let mut loader = Loader::new(matches, env);
+            let trusted: Vec<IpNet> =
+                loader.list("proxy.trusted", "CIDR ranges", "127.0.0.0/8,::1/128");
+            let routes: Vec<RouteLimit> = loader.list("rate_limit.routes", "route limits", "");
+
+            assert!(routes.is_empty());
+            (trusted, loader.problems)
+        });
+
+        assert_eq!(trusted.len(), 2);
+        assert!(problems.is_empty(), "{problems:?}");
+    }
+
+    #[test]
+    fn invalid_list_items_are_reported_together_and_valid_ones_kept() {
+        let (trusted, problems) = with_matches(
+            "",
+            &[(
+                "STACC_TRUSTED_PROXIES",
+                "10.0.0.0/8,nope,fd00::/8,also-nope",
+            )],
+            &[],
+            |matches, env| {
+                let mut loader = Loader::new(matches, env);
+                let trusted: Vec<IpNet> = loader.list("proxy.trusted", "CIDR ranges", "");
+
+                (trusted, loader.problems)
+            },
+        );
+
+        assert_eq!(trusted.len(), 2);
+        assert_one_problem(&problems, "must be CIDR ranges, got `nope`, `also-nope`.");
+    }
+
+    #[test]
+    fn parse_or_falls_back_to_its_default() {
+        for value in [None, Some(""), Some("30/10"), Some("0/60"), Some("lots")] {
+            let env_vars: Vec<(&str, &str)> = value
+                .map(|value| ("STACC_RATE_LIMIT_DEFAULT", value))
+                .into_iter()
+                .collect();
+
+            let (limit, problems) = with_matches("", &env_vars, &[], |matches, env| {
+                let mut loader = Loader::new(matches, env);
+                let limit = loader.parse_or(
+                    "rate_limit.default",
+                    "a limit",
+                    Limit {
+                        requests: 120,
+                        seconds: 60,
+                    },
+                );
+
+                (limit, loader.problems)
+            });
+
+            match value {
+                Some("30/10") => {
+                    assert_eq!((limit.requests, limit.seconds), (30, 10));
+                    assert!(problems.is_empty(), "{problems:?}");
+                }
+                None | Some("") => {
+                    assert_eq!((limit.requests, limit.seconds), (120, 60));
+                    assert!(problems.is_empty(), "{problems:?}");
+                }
+                Some(value) => {
+                    assert_eq!((limit.requests, limit.seconds), (120, 60));
+                    assert_one_problem(&problems, &format!("must be a limit, got `{value}`."));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,20 @@
+//! Contains utilities for diffing post revisions.
+
+use similar::{ChangeTag, TextDiff};
+
+use crate::models::revision::{DiffLine, LineChange};
+
+/// Create a line-by-line diff between the `old` and `new` text.
+pub fn line_diff(old: &str, new: &str) -> Vec<DiffLine> {
+    TextDiff::from_lines(old, new)
+        .iter_all_changes()
+        .map(|change| DiffLine {
+            change: match change.tag() {
+                ChangeTag::Delete => LineChange::Delete,
+                ChangeTag::Equal => LineChange::Equal,
+                ChangeTag::Insert => LineChange::Insert,
+            },
+            line: change.value().trim_end_matches(['\r', '\n']).to_string(),
+        })
+        .collect()
+}
@@ -0,0 +1,388 @@
+//! Contains functionality for finding a client's real IP address behind reverse proxies.
+//!
+//! A proxy appends the address it received a request from to the end of the forwarding header, so
+//! the chain is read from right to left. Only hops that arrived from a trusted proxy are believed.
+//! The first untrusted address is the client, and anything to the left of it may have been made up
+//! by the client itself.
+
+use std::net::{IpAddr, SocketAddr};
+
+use actix_web::http::header::{self, HeaderMap};
+
+use super::config::{ForwardedHeader, ProxyConfig};
+
+/// Find the real IP address of a client, given the address of the peer that connected to the API.
+pub fn client_ip(peer: IpAddr, headers: &HeaderMap, proxy: &ProxyConfig) -> IpAddr {
+    let mut client = peer.to_canonical();
+
+    if !is_trusted(client, proxy) {
+        return client;
+    }
+
+    for hop in forwarded_chain(headers, proxy.header).into_iter().rev() {
+        // A trusted proxy forwarded a request for an address it could not describe (e.g. `unknown`
+        // or an obfuscated identifier), so that proxy is the closest thing to a client there is.
+        let Some(hop) = hop else {
+            return client;
+        };
+
+        client = hop.to_canonical();
+        if !is_trusted(client, proxy) {
+            return client;
+        }
+    }
+
+    // Every hop was a trusted proxy, so the request came from inside the trusted network.
+    client
+}
+
+/// Check whether an address belongs to a trusted proxy.
+fn is_trusted(ip_address: IpAddr, proxy: &ProxyConfig) -> bool {
+    proxy
+        .trusted
+        .iter()
+        .any(|network| network.contains(&ip_address))
+}
+
+/// Get every address in the forwarding header, from the original client to the last proxy. Hops
+/// that are not IP addresses are `None`. Multiple headers are read in order, as if they were a
+/// single comma-separated list.
+fn forwarded_chain(headers: &HeaderMap, forwarded_header: ForwardedHeader) -> Vec<Option<IpAddr>> {
+    let name = match forwarded_header {
+        ForwardedHeader::Forwarded => header::FORWARDED,
+        ForwardedHeader::XForwardedFor => header::X_FORWARDED_FOR,
+    };
+
+    headers
+        .get_all(name)
+        .flat_map(|value| value.to_str().unwrap_or("unknown").split(','))
+        .map(|element| match forwarded_header {
+            ForwardedHeader::Forwarded => element
+                .split(';')
+                .filter_map(|pair| pair.split_once('='))
+                .find(|(parameter, _node)| parameter.trim().eq_ignore_ascii_case("for"))
+                .and_then(|(_parameter, node)| parse_node(node)),
+            ForwardedHeader::XForwardedFor => parse_node(element),
+        })
+        .collect()
+}
+
+/// Parse a single hop, which may be a bare address (`192.0.2.60`, `2001:db8::1`), an address with
+/// a port (`192.0.2.60:4711`, `[2001:db8::1]:4711`), a bracketed IPv6 address (`[2001:db8::1]`), or
+/// any of these in quotes, as the `Forwarded` header requires for IPv6 addresses.
+fn parse_node(node: &str) -> Option<IpAddr> {
+    let node = node.trim().trim_matches('"');
+
+    if let Ok(ip_address) = node.parse::<IpAddr>() {
+        return Some(ip_address);
+    }
+    if let Ok(socket_address) = node.parse::<SocketAddr>() {
+        return Some(socket_address.ip());
+    }
+
+    node.strip_prefix('[')
+        .and_then(|node| node.strip_suffix(']'))
+        .and_then(|node| node.parse::<IpAddr>().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::http::header::{HeaderName, HeaderValue};
+
+    use super::*;
+
+    /// A config trusting loopback and a private proxy network.
+    fn proxy(header: ForwardedHeader) -> ProxyConfig {
+        ProxyConfig {
+            header,
+            trusted: vec![
+                "127.0.0.0/8".parse().unwrap(),
+                "::1/128".parse().unwrap(),
+                "10.0.0.0/8".parse().unwrap(),
+                "fd00::/8".parse().unwrap(),
+            ],
+        }
+    }
+
+    /// Build a header map from name and value pairs.
+    fn headers(pairs: &[(HeaderName, &'static str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.append(name.clone(), HeaderValue::from_static(value));
+        }
+
+        headers
+    }
+
+    /// Parse an IP address.
+    fn ip(ip_address: &str) -> IpAddr {
+        ip_address.parse().unwrap()
+    }
+
+    #[test]
+    fn untrusted_peer_is_the_client() {
+        let headers = headers(&[(header::X_FORWARDED_FOR, "1.1.1.1")]);
+
+        assert_eq!(
+            client_ip(
+                ip("203.0.113.7"),
+                &headers,
+                &proxy(ForwardedHeader::XForwardedFor)
+            ),
+            ip("203.0.113.7")
+        );
+    }
+
+    #[test]
+    fn trusted_peer_without_header_is_the_client() {
+        assert_eq!(
+            client_ip(
+                ip("127.0.0.1"),
+                &HeaderMap::new(),
+                &proxy(ForwardedHeader::XForwardedFor)
+            ),
+            ip("127.0.0.1")
+        );
+    }
+
+    #[test]
+    fn x_forwarded_for_from_trusted_proxy() {
+        let headers = headers(&[(header::X_FORWARDED_FOR, "203.0.113.7")]);
+
+        assert_eq!(
+            client_ip(
+                ip("127.0.0.1"),
+                &headers,
+                &proxy(ForwardedHeader::XForwardedFor)
+            ),
+            ip("203.0.113.7")
+        );
+    }
+
+    #[test]
+    fn spoofed_x_forwarded_for_is_ignored() {
+        // The client claimed to be 1.1.1.1, and the proxy appended the address it really saw.
+        let headers = headers(&[(header::X_FORWARDED_FOR, "1.1.1.1, 203.0.113.7")]);
+
+        assert_eq!(
+            client_ip(
+                ip("127.0.0.1"),
+                &headers,
+                &proxy(ForwardedHeader::XForwardedFor)
+            ),
+            ip("203.0.113.7")
+        );
+    }
+
+    #[test]
+    fn spoofed_trusted_address_is_ignored() {
+        // Claiming to be a trusted proxy does not let the client pick the address either.
+        let headers = headers(&[(header::X_FORWARDED_FOR, "1.1.1.1, 10.0.0.5, 203.0.113.7")]);
+
+        assert_eq!(
+            client_ip(
+                ip("127.0.0.1"),
+                &headers,
+                &proxy(ForwardedHeader::XForwardedFor)
+            ),
+            ip("203.0.113.7")
+        );
+    }
+
+    #[test]
+    fn chain_of_trusted_proxies_is_skipped() {
+        let headers = headers(&[(header::X_FORWARDED_FOR, "1.1.1.1, 203.0.113.7, 10.0.0.5")]);
+
+        assert_eq!(
+            client_ip(
+                ip("10.0.0.6"),
+                &headers,
+                &proxy(ForwardedHeader::XForwardedFor)
+            ),
+            ip("203.0.113.7")
+        );
+    }
+
+    #[test]
+    fn multiple_x_forwarded_for_headers_are_one_chain() {
+        let headers = headers(&[
+            (header::X_FORWARDED_FOR, "1.1.1.1"),
+            (header::X_FORWARDED_FOR, "203.0.113.7"),
+        ]);
+
+        assert_eq!(
+            client_ip(
+                ip("127.0.0.1"),
+                &headers,
+                &proxy(ForwardedHeader::XForwardedFor)
+            ),
+            ip("203.0.113.7")
+        );
+    }
+
+    #[test]
+    fn unconfigured_header_is_ignored() {
+        // The proxy writes `X-Forwarded-For`, so a `Forwarded` header can only be the client's.
+        let headers = headers(&[
+            (header::FORWARDED, "for=1.1.1.1"),
+            (header::X_FORWARDED_FOR, "203.0.113.7"),
+        ]);
+
+        assert_eq!(
+            client_ip(
+                ip("127.0.0.1"),
+                &headers,
+                &proxy(ForwardedHeader::XForwardedFor)
+            ),
+            ip("203.0.113.7")
+        );
+    }
+
+    #[test]
+    fn unknown_hop_stops_at_the_proxy_that_forwarded_it() {
+        let headers = headers(&[(header::X_FORWARDED_FOR, "1.1.1.1, unknown, 10.0.0.5")]);
+
+        assert_eq!(
+            client_ip(
+                ip("127.0.0.1"),
+                &headers,
+                &proxy(ForwardedHeader::XForwardedFor)
+            ),
+            ip("10.0.0.5")
+        );
+    }
+
+    #[test]
+    fn forwarded_header() {
+        let headers = headers(&[(
+            header::FORWARDED,
+            "for=1.1.1.1;proto=https, for=203.0.113.7;proto=https;by=10.0.0.5",
+        )]);
+
+        assert_eq!(
+            client_ip(
+                ip("127.0.0.1"),
+                &headers,
+                &proxy(ForwardedHeader::Forwarded)
+            ),
+            ip("203.0.113.7")
+        );
+    }
+
+    #[test]
+    fn forwarded_header_is_case_insensitive() {
+        let headers = headers(&[(header::FORWARDED, "Proto=https;For=203.0.113.7")]);
+
+        assert_eq!(
+            client_ip(
+                ip("127.0.0.1"),
+                &headers,
+                &proxy(ForwardedHeader::Forwarded)
+            ),
+            ip("203.0.113.7")
+        );
+    }
+
+    #[test]
+    fn forwarded_header_with_quoted_ipv6_and_port() {
+        let headers = headers(&[(header::FORWARDED, r#"for="[2001:db8:cafe::17]:4711""#)]);
+
+        assert_eq!(
+            client_ip(
+                ip("127.0.0.1"),
+                &headers,
+                &proxy(ForwardedHeader::Forwarded)
+            ),
+            ip("2001:db8:cafe::17")
+        );
+    }
+
+    #[test]
+    fn forwarded_header_with_obfuscated_node() {
+        let headers = headers(&[(header::FORWARDED, "for=_hidden, for=10.0.0.5")]);
+
+        assert_eq!(
+            client_ip(
+                ip("127.0.0.1"),
+                &headers,
+                &proxy(ForwardedHeader::Forwarded)
+            ),
+            ip("10.0.0.5")
+        );
+    }
+
+    #[test]
+    fn ipv6_peer_is_not_split_on_colons() {
+        assert_eq!(
+            client_ip(
+                ip("2001:db8::1"),
+                &HeaderMap::new(),
+                &proxy(ForwardedHeader::XForwardedFor)
+            ),
+            ip("2001:db8::1")
+        );
+    }
+
+    #[test]
+    fn ipv6_proxy_and_client() {
+        let headers = headers(&[(header::X_FORWARDED_FOR, "2001:db8::1, fd00::2")]);
+
+        assert_eq!(
+            client_ip(ip("::1"), &headers, &proxy(ForwardedHeader::XForwardedFor)),
+            ip("2001:db8::1")
+        );
+    }
+
+    #[test]
+    fn ipv4_with_port_and_bracketed_ipv6() {
+        let headers = headers(&[(header::X_FORWARDED_FOR, "[2001:db8::1], 203.0.113.7:4711")]);
+
+        assert_eq!(
+            client_ip(
+                ip("127.0.0.1"),
+                &headers,
+                &proxy(ForwardedHeader::XForwardedFor)
+            ),
+            ip("203.0.113.7")
+        );
+    }
+
+    #[test]
+    fn ipv4_mapped_ipv6_is_canonicalized() {
+        // A trusted proxy connecting over a dual-stack socket is still trusted.
+        let headers = headers(&[(header::X_FORWARDED_FOR, "::ffff:203.0.113.7")]);
+
+        assert_eq!(
+            client_ip(
+                ip("::ffff:127.0.0.1"),
+                &headers,
+                &proxy(ForwardedHeader::XForwardedFor)
+            ),
+            ip("203.0.113.7")
+        );
+    }
+
+    #[test]
+    fn docker_gateway_is_trusted_once_configured() {
+        // nginx on the host reaches the API in Docker through the network's gateway.
+        let headers = headers(&[(header::X_FORWARDED_FOR, "203.0.113.7")]);
+        let mut proxy = proxy(ForwardedHeader::XForwardedFor);
+        proxy.trusted = vec!["127.0.0.0/8".parse().unwrap(), "::1/128".parse().unwrap()];
+
+        assert_eq!(
+            client_ip(ip("172.30.0.1"), &headers, &proxy),
+            ip("172.30.0.1")
+        );
+
+        // The ranges `compose.yaml` sets `STACC_TRUSTED_PROXIES` to.
+        proxy.trusted = "127.0.0.0/8,::1/128,172.30.0.1/32"
+            .split(',')
+            .map(|network| network.parse().unwrap())
+            .collect();
+
+        assert_eq!(
+            client_ip(ip("172.30.0.1"), &headers, &proxy),
+            ip("203.0.113.7")
+        );
+    }
+}
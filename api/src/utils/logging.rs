@@ -0,0 +1,49 @@
+//! Sets up the API's logging.
+//!
+//! Logs are written to stdout as human-readable text or as one JSON object per line. Every event
+//! logged while handling a request carries that request's ID (see `middleware::request_id`).
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use tracing_subscriber::{fmt, util::SubscriberInitExt, EnvFilter};
+
+use crate::{
+    errors::StaccError,
+    utils::config::{LogFormat, LoggingConfig},
+};
+
+/// Whether IP addresses should be redacted from logs. This is set once by `init`.
+static REDACT_IP_ADDRESSES: AtomicBool = AtomicBool::new(false);
+
+/// Install the global logger. Events from crates that use `log` rather than `tracing` are
+/// forwarded to it as well.
+pub fn init(config: &LoggingConfig) -> Result<(), StaccError> {
+    REDACT_IP_ADDRESSES.store(config.redact_ip_addresses, Ordering::Relaxed);
+
+    // The level is validated when the config is loaded, so this only fails if it is changed in
+    // between.
+    let filter = EnvFilter::try_new(&config.level).unwrap_or_else(|_| EnvFilter::new("info"));
+    let builder = fmt().with_env_filter(filter);
+
+    match config.format {
+        LogFormat::Json => builder
+            .json()
+            .flatten_event(true)
+            .with_current_span(true)
+            .with_span_list(false)
+            .finish()
+            .try_init()?,
+        LogFormat::Text => builder.finish().try_init()?,
+    }
+
+    Ok(())
+}
+
+/// Get an IP address as it should appear in logs, ie. `[redacted]` if IP addresses are redacted.
+pub fn ip_address(ip_address: &str) -> &str {
+    if REDACT_IP_ADDRESSES.load(Ordering::Relaxed) {
+        "[redacted]"
+    } else {
+        ip_address
+    }
+}
@@ -0,0 +1,229 @@
+//! Contains the SMTP mailer used to send newsletter emails.
+
+use actix_web::web::Data;
+use futures_util::stream::StreamExt;
+use lettre::{
+    message::{
+        header::{Header, HeaderName, HeaderValue},
+        Mailbox, MultiPart,
+    },
+    transport::smtp::authentication::Credentials,
+    AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
+};
+use mongodb::bson::doc;
+use reqwest::Url;
+use tracing::{error, info};
+
+use crate::{errors::StaccError, models::post::PostData};
+
+use super::{
+    config::{self, Config},
+    mongo::Mongo,
+    signature,
+};
+
+/// The `List-Unsubscribe` header, which lets mail clients render their own unsubscribe button.
+#[derive(Clone, Debug)]
+struct ListUnsubscribe(String);
+
+impl Header for ListUnsubscribe {
+    fn name() -> HeaderName {
+        HeaderName::new_from_ascii_str("List-Unsubscribe")
+    }
+
+    fn parse(value: &str) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(Self(value.to_string()))
+    }
+
+    fn display(&self) -> HeaderValue {
+        HeaderValue::new(Self::name(), format!("<{}>", self.0))
+    }
+}
+
+/// Sends emails through the configured SMTP server.
+#[derive(Clone)]
+pub struct Mailer {
+    /// The site's domain, used for building links.
+    domain: String,
+    /// The address emails are sent from.
+    from_address: Mailbox,
+    /// The SMTP transport.
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+}
+
+impl Mailer {
+    /// Create a new `Mailer` from the SMTP config. STARTTLS is required unless `smtp.starttls` is
+    /// set to `false`, which allows the mailer to be pointed at a local SMTP test server.
+    pub fn new(config: &Config) -> Result<Self, StaccError> {
+        let smtp = &config.smtp;
+
+        let mut builder = if smtp.starttls {
+            AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&smtp.host)?
+        } else {
+            AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&smtp.host)
+        }
+        .port(smtp.port);
+
+        if let (Some(username), Some(password)) = (&smtp.username, &smtp.password) {
+            builder = builder.credentials(Credentials::new(username.clone(), password.clone()));
+        }
+
+        Ok(Self {
+            domain: config.server.domain.clone(),
+            from_address: smtp.from_address.clone(),
+            transport: builder.build(),
+        })
+    }
+
+    /// Send the double opt-in email containing the confirmation link.
+    pub async fn send_confirmation(&self, email: &str, token: &str) -> Result<(), StaccError> {
+        let confirm_link = self.site_url(&format!("/newsletter/confirm/{token}"));
+
+        let text = format!(
+            "Someone (hopefully you) asked to be emailed whenever a new post goes up on the blog.\n\nConfirm your subscription here: {confirm_link}\n\nIf this wasn't you, ignore this email and you won't hear from me again."
+        );
+        let html = format!(
+            r#"<div style="font-family: monospace; color: #1c2128;">
+  <p>Someone (hopefully you) asked to be emailed whenever a new post goes up on the blog.</p>
+  <p><a href="{confirm_link}" style="color: #b7410e;">Confirm your subscription</a></p>
+  <p style="color: #5e5e5e;">If this wasn't you, ignore this email and you won't hear from me again.</p>
+</div>"#,
+            confirm_link = escape_html(&confirm_link)
+        );
+
+        self.send(email, "jl | confirm your subscription", text, html, None)
+            .await
+    }
+
+    /// Send the email announcing a newly published post. The unsubscribe link is signed with the
+    /// `subscription_secret`.
+    pub async fn send_new_post(
+        &self,
+        email: &str,
+        post: &PostData,
+        subscription_secret: &str,
+    ) -> Result<(), StaccError> {
+        let post_link = self.site_url(&format!("/blog/post/{}", post.post_id));
+        let unsubscribe_link = self.unsubscribe_url(email, subscription_secret)?;
+
+        let text = format!(
+            "{}\n\n{}\n\nRead it here: {post_link}\n\nUnsubscribe: {unsubscribe_link}",
+            post.title, post.preview_summary
+        );
+        let html = format!(
+            r#"<div style="font-family: monospace; color: #1c2128;">
+  <a href="{post_link}"><img alt="{title}" src="{image}" style="max-width: 100%;" /></a>
+  <h2><a href="{post_link}" style="color: #b7410e; text-decoration: none;">{title}</a></h2>
+  <p><i>{summary}</i></p>
+  <p><a href="{post_link}" style="color: #b7410e;">Read the post</a></p>
+  <p style="color: #5e5e5e; font-size: small;">
+    <a href="{unsubscribe_link}" style="color: #5e5e5e;">Unsubscribe</a>
+  </p>
+</div>"#,
+            image = escape_html(&post.preview_image_link),
+            post_link = escape_html(&post_link),
+            summary = escape_html(&post.preview_summary),
+            title = escape_html(&post.title),
+            unsubscribe_link = escape_html(&unsubscribe_link),
+        );
+
+        self.send(
+            email,
+            &format!("jl | {}", post.title),
+            text,
+            html,
+            Some(unsubscribe_link),
+        )
+        .await
+    }
+
+    /// Get the public URL of a page on the site.
+    fn site_url(&self, path: &str) -> String {
+        config::site_url(&self.domain, path)
+    }
+
+    /// Create the signed unsubscribe link for an email address.
+    fn unsubscribe_url(
+        &self,
+        email: &str,
+        subscription_secret: &str,
+    ) -> Result<String, StaccError> {
+        let signature = signature::sign(subscription_secret, email)?;
+
+        Ok(Url::parse_with_params(
+            &self.site_url("/newsletter/unsubscribe"),
+            &[("email", email), ("signature", &signature)],
+        )?
+        .to_string())
+    }
+
+    /// Build and send a multipart email containing both plain text and HTML bodies.
+    async fn send(
+        &self,
+        to: &str,
+        subject: &str,
+        text: String,
+        html: String,
+        unsubscribe_link: Option<String>,
+    ) -> Result<(), StaccError> {
+        let mut builder = Message::builder()
+            .from(self.from_address.clone())
+            .to(to.parse()?)
+            .subject(subject);
+
+        if let Some(unsubscribe_link) = unsubscribe_link {
+            builder = builder.header(ListUnsubscribe(unsubscribe_link));
+        }
+
+        self.transport
+            .send(builder.multipart(MultiPart::alternative_plain_html(text, html))?)
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// Email every confirmed subscriber about a newly published post.
+pub async fn send_newsletter(
+    config: Data<Config>,
+    mongo: Data<Mongo>,
+    mailer: Data<Mailer>,
+    post: PostData,
+) {
+    let mut cursor = match mongo
+        .subscribers_collection
+        .find(doc! { "confirmed": true }, None)
+        .await
+    {
+        Ok(cursor) => cursor,
+        Err(error) => {
+            error!(%error, "failed to get newsletter subscribers");
+            return;
+        }
+    };
+
+    let mut sent = 0;
+    while let Some(document) = cursor.next().await {
+        match document {
+            Ok(subscriber) => match mailer
+                .send_new_post(&subscriber.email, &post, &config.server.subscription_secret)
+                .await
+            {
+                Ok(()) => sent += 1,
+                Err(error) => error!(%error, "failed to email a subscriber"),
+            },
+            Err(error) => error!(%error, "failed to read a newsletter subscriber"),
+        }
+    }
+
+    info!(post_id = post.post_id, sent, "sent newsletter emails");
+}
+
+/// Escape text so that it can be safely embedded in an HTML email.
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
@@ -0,0 +1,246 @@
+//! Contains the background media proxy, which caches background GIFs on disk and converts them to
+//! smaller formats, so that visitors never request them from a third party.
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    process::Stdio,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use reqwest::{redirect::Policy, Client};
+use tokio::{fs, process::Command, time};
+use tracing::info;
+
+use crate::{errors::StaccError, middleware::metrics};
+
+use super::config::MediaConfig;
+
+/// The largest background that will be downloaded, in bytes.
+const MAX_DOWNLOAD_BYTES: usize = 32 * 1024 * 1024;
+
+/// How long `ffmpeg` is given to convert a background.
+const CONVERSION_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// The formats a background may be served in.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MediaFormat {
+    /// The original GIF.
+    Gif,
+    /// An H.264 MP4, for `<video>` elements.
+    Mp4,
+    /// An animated WebP, for browsers that accept it.
+    Webp,
+}
+
+impl MediaFormat {
+    /// Parse a format from its file extension.
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension {
+            "gif" => Some(Self::Gif),
+            "mp4" => Some(Self::Mp4),
+            "webp" => Some(Self::Webp),
+            _ => None,
+        }
+    }
+
+    /// The file extension for this format.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Gif => "gif",
+            Self::Mp4 => "mp4",
+            Self::Webp => "webp",
+        }
+    }
+
+    /// The `ffmpeg` output arguments that convert a GIF to this format.
+    fn ffmpeg_args(&self) -> &'static [&'static str] {
+        match self {
+            Self::Gif => &[],
+            Self::Mp4 => &[
+                "-c:v",
+                "libx264",
+                "-pix_fmt",
+                "yuv420p",
+                // H.264 requires even dimensions.
+                "-vf",
+                "scale=trunc(iw/2)*2:trunc(ih/2)*2",
+                "-movflags",
+                "+faststart",
+                "-an",
+                "-f",
+                "mp4",
+            ],
+            Self::Webp => &[
+                "-c:v", "libwebp", "-quality", "75", "-loop", "0", "-an", "-f", "webp",
+            ],
+        }
+    }
+}
+
+/// Downloads backgrounds into a directory on disk, converting them with `ffmpeg` on request.
+/// Each background and format is only downloaded or converted once.
+#[derive(Debug)]
+pub struct MediaCache {
+    /// The directory that backgrounds are cached in.
+    cache_dir: PathBuf,
+    /// The HTTP client that backgrounds are downloaded with.
+    client: Client,
+    /// The path to `ffmpeg`, if conversions are enabled.
+    ffmpeg: Option<String>,
+    /// Maps each background ID to a lock, so that concurrent requests for a background that is not
+    /// cached yet only download or convert it once.
+    locks: Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>,
+}
+
+impl MediaCache {
+    /// Create a new `MediaCache`, creating the cache directory if it does not exist.
+    pub fn new(config: &MediaConfig) -> Result<Self, StaccError> {
+        std::fs::create_dir_all(&config.cache_dir)?;
+
+        Ok(Self {
+            cache_dir: config.cache_dir.clone(),
+            client: Client::builder()
+                .redirect(Policy::limited(5))
+                .timeout(Duration::from_secs(30))
+                .user_agent(concat!("stacc-media/", env!("CARGO_PKG_VERSION")))
+                .build()?,
+            ffmpeg: config.ffmpeg.clone(),
+            locks: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Whether backgrounds may be converted to formats other than GIF.
+    pub fn can_convert(&self) -> bool {
+        self.ffmpeg.is_some()
+    }
+
+    /// Get the path to a cached background in the given format, downloading and converting it
+    /// first if it has not been cached yet.
+    pub async fn get(
+        &self,
+        background_id: &str,
+        link: &str,
+        format: MediaFormat,
+    ) -> Result<PathBuf, StaccError> {
+        let path = self.path(background_id, format);
+        if fs::try_exists(&path).await? {
+            return Ok(path);
+        }
+
+        let lock = self
+            .locks
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .entry(background_id.to_string())
+            .or_default()
+            .clone();
+        let _guard = lock.lock().await;
+
+        // Another request may have cached it while this one was waiting for the lock.
+        if fs::try_exists(&path).await? {
+            return Ok(path);
+        }
+
+        let original = self.path(background_id, MediaFormat::Gif);
+        if !fs::try_exists(&original).await? {
+            self.download(link, &original).await?;
+        }
+        if format != MediaFormat::Gif {
+            self.convert(&original, &path, format).await?;
+        }
+
+        Ok(path)
+    }
+
+    /// Download a GIF to the given path. The GIF is written to a temporary file first, so that a
+    /// failed download is never served.
+    async fn download(&self, link: &str, path: &Path) -> Result<(), StaccError> {
+        info!(link, "downloading a background");
+
+        let body = metrics::time_upstream("media", async {
+            let mut response = self.client.get(link).send().await?.error_for_status()?;
+
+            let mut body: Vec<u8> = Vec::new();
+            while let Some(chunk) = response.chunk().await? {
+                if body.len() + chunk.len() > MAX_DOWNLOAD_BYTES {
+                    return Err(StaccError::Media(format!(
+                        "the background is larger than {MAX_DOWNLOAD_BYTES} bytes"
+                    )));
+                }
+                body.extend_from_slice(&chunk);
+            }
+
+            Ok::<_, StaccError>(body)
+        })
+        .await?;
+
+        if !body.starts_with(b"GIF8") {
+            return Err(StaccError::Media("the background is not a GIF".to_string()));
+        }
+
+        let partial = partial_path(path);
+        fs::write(&partial, body).await?;
+        fs::rename(&partial, path).await?;
+
+        Ok(())
+    }
+
+    /// Convert a GIF to another format with `ffmpeg`.
+    async fn convert(
+        &self,
+        input: &Path,
+        output: &Path,
+        format: MediaFormat,
+    ) -> Result<(), StaccError> {
+        let Some(ffmpeg) = &self.ffmpeg else {
+            return Err(StaccError::Media(format!(
+                "converting backgrounds to {} is disabled",
+                format.extension()
+            )));
+        };
+
+        info!(?output, "converting a background");
+
+        let partial = partial_path(output);
+        let status = time::timeout(
+            CONVERSION_TIMEOUT,
+            Command::new(ffmpeg)
+                .args(["-y", "-loglevel", "error", "-i"])
+                .arg(input)
+                .args(format.ffmpeg_args())
+                .arg(&partial)
+                .kill_on_drop(true)
+                .stdin(Stdio::null())
+                .status(),
+        )
+        .await
+        .map_err(|_| StaccError::Media("ffmpeg timed out".to_string()))??;
+
+        if !status.success() {
+            // Do not leave a half-written file behind for the next attempt to trip over.
+            let _ = fs::remove_file(&partial).await;
+
+            return Err(StaccError::Media(format!("ffmpeg exited with {status}")));
+        }
+
+        fs::rename(&partial, output).await?;
+
+        Ok(())
+    }
+
+    /// The path a background is cached at in the given format.
+    fn path(&self, background_id: &str, format: MediaFormat) -> PathBuf {
+        self.cache_dir
+            .join(format!("{background_id}.{}", format.extension()))
+    }
+}
+
+/// The temporary path a file is written to before it is moved into place.
+fn partial_path(path: &Path) -> PathBuf {
+    let mut partial = path.as_os_str().to_owned();
+    partial.push(".part");
+
+    PathBuf::from(partial)
+}
@@ -1,6 +1,16 @@
 //! Contains miscellaneous utilities for `stacc`.
 
-pub mod checks;
+pub mod auth;
 pub mod chicago;
-pub mod environment;
+pub mod config;
+pub mod diff;
+pub mod ip;
+pub mod logging;
+pub mod mailer;
+pub mod media;
+pub mod moderation;
 pub mod mongo;
+pub mod pool;
+pub mod signature;
+pub mod tasks;
+pub mod webmention;
@@ -0,0 +1,172 @@
+//! Contains the checks shared by every kind of reader submission that goes through moderation.
+
+use actix_web::{HttpRequest, HttpResponse};
+use lazy_static::lazy_static;
+use tracing::info;
+
+use crate::{errors::StaccResponseError, middleware, models::data::Response};
+
+use super::logging;
+
+lazy_static! {
+    /// The maximum number of characters allowed in a submission author's name.
+    static ref MAX_AUTHOR_LENGTH: usize = 50;
+    /// The maximum number of characters allowed in a submission's body.
+    static ref MAX_BODY_LENGTH: usize = 2000;
+}
+
+/// Contains every kind of reader submission.
+#[derive(Clone, Copy, Debug)]
+pub enum SubmissionKind {
+    /// A comment on a post.
+    Comment,
+    /// A story for the 404 page.
+    Story,
+}
+
+impl SubmissionKind {
+    /// The name of a single submission of this kind, e.g. `Comment`.
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Comment => "Comment",
+            Self::Story => "Story",
+        }
+    }
+
+    /// The name of many submissions of this kind, e.g. `Comments`.
+    fn plural(&self) -> &'static str {
+        match self {
+            Self::Comment => "Comments",
+            Self::Story => "Stories",
+        }
+    }
+}
+
+/// Contains a reader submission's author and body, trimmed and checked.
+#[derive(Debug)]
+pub struct Submission {
+    /// The name the reader submitted under, or `anonymous` if they did not give one.
+    pub author: String,
+    /// The body of the submission.
+    pub body: String,
+}
+
+/// Check a reader submission before it is queued for moderation. Returns `None` if the honeypot
+/// `website` field was filled in, which only bots do. Those submissions should be dropped, but
+/// still answered with `submitted`, so that bots cannot tell.
+pub fn check_submission(
+    kind: SubmissionKind,
+    request: &HttpRequest,
+    author: &str,
+    body: &str,
+    website: &str,
+) -> Result<Option<Submission>, StaccResponseError> {
+    if !website.is_empty() {
+        let ip_address = middleware::get_real_ip(request)
+            .map_or("UNKNOWN".to_string(), |ip_address| ip_address.to_string());
+        info!(
+            ip_address = logging::ip_address(&ip_address),
+            kind = kind.name(),
+            "dropping a submission that filled in the honeypot field"
+        );
+
+        return Ok(None);
+    }
+
+    let author = match author.trim() {
+        "" => "anonymous".to_string(),
+        author => author.to_string(),
+    };
+    let body = body.trim().to_string();
+
+    if author.chars().count() > *MAX_AUTHOR_LENGTH {
+        return Err(StaccResponseError::BadInput {
+            error: format!("Names may not exceed {} characters.", *MAX_AUTHOR_LENGTH),
+        });
+    }
+    if body.is_empty() || body.chars().count() > *MAX_BODY_LENGTH {
+        return Err(StaccResponseError::BadInput {
+            error: format!(
+                "{} must contain between 1 and {} characters.",
+                kind.plural(),
+                *MAX_BODY_LENGTH
+            ),
+        });
+    }
+
+    Ok(Some(Submission { author, body }))
+}
+
+/// The response to a submission that was queued for moderation, or dropped by `check_submission`.
+pub fn submitted(kind: SubmissionKind) -> HttpResponse {
+    HttpResponse::Accepted().json(Response {
+        message: format!("{} submitted for moderation.", kind.name()),
+        status_code: 202,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::test::TestRequest;
+
+    use super::*;
+
+    /// Check a submission made by a request without any headers.
+    fn check(
+        author: &str,
+        body: &str,
+        website: &str,
+    ) -> Result<Option<Submission>, StaccResponseError> {
+        check_submission(
+            SubmissionKind::Comment,
+            &TestRequest::default().to_http_request(),
+            author,
+            body,
+            website,
+        )
+    }
+
+    #[test]
+    fn author_and_body_are_trimmed() {
+        let submission = check("  Jo ", "\n Hello! \n", "").unwrap().unwrap();
+
+        assert_eq!(submission.author, "Jo");
+        assert_eq!(submission.body, "Hello!");
+    }
+
+    #[test]
+    fn blank_authors_are_anonymous() {
+        let submission = check("   ", "Hello!", "").unwrap().unwrap();
+
+        assert_eq!(submission.author, "anonymous");
+    }
+
+    #[test]
+    fn filled_in_honeypot_is_dropped() {
+        assert!(check("Jo", "Hello!", "https://spam.test/")
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn lengths_are_checked() {
+        for (author, body) in [
+            ("Jo", "   "),
+            ("Jo", &"a".repeat(*MAX_BODY_LENGTH + 1)),
+            (&"a".repeat(*MAX_AUTHOR_LENGTH + 1), "Hello!"),
+        ] {
+            assert!(matches!(
+                check(author, body, ""),
+                Err(StaccResponseError::BadInput { .. })
+            ));
+        }
+
+        assert!(check(
+            &"a".repeat(*MAX_AUTHOR_LENGTH),
+            &"a".repeat(*MAX_BODY_LENGTH),
+            ""
+        )
+        .unwrap()
+        .is_some());
+    }
+}
@@ -1,53 +1,179 @@
 //! Instantiate a connection to MongoDB.
 
-use mongodb::{Client, Collection};
+use std::{sync::Arc, time::Duration};
+
+use mongodb::{
+    bson::doc,
+    error::{Error, ErrorKind, WriteFailure},
+    options::{ClientOptions, IndexOptions},
+    Client, Collection, Database, IndexModel,
+};
+use tokio::time::sleep;
+use tracing::{info, warn};
 
 use crate::{
     errors::StaccError,
+    middleware::metrics::MongoCommandMetrics,
     models::{
-        data::{BackgroundGIF, Story},
-        post::PostData,
-        visitor::Visitor,
+        background::BackgroundGIF, comment::Comment, post::PostData, revision::PostRevision,
+        story::Story, subscriber::Subscriber, visitor::Visitor, webmention::Webmention,
     },
 };
 
-use super::environment::EnvironmentVariables;
+use super::config::Config;
+
+/// How many times to try connecting to MongoDB at startup before giving up.
+pub const CONNECT_ATTEMPTS: u32 = 6;
+
+/// How long to wait after the first failed connection attempt. This doubles after every attempt.
+const INITIAL_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// The longest to wait between connection attempts.
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(16);
+
+/// The server error code for a write that breaks a unique index.
+const DUPLICATE_KEY_ERROR: i32 = 11000;
 
 #[derive(Clone, Debug)]
 pub struct Mongo {
     /// The collection containing background GIFs.
     pub backgrounds_collection: Collection<BackgroundGIF>,
+    /// The collection containing post comments.
+    pub comments_collection: Collection<Comment>,
+    /// The database containing every collection.
+    pub database: Database,
     /// The collection containing posts.
     pub posts_collection: Collection<PostData>,
+    /// The collection containing post revisions.
+    pub revisions_collection: Collection<PostRevision>,
     /// The collection containing 404 page stories.
     pub stories_collection: Collection<Story>,
+    /// The collection containing newsletter subscribers.
+    pub subscribers_collection: Collection<Subscriber>,
     /// The collection containing visitor information.
     pub visitor_collection: Collection<Visitor>,
+    /// The collection containing Webmentions.
+    pub webmentions_collection: Collection<Webmention>,
 }
 
 impl Mongo {
+    /// Initialize a new MongoDB client and wait until MongoDB responds. Failed attempts are retried
+    /// with exponential backoff, so that the API can start alongside MongoDB.
+    pub async fn connect(config: &Config) -> Result<Self, StaccError> {
+        let mut delay = INITIAL_RETRY_DELAY;
+        let mut attempt = 1;
+
+        loop {
+            let result = match Self::init(config).await {
+                Ok(mongo) => mongo.ping().await.map(|_| mongo),
+                Err(error) => Err(error),
+            };
+
+            match result {
+                Ok(mongo) => return Ok(mongo),
+                Err(error) if attempt < CONNECT_ATTEMPTS => {
+                    warn!(
+                        attempt,
+                        %error,
+                        retry_in_secs = delay.as_secs(),
+                        "failed to connect to mongodb, retrying"
+                    );
+
+                    sleep(delay).await;
+                    delay = (delay * 2).min(MAX_RETRY_DELAY);
+                    attempt += 1;
+                }
+                Err(error) => return Err(error),
+            }
+        }
+    }
+
     /// Initialize a new MongoDB client.
-    pub async fn init() -> Result<Self, StaccError> {
-        let mongo_uri = format!(
-            "mongodb://{}:{}@{}",
-            EnvironmentVariables::MongoDBUser.env_var()?,
-            EnvironmentVariables::MongoDBPassword.env_var()?,
-            EnvironmentVariables::MongoDBURI.env_var()?,
-        );
-        let client = Client::with_uri_str(&mongo_uri).await?;
-        let database = client.database(&EnvironmentVariables::StaccDatabase.env_var()?);
+    async fn init(config: &Config) -> Result<Self, StaccError> {
+        let mut client_options = ClientOptions::parse(config.mongo.connection_string()).await?;
+        client_options.command_event_handler = Some(Arc::new(MongoCommandMetrics::default()));
+
+        let client = Client::with_options(client_options)?;
+        let database = client.database(&config.mongo.database);
+        let collections = &config.collections;
 
         Ok(Self {
-            backgrounds_collection: database.collection::<BackgroundGIF>(
-                &EnvironmentVariables::StaccBackgroundsCollectionName.env_var()?,
+            backgrounds_collection: database.collection::<BackgroundGIF>(&collections.backgrounds),
+            comments_collection: database.collection::<Comment>(&collections.comments),
+            database: database.clone(),
+            posts_collection: database.collection::<PostData>(&collections.posts),
+            revisions_collection: database.collection::<PostRevision>(&collections.revisions),
+            stories_collection: database.collection::<Story>(&collections.stories),
+            subscribers_collection: database.collection::<Subscriber>(&collections.subscribers),
+            visitor_collection: database.collection::<Visitor>(&collections.visitors),
+            webmentions_collection: database.collection::<Webmention>(&collections.webmentions),
+        })
+    }
+
+    /// Give every background and story stored before they had IDs an ID, using the string form
+    /// of its MongoDB `_id`, so that the ID never changes once it is assigned.
+    pub async fn backfill_ids(&self) {
+        for (collection, result) in [
+            (
+                "backgrounds",
+                backfill_id_field(&self.backgrounds_collection, "background_id").await,
             ),
-            posts_collection: database
-                .collection::<PostData>(&EnvironmentVariables::StaccPostsCollectionName.env_var()?),
-            stories_collection: database
-                .collection::<Story>(&EnvironmentVariables::StaccStoriesCollectionName.env_var()?),
-            visitor_collection: database.collection::<Visitor>(
-                &EnvironmentVariables::StaccVisitorsCollectionName.env_var()?,
+            (
+                "stories",
+                backfill_id_field(&self.stories_collection, "story_id").await,
             ),
-        })
+        ] {
+            match result {
+                Ok(0) => {}
+                Ok(backfilled) => info!(collection, backfilled, "backfilled ids"),
+                Err(error) => warn!(collection, %error, "failed to backfill ids"),
+            }
+        }
     }
+
+    /// Create the indexes that keep documents unique. Each post's revisions are numbered by reading
+    /// the latest one, so concurrent edits rely on the index to never share a number.
+    pub async fn create_indexes(&self) {
+        let index = IndexModel::builder()
+            .keys(doc! { "post_id": 1, "revision_number": 1 })
+            .options(IndexOptions::builder().unique(true).build())
+            .build();
+
+        if let Err(error) = self.revisions_collection.create_index(index, None).await {
+            warn!(collection = "revisions", %error, "failed to create index");
+        }
+    }
+
+    /// Ping the database to check that MongoDB is reachable.
+    pub async fn ping(&self) -> Result<(), StaccError> {
+        self.database.run_command(doc! { "ping": 1 }, None).await?;
+
+        Ok(())
+    }
+}
+
+/// Set the ID field of every document in the collection that does not have one to the string form
+/// of its `_id`. Returns the number of documents that were updated.
+async fn backfill_id_field<T>(
+    collection: &Collection<T>,
+    id_field: &str,
+) -> Result<u64, StaccError> {
+    let result = collection
+        .update_many(
+            doc! { id_field: { "$exists": false } },
+            vec![doc! { "$set": { id_field: { "$toString": "$_id" } } }],
+            None,
+        )
+        .await?;
+
+    Ok(result.modified_count)
+}
+
+/// Check whether a write failed because it would break a unique index.
+pub fn is_duplicate_key(error: &Error) -> bool {
+    matches!(
+        error.kind.as_ref(),
+        ErrorKind::Write(WriteFailure::WriteError(write_error))
+            if write_error.code == DUPLICATE_KEY_ERROR
+    )
 }
@@ -0,0 +1,106 @@
+//! Contains an in-memory pool of documents to choose from at random.
+
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, RwLock,
+    },
+    time::{Duration, Instant},
+};
+
+use futures_util::stream::TryStreamExt;
+use mongodb::{bson::Document, Collection};
+use serde::de::DeserializeOwned;
+
+/// Caches every document in a collection that matches a filter, so that choosing one at random
+/// does not require a round trip to MongoDB. The pool is reloaded once it is older than its
+/// maximum age, or after it has been invalidated because the documents changed.
+#[derive(Debug)]
+pub struct RandomPool<T> {
+    /// The cached documents, if they have been loaded and not invalidated.
+    documents: RwLock<Option<Loaded<T>>>,
+    /// The filter that documents must match to be in the pool.
+    filter: Document,
+    /// Counts how many times the pool has been invalidated. Documents loaded before an
+    /// invalidation may already be out of date, so they are not kept.
+    generation: AtomicU64,
+    /// How long the cached documents are used before they are reloaded.
+    max_age: Duration,
+}
+
+/// Contains documents loaded into a `RandomPool`.
+#[derive(Debug)]
+struct Loaded<T> {
+    /// The documents.
+    documents: Arc<Vec<T>>,
+    /// When the documents were loaded.
+    loaded_at: Instant,
+}
+
+impl<T> RandomPool<T>
+where
+    T: DeserializeOwned + Send + Sync + Unpin,
+{
+    /// Create a new, empty `RandomPool`. Nothing is loaded until the pool is first used.
+    pub fn new(filter: Document, max_age: Duration) -> Self {
+        Self {
+            documents: RwLock::new(None),
+            filter,
+            generation: AtomicU64::new(0),
+            max_age,
+        }
+    }
+
+    /// Get the documents in the pool, loading them from the collection if they are missing or
+    /// stale. The returned documents may be empty.
+    pub async fn documents(
+        &self,
+        collection: &Collection<T>,
+    ) -> Result<Arc<Vec<T>>, mongodb::error::Error> {
+        if let Some(loaded) = self
+            .documents
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .as_ref()
+            .filter(|loaded| loaded.loaded_at.elapsed() < self.max_age)
+        {
+            return Ok(loaded.documents.clone());
+        }
+
+        let generation = self.generation.load(Ordering::Acquire);
+        let documents: Arc<Vec<T>> = Arc::new(
+            collection
+                .find(self.filter.clone(), None)
+                .await?
+                .try_collect()
+                .await?,
+        );
+
+        let mut cached = self
+            .documents
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        // The pool was invalidated while loading, so these documents may be from before the change.
+        // They are still returned, but the next use loads them again.
+        if self.generation.load(Ordering::Acquire) == generation {
+            *cached = Some(Loaded {
+                documents: documents.clone(),
+                loaded_at: Instant::now(),
+            });
+        }
+
+        Ok(documents)
+    }
+
+    /// Drop the cached documents so that they are reloaded the next time the pool is used. Call
+    /// this whenever documents in the collection are added, removed, or changed.
+    pub fn invalidate(&self) {
+        let mut cached = self
+            .documents
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        self.generation.fetch_add(1, Ordering::AcqRel);
+        *cached = None;
+    }
+}
@@ -0,0 +1,30 @@
+//! Contains utilities for signing and verifying values with a secret.
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::errors::StaccError;
+
+/// HMAC-SHA256, used to sign values that are embedded in links.
+type HmacSha256 = Hmac<Sha256>;
+
+/// Sign a value with the secret, returning the hex-encoded signature.
+pub fn sign(secret: &str, value: &str) -> Result<String, StaccError> {
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes())?;
+    mac.update(value.as_bytes());
+
+    Ok(hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Check whether the hex-encoded signature is valid for the value and secret. The comparison is
+/// done in constant time.
+pub fn verify(secret: &str, value: &str, signature: &str) -> Result<bool, StaccError> {
+    let Ok(signature) = hex::decode(signature) else {
+        return Ok(false);
+    };
+
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes())?;
+    mac.update(value.as_bytes());
+
+    Ok(mac.verify_slice(&signature).is_ok())
+}
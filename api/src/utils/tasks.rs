@@ -0,0 +1,62 @@
+//! Contains the tracker for work that outlives the request that started it, such as logging
+//! visitors, sending Webmentions, and emailing subscribers.
+
+use std::{future::Future, time::Duration};
+
+use tokio::{runtime::Handle, time::timeout};
+use tokio_util::task::TaskTracker;
+use tracing::{info, warn, Instrument};
+
+/// Runs background work on the main runtime and keeps track of it, so that it can be finished
+/// before the API exits.
+///
+/// Actix Web stops each worker's runtime once the worker shuts down, which would cancel any work
+/// spawned on it. Spawning on the main runtime instead lets the work outlive the workers.
+#[derive(Clone)]
+pub struct BackgroundTasks {
+    /// The main runtime, which runs until the API exits.
+    handle: Handle,
+    /// Tracks every task that has not finished yet.
+    tracker: TaskTracker,
+}
+
+impl BackgroundTasks {
+    /// Create a new tracker. This must be called from the main runtime.
+    pub fn new() -> Self {
+        Self {
+            handle: Handle::current(),
+            tracker: TaskTracker::new(),
+        }
+    }
+
+    /// Run a task in the background. The task is attached to the current span, so it is logged
+    /// with the ID of the request that started it.
+    pub fn spawn<F>(&self, task: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        self.tracker.spawn_on(task.in_current_span(), &self.handle);
+    }
+
+    /// Stop accepting new tasks and wait for the remaining ones to finish, giving up after
+    /// `grace_period`.
+    pub async fn shutdown(&self, grace_period: Duration) {
+        self.tracker.close();
+
+        if self.tracker.is_empty() {
+            return;
+        }
+
+        info!(
+            tasks = self.tracker.len(),
+            "waiting for background tasks to finish"
+        );
+
+        if timeout(grace_period, self.tracker.wait()).await.is_err() {
+            warn!(
+                tasks = self.tracker.len(),
+                "gave up waiting for background tasks to finish"
+            );
+        }
+    }
+}
@@ -0,0 +1,658 @@
+//! Contains utilities for sending and verifying Webmentions.
+
+use std::{
+    collections::HashSet,
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+    time::Duration,
+};
+
+use hyper::client::connect::dns::Name;
+use lazy_static::lazy_static;
+use regex::Regex;
+use reqwest::{
+    dns::{Addrs, Resolve, Resolving},
+    header,
+    redirect::Policy,
+    Client, Proxy, Response, Url,
+};
+use scraper::{ElementRef, Html, Selector};
+use tracing::{error, info};
+use url::Host;
+
+use crate::{errors::StaccError, models::webmention::MentionType};
+
+use super::config::Config;
+
+lazy_static! {
+    /// Matches absolute HTTP(S) links within a Markdown post body.
+    static ref LINK_REGEX: Regex =
+        Regex::new(r#"https?://[^\s<>()\[\]"'`]+"#).expect("INVALID LINK REGEX");
+    /// The maximum number of characters stored for a reply's content.
+    static ref MAX_REPLY_LENGTH: usize = 500;
+    /// The maximum number of redirects followed for a single request.
+    static ref MAX_REDIRECTS: usize = 5;
+    /// The maximum number of bytes read from a source or target page.
+    static ref MAX_BODY_BYTES: usize = 1024 * 1024;
+}
+
+/// Contains everything that was extracted from a source page that links to a post.
+#[derive(Debug)]
+pub struct VerifiedSource {
+    /// The name of the source page's author, if it could be found.
+    pub author_name: Option<String>,
+    /// The text content of the source page's entry. Only set for replies.
+    pub content: Option<String>,
+    /// The type of this mention.
+    pub mention_type: MentionType,
+    /// The title of the source page, if it has one.
+    pub source_title: Option<String>,
+}
+
+/// The HTTP client used to verify incoming Webmentions and to send outgoing ones.
+///
+/// Anyone can make the API fetch a source page, so the client only connects to public addresses.
+/// Hosts are resolved by `PublicResolver`, which drops every address that is not public, and hosts
+/// given as IP addresses are checked before the first request and on every redirect. With a proxy,
+/// resolving hosts is left to the proxy.
+#[derive(Clone, Debug)]
+pub struct WebmentionClient {
+    /// The underlying `reqwest` `Client`.
+    client: Client,
+}
+
+impl WebmentionClient {
+    /// Create a new `WebmentionClient`. Every request is routed through the `proxy` URL if one is
+    /// provided, which allows a local stand-in to play the part of the rest of the web in tests.
+    pub fn new(proxy: Option<String>) -> Result<Self, StaccError> {
+        let mut builder = Client::builder()
+            .redirect(Policy::custom(|attempt| {
+                if attempt.previous().len() > *MAX_REDIRECTS {
+                    attempt.error("too many redirects")
+                } else if let Err(error) = check_url(attempt.url()) {
+                    attempt.error(error)
+                } else {
+                    attempt.follow()
+                }
+            }))
+            .timeout(Duration::from_secs(10))
+            .user_agent(concat!("stacc-webmention/", env!("CARGO_PKG_VERSION")));
+
+        builder = match proxy {
+            Some(proxy) => builder.proxy(Proxy::all(proxy)?),
+            None => builder.no_proxy().dns_resolver(Arc::new(PublicResolver)),
+        };
+
+        Ok(Self {
+            client: builder.build()?,
+        })
+    }
+
+    /// Fetch the source page and check whether it links to the target. Returns `None` if the
+    /// source does not link to the target.
+    pub async fn verify(
+        &self,
+        source: &str,
+        target: &str,
+    ) -> Result<Option<VerifiedSource>, StaccError> {
+        let response = self
+            .client
+            .get(parse_public_url(source)?)
+            .send()
+            .await?
+            .error_for_status()?;
+        let body = read_body(response).await?;
+
+        Ok(parse_source(&body, target))
+    }
+
+    /// Discover the target's Webmention endpoint and notify it that the source links to it. Targets
+    /// that do not advertise an endpoint are skipped.
+    pub async fn send(&self, source: &str, target: &str) -> Result<(), StaccError> {
+        match self.discover_endpoint(target).await? {
+            Some(endpoint) => {
+                info!(%endpoint, target, "sending webmention");
+
+                check_url(&endpoint)?;
+
+                self.client
+                    .post(endpoint)
+                    .form(&[("source", source), ("target", target)])
+                    .send()
+                    .await?
+                    .error_for_status()?;
+            }
+            None => info!(target, "no webmention endpoint found, skipping"),
+        }
+
+        Ok(())
+    }
+
+    /// Find the Webmention endpoint advertised by the target. `Link` headers take priority over
+    /// `<link>` and `<a>` elements in the target's HTML.
+    async fn discover_endpoint(&self, target: &str) -> Result<Option<Url>, StaccError> {
+        let response = self
+            .client
+            .get(parse_public_url(target)?)
+            .send()
+            .await?
+            .error_for_status()?;
+        let base_url = response.url().clone();
+
+        let header_endpoint = response
+            .headers()
+            .get_all(header::LINK)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .find_map(parse_link_header);
+        if let Some(href) = header_endpoint {
+            return Ok(base_url.join(&href).ok());
+        }
+
+        let body = read_body(response).await?;
+
+        Ok(find_html_endpoint(&body).and_then(|href| base_url.join(&href).ok()))
+    }
+}
+
+/// Read a page's body as text. Anyone can make the API fetch a source page, so reading stops with
+/// an error once the body grows past `MAX_BODY_BYTES`.
+async fn read_body(mut response: Response) -> Result<String, StaccError> {
+    let url = response.url().clone();
+    let too_large =
+        || StaccError::Webmention(format!("{url} is larger than {} bytes", *MAX_BODY_BYTES));
+
+    if response
+        .content_length()
+        .is_some_and(|length| length > *MAX_BODY_BYTES as u64)
+    {
+        return Err(too_large());
+    }
+
+    let mut body: Vec<u8> = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+        if body.len() + chunk.len() > *MAX_BODY_BYTES {
+            return Err(too_large());
+        }
+        body.extend_from_slice(&chunk);
+    }
+
+    Ok(String::from_utf8_lossy(&body).into_owned())
+}
+
+/// Resolves hosts for the `WebmentionClient`, keeping only public addresses. Hosts that only
+/// resolve to loopback, private, link-local, or otherwise internal addresses fail to resolve.
+struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let addresses: Vec<SocketAddr> = tokio::net::lookup_host((name.as_str(), 0))
+                .await?
+                .filter(|address| is_public_address(address.ip()))
+                .collect();
+
+            if addresses.is_empty() {
+                return Err(format!("{name} does not resolve to a public address").into());
+            }
+
+            let addresses: Addrs = Box::new(addresses.into_iter());
+            Ok(addresses)
+        })
+    }
+}
+
+/// Parse a URL and check it with `check_url`.
+fn parse_public_url(url: &str) -> Result<Url, StaccError> {
+    let url = Url::parse(url)?;
+    check_url(&url)?;
+
+    Ok(url)
+}
+
+/// Check that a URL uses HTTP(S) and, if its host is an IP address, that the address is public.
+/// Hosts given as names are checked by `PublicResolver` when they are resolved.
+fn check_url(url: &Url) -> Result<(), StaccError> {
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(StaccError::ForbiddenUrl(url.to_string()));
+    }
+
+    let ip_address = match url.host() {
+        Some(Host::Ipv4(ip_address)) => IpAddr::V4(ip_address),
+        Some(Host::Ipv6(ip_address)) => IpAddr::V6(ip_address),
+        Some(Host::Domain(_)) => return Ok(()),
+        None => return Err(StaccError::ForbiddenUrl(url.to_string())),
+    };
+
+    if is_public_address(ip_address) {
+        Ok(())
+    } else {
+        Err(StaccError::ForbiddenUrl(url.to_string()))
+    }
+}
+
+/// Check whether an address is on the public internet. Loopback, private, shared (carrier-grade
+/// NAT), link-local, unique local, multicast, documentation, and reserved addresses are not, and
+/// neither are IPv4-mapped IPv6 addresses of any of these.
+fn is_public_address(ip_address: IpAddr) -> bool {
+    match ip_address {
+        IpAddr::V4(ip_address) => {
+            let [first, second, ..] = ip_address.octets();
+
+            !(first == 0
+                || ip_address.is_loopback()
+                || ip_address.is_private()
+                || (first == 100 && second & 0b1100_0000 == 64)
+                || ip_address.is_link_local()
+                || ip_address.is_multicast()
+                || ip_address.is_documentation()
+                || first >= 240)
+        }
+        IpAddr::V6(ip_address) => match ip_address.to_ipv4_mapped() {
+            Some(ip_address) => is_public_address(IpAddr::V4(ip_address)),
+            None => {
+                !(ip_address.is_unspecified()
+                    || ip_address.is_loopback()
+                    || ip_address.is_unique_local()
+                    || ip_address.is_unicast_link_local()
+                    || ip_address.is_multicast())
+            }
+        },
+    }
+}
+
+/// Get the public URL of a post.
+pub fn post_url(config: &Config, post_id: &str) -> String {
+    config.site_url(&format!("/blog/post/{post_id}"))
+}
+
+/// Send a Webmention to every link found in a post's body.
+pub async fn send_webmentions(webmention_client: &WebmentionClient, source: &str, body: &str) {
+    for target in extract_links(body) {
+        if let Err(error) = webmention_client.send(source, &target).await {
+            error!(target, %error, "failed to send webmention");
+        }
+    }
+}
+
+/// Extract every unique absolute link from a Markdown post body.
+fn extract_links(body: &str) -> Vec<String> {
+    let mut seen = HashSet::new();
+
+    LINK_REGEX
+        .find_iter(body)
+        .map(|link| {
+            link.as_str()
+                .trim_end_matches(['.', ',', ';', ':', '!', '?'])
+        })
+        .filter(|link| seen.insert(link.to_string()))
+        .map(|link| link.to_string())
+        .collect()
+}
+
+/// Find the Webmention endpoint in a single `Link` header value, if there is one.
+fn parse_link_header(value: &str) -> Option<String> {
+    value.split(',').find_map(|link| {
+        let mut parts = link.split(';');
+        let href = parts.next()?.trim().strip_prefix('<')?.strip_suffix('>')?;
+
+        parts
+            .filter_map(|parameter| parameter.trim().strip_prefix("rel="))
+            .any(|rel| {
+                rel.trim_matches('"')
+                    .split_whitespace()
+                    .any(|rel| rel.eq_ignore_ascii_case("webmention"))
+            })
+            .then(|| href.to_string())
+    })
+}
+
+/// Find the Webmention endpoint in the first `<link>` or `<a>` element with `rel="webmention"`.
+fn find_html_endpoint(body: &str) -> Option<String> {
+    let selector = Selector::parse("link[rel][href], a[rel][href]").ok()?;
+
+    Html::parse_document(body)
+        .select(&selector)
+        .find(|element| {
+            element
+                .value()
+                .attr("rel")
+                .is_some_and(|rel| rel.split_whitespace().any(|rel| rel == "webmention"))
+        })
+        .and_then(|element| element.value().attr("href"))
+        .map(|href| href.to_string())
+}
+
+/// Parse the source page and extract the mention's metadata if the page links to the target.
+fn parse_source(body: &str, target: &str) -> Option<VerifiedSource> {
+    let document = Html::parse_document(body);
+    let target = target.trim_end_matches('/');
+
+    let link_selector = Selector::parse("a[href]").ok()?;
+    let links: Vec<ElementRef> = document
+        .select(&link_selector)
+        .filter(|element| {
+            element
+                .value()
+                .attr("href")
+                .is_some_and(|href| href.trim_end_matches('/') == target)
+        })
+        .collect();
+
+    if links.is_empty() {
+        return None;
+    }
+
+    let has_class = |class: &str| {
+        links
+            .iter()
+            .any(|element| element.value().classes().any(|name| name == class))
+    };
+    let mention_type = if has_class("u-in-reply-to") {
+        MentionType::Reply
+    } else if has_class("u-like-of") {
+        MentionType::Like
+    } else {
+        MentionType::Mention
+    };
+
+    let content = match mention_type {
+        MentionType::Reply => {
+            select_text(&document, ".h-entry .e-content, .e-content").map(|content| {
+                match content.char_indices().nth(*MAX_REPLY_LENGTH) {
+                    Some((index, _)) => format!("{}…", &content[..index]),
+                    None => content,
+                }
+            })
+        }
+        _ => None,
+    };
+
+    Some(VerifiedSource {
+        author_name: select_text(&document, ".p-author .p-name, .h-card .p-name, .p-author"),
+        content,
+        mention_type,
+        source_title: select_text(&document, "title"),
+    })
+}
+
+/// Get the whitespace-normalized text of the first element matching the selector.
+fn select_text(document: &Html, selector: &str) -> Option<String> {
+    let selector = Selector::parse(selector).ok()?;
+
+    document
+        .select(&selector)
+        .next()
+        .map(|element| {
+            element
+                .text()
+                .flat_map(|text| text.split_whitespace())
+                .collect::<Vec<&str>>()
+                .join(" ")
+        })
+        .filter(|text| !text.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    use super::*;
+
+    /// The post that every source in these tests mentions.
+    const TARGET: &str = "https://example.com/blog/post/hello";
+
+    /// Start a local stand-in for the rest of the web, and a client that routes every request
+    /// through it. The stand-in answers each request with the response `respond` gives for the
+    /// requested URL, and counts the requests it receives.
+    async fn stand_in(respond: fn(&str) -> String) -> (WebmentionClient, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let proxy = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(AtomicUsize::new(0));
+
+        let counter = requests.clone();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                counter.fetch_add(1, Ordering::SeqCst);
+
+                let mut request = Vec::new();
+                let mut buffer = [0; 1024];
+                while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+                    match stream.read(&mut buffer).await {
+                        Ok(0) | Err(_) => break,
+                        Ok(read) => request.extend_from_slice(&buffer[..read]),
+                    }
+                }
+
+                // A proxied request line holds the absolute URL, e.g. `GET http://a.test/ HTTP/1.1`.
+                let request = String::from_utf8_lossy(&request);
+                let url = request.split_whitespace().nth(1).unwrap_or_default();
+
+                let _ = stream.write_all(respond(url).as_bytes()).await;
+                let _ = stream.shutdown().await;
+            }
+        });
+
+        (WebmentionClient::new(Some(proxy)).unwrap(), requests)
+    }
+
+    /// Create a response with an HTML body.
+    fn html(body: &str) -> String {
+        format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            body.len()
+        )
+    }
+
+    /// Create a response redirecting to a location.
+    fn redirect(location: &str) -> String {
+        format!(
+            "HTTP/1.1 302 Found\r\nLocation: {location}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+        )
+    }
+
+    /// A source page replying to the target.
+    fn reply(_url: &str) -> String {
+        html(&format!(
+            r#"<title>A reply</title>
+            <div class="h-entry">
+                <a class="p-author h-card" href="https://source.test/"><span class="p-name">Jo</span></a>
+                <a class="u-in-reply-to" href="{TARGET}">Hello</a>
+                <div class="e-content">Nice   post!</div>
+            </div>"#
+        ))
+    }
+
+    /// Check whether an error is a refused redirect.
+    fn is_refused_redirect(error: &StaccError) -> bool {
+        matches!(error, StaccError::Reqwest(error) if error.is_redirect())
+    }
+
+    #[tokio::test]
+    async fn reply_is_verified_through_the_stand_in() {
+        let (client, requests) = stand_in(reply).await;
+
+        let source = client
+            .verify("http://source.test/reply", TARGET)
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert!(matches!(source.mention_type, MentionType::Reply));
+        assert_eq!(source.author_name.as_deref(), Some("Jo"));
+        assert_eq!(source.content.as_deref(), Some("Nice post!"));
+        assert_eq!(source.source_title.as_deref(), Some("A reply"));
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn source_without_a_link_to_the_target_is_not_verified() {
+        let (client, _) = stand_in(|_| html(r#"<a href="https://example.com/">Home</a>"#)).await;
+
+        let source = client.verify("http://source.test/", TARGET).await.unwrap();
+
+        assert!(source.is_none());
+    }
+
+    #[tokio::test]
+    async fn oversized_sources_are_not_read() {
+        let (client, _) = stand_in(|_| html(&"a".repeat(*MAX_BODY_BYTES + 1))).await;
+
+        let result = client.verify("http://source.test/", TARGET).await;
+
+        assert!(matches!(result, Err(StaccError::Webmention(_))));
+    }
+
+    #[tokio::test]
+    async fn oversized_sources_without_a_length_are_cut_off() {
+        let (client, _) = stand_in(|_| {
+            let body = "a".repeat(*MAX_BODY_BYTES + 1);
+
+            format!("HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nConnection: close\r\n\r\n{body}")
+        })
+        .await;
+
+        let result = client.verify("http://source.test/", TARGET).await;
+
+        assert!(matches!(result, Err(StaccError::Webmention(_))));
+    }
+
+    #[tokio::test]
+    async fn internal_sources_are_never_requested() {
+        let (client, requests) = stand_in(reply).await;
+
+        for source in [
+            "http://127.0.0.1:27017/",
+            "http://2130706433/",
+            "http://169.254.169.254/latest/meta-data/",
+            "http://10.0.0.1/",
+            "http://172.16.0.1/",
+            "http://192.168.1.1/",
+            "http://100.64.0.1/",
+            "http://0.0.0.0/",
+            "http://[::1]/",
+            "http://[fd00::1]/",
+            "http://[fe80::1]/",
+            "http://[::ffff:127.0.0.1]/",
+            "ftp://source.test/",
+        ] {
+            let result = client.verify(source, TARGET).await;
+
+            assert!(
+                matches!(result, Err(StaccError::ForbiddenUrl(_))),
+                "{source} was not refused: {result:?}"
+            );
+        }
+
+        assert_eq!(requests.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn redirects_to_internal_addresses_are_refused() {
+        let (client, requests) =
+            stand_in(|_| redirect("http://169.254.169.254/latest/meta-data/")).await;
+
+        let error = client
+            .verify("http://source.test/", TARGET)
+            .await
+            .unwrap_err();
+
+        assert!(is_refused_redirect(&error), "{error:?}");
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn redirects_to_public_hosts_are_followed() {
+        let (client, requests) = stand_in(|url| match url {
+            "http://source.test/" => redirect("http://mirror.test/reply"),
+            _ => reply(url),
+        })
+        .await;
+
+        let source = client.verify("http://source.test/", TARGET).await.unwrap();
+
+        assert!(source.is_some());
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn redirect_loops_are_cut_short() {
+        let (client, requests) = stand_in(|_| redirect("http://source.test/")).await;
+
+        let error = client
+            .verify("http://source.test/", TARGET)
+            .await
+            .unwrap_err();
+
+        assert!(is_refused_redirect(&error), "{error:?}");
+        assert_eq!(requests.load(Ordering::SeqCst), *MAX_REDIRECTS + 1);
+    }
+
+    #[tokio::test]
+    async fn internal_endpoints_are_never_notified() {
+        let (client, requests) = stand_in(|_| {
+            "HTTP/1.1 200 OK\r\nLink: <http://127.0.0.1:27017/>; rel=\"webmention\"\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                .to_string()
+        })
+        .await;
+
+        let result = client.send(TARGET, "http://target.test/post").await;
+
+        assert!(
+            matches!(result, Err(StaccError::ForbiddenUrl(_))),
+            "{result:?}"
+        );
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn resolver_drops_internal_addresses() {
+        let result = PublicResolver.resolve("localhost".parse().unwrap()).await;
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn public_addresses() {
+        for ip_address in [
+            "1.1.1.1",
+            "8.8.8.8",
+            "2606:4700:4700::1111",
+            "::ffff:1.1.1.1",
+        ] {
+            assert!(
+                is_public_address(ip_address.parse().unwrap()),
+                "{ip_address}"
+            );
+        }
+
+        for ip_address in [
+            "0.1.2.3",
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.31.255.255",
+            "192.168.0.1",
+            "100.127.255.255",
+            "169.254.169.254",
+            "192.0.2.1",
+            "224.0.0.1",
+            "255.255.255.255",
+            "::",
+            "::1",
+            "fc00::1",
+            "fe80::1",
+            "ff02::1",
+            "::ffff:10.0.0.1",
+        ] {
+            assert!(
+                !is_public_address(ip_address.parse().unwrap()),
+                "{ip_address}"
+            );
+        }
+    }
+}
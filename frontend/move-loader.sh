@@ -0,0 +1,24 @@
+#!/bin/sh
+# Move the inline `<script type="module">` that Trunk writes into `index.html` to load the WASM
+# bundle into `loader-<hash>.js`, and point `index.html` at it. Trunk runs this after every build
+# (see `Trunk.toml`). The file is named after its contents like the rest of Trunk's output, so the
+# service worker drops old loaders along with the bundles they load.
+
+set -eu
+
+cd "$TRUNK_STAGING_DIR"
+
+perl -0777 -i -pe '
+    use Digest::SHA qw(sha256_hex);
+
+    s{<script type="module">(.*?)</script>}{
+        my $loader = $1;
+        my $name = "loader-" . substr(sha256_hex($loader), 0, 16) . ".js";
+
+        open(my $file, ">", $name) or die "Unable to write $name: $!\n";
+        print $file $loader;
+        close($file) or die "Unable to write $name: $!\n";
+
+        qq{<script type="module" src="/$name"></script>};
+    }se or die "No inline loader found in index.html!\n";
+' index.html
@@ -0,0 +1,94 @@
+//! Contains the `use_api` hook, which runs an API request and tracks its state.
+
+use std::{future::Future, rc::Rc};
+
+use yew::prelude::*;
+
+use crate::models::response::Response;
+
+/// The state of an API request.
+#[derive(Debug, PartialEq)]
+pub struct ApiState<T> {
+    /// The response data, if the request succeeded.
+    pub data: Option<Rc<T>>,
+    /// The error, if the request failed.
+    pub error: Option<Response>,
+    /// Whether the request is still running.
+    pub loading: bool,
+}
+
+impl<T> Clone for ApiState<T> {
+    fn clone(&self) -> Self {
+        Self {
+            data: self.data.clone(),
+            error: self.error.clone(),
+            loading: self.loading,
+        }
+    }
+}
+
+impl<T> Default for ApiState<T> {
+    fn default() -> Self {
+        Self {
+            data: None,
+            error: None,
+            loading: true,
+        }
+    }
+}
+
+/// Run an API request when the component mounts, and again whenever `deps` changes. Only the
+/// response to the latest `deps` is kept, so a slow response to earlier `deps` cannot overwrite a
+/// newer one, and responses that arrive after the component unmounts are dropped.
+#[hook]
+pub fn use_api<T, D, F, Fut>(deps: D, request: F) -> ApiState<T>
+where
+    T: 'static,
+    D: Clone + PartialEq + 'static,
+    F: FnOnce(D) -> Fut + 'static,
+    Fut: Future<Output = Result<T, Response>> + 'static,
+{
+    let state = use_state(ApiState::default);
+    // Bumped whenever `deps` changes or the component unmounts, which makes every request that is
+    // still running stale.
+    let generation = use_mut_ref(|| 0_u64);
+    {
+        let state = state.clone();
+
+        use_effect_with_deps(
+            move |deps| {
+                if !state.loading {
+                    state.set(ApiState::default());
+                }
+
+                let current = *generation.borrow();
+                let deps = deps.clone();
+                let request_generation = generation.clone();
+                wasm_bindgen_futures::spawn_local(async move {
+                    let result = request(deps).await;
+                    if *request_generation.borrow() != current {
+                        return;
+                    }
+
+                    state.set(match result {
+                        Ok(data) => ApiState {
+                            data: Some(Rc::new(data)),
+                            error: None,
+                            loading: false,
+                        },
+                        Err(error) => ApiState {
+                            data: None,
+                            error: Some(error),
+                            loading: false,
+                        },
+                    });
+                });
+
+                move || *generation.borrow_mut() += 1
+            },
+            deps,
+        );
+    }
+
+    (*state).clone()
+}
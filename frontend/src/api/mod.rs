@@ -0,0 +1,209 @@
+//! Contains a typed client for the API. Every request shares the same timeout, retry, and error
+//! handling, and errors are always returned as a `Response` that can be shown to the reader.
+
+pub mod hook;
+
+use futures_util::future::{select, Either};
+use gloo_console::error;
+use gloo_net::http::Request;
+use gloo_timers::future::TimeoutFuture;
+use serde::{de::DeserializeOwned, Serialize};
+use web_sys::AbortController;
+
+use crate::models::{
+    background::Background,
+    blog::{AllPosts, PostData},
+    chicago::ChicagoMapData,
+    comment::{AllComments, NewComment},
+    newsletter::{NewSubscriber, Unsubscribe},
+    response::Response,
+    revision::{PostRevisions, RevisionDiff},
+    story::{Story, StoryPage},
+    webmention::AllWebmentions,
+};
+
+/// How long to wait for the API before giving up on a request, in milliseconds.
+const TIMEOUT_MS: u32 = 15_000;
+
+/// How many times to retry a request that failed for a reason that may be temporary.
+const RETRIES: u32 = 2;
+
+/// How long to wait before the first retry, in milliseconds. This doubles after every retry.
+const RETRY_DELAY_MS: u32 = 500;
+
+/// Get every blog post.
+pub async fn get_posts() -> Result<AllPosts, Response> {
+    get("/api/blog/posts").await
+}
+
+/// Get a single blog post.
+pub async fn get_post(post_id: &str) -> Result<PostData, Response> {
+    get(&format!("/api/blog/post/{post_id}")).await
+}
+
+/// Get every revision of a blog post.
+pub async fn get_revisions(post_id: &str) -> Result<PostRevisions, Response> {
+    get(&format!("/api/blog/post/{post_id}/revisions")).await
+}
+
+/// Get the line diff between two revisions of a blog post.
+pub async fn get_revision_diff(
+    post_id: &str,
+    from: i32,
+    to: i32,
+) -> Result<RevisionDiff, Response> {
+    get(&format!(
+        "/api/blog/post/{post_id}/revisions/diff?from={from}&to={to}"
+    ))
+    .await
+}
+
+/// Get every approved comment on a blog post.
+pub async fn get_comments(post_id: &str) -> Result<AllComments, Response> {
+    get(&format!("/api/blog/post/{post_id}/comments")).await
+}
+
+/// Submit a comment on a blog post for moderation.
+pub async fn submit_comment(post_id: &str, new_comment: &NewComment) -> Result<Response, Response> {
+    post(&format!("/api/blog/post/{post_id}/comments"), new_comment).await
+}
+
+/// Get every verified Webmention of a blog post.
+pub async fn get_webmentions(post_id: &str) -> Result<AllWebmentions, Response> {
+    get(&format!("/api/blog/post/{post_id}/webmentions")).await
+}
+
+/// Subscribe to the newsletter. The API emails a confirmation link.
+pub async fn subscribe(new_subscriber: &NewSubscriber) -> Result<Response, Response> {
+    post("/api/subscribe", new_subscriber).await
+}
+
+/// Confirm a newsletter subscription with the token from the confirmation email.
+pub async fn confirm_subscription(token: &str) -> Result<Response, Response> {
+    get(&format!("/api/confirm/{token}")).await
+}
+
+/// Unsubscribe from the newsletter with the signed email address from a newsletter email.
+pub async fn unsubscribe(unsubscribe: &Unsubscribe) -> Result<Response, Response> {
+    post("/api/unsubscribe", unsubscribe).await
+}
+
+/// Get a random background GIF.
+pub async fn get_background() -> Result<Background, Response> {
+    get("/api/background").await
+}
+
+/// Get a random 404 page story.
+pub async fn get_story() -> Result<Story, Response> {
+    get("/api/story").await
+}
+
+/// Get a single story by its ID.
+pub async fn get_story_by_id(story_id: &str) -> Result<Story, Response> {
+    get(&format!("/api/story/{story_id}")).await
+}
+
+/// Get a page of the story archive.
+pub async fn get_stories(page: u64) -> Result<StoryPage, Response> {
+    get(&format!("/api/stories?page={page}")).await
+}
+
+/// Get the data plotted on the Chicago map.
+pub async fn get_chiraq() -> Result<ChicagoMapData, Response> {
+    get("/api/chiraq").await
+}
+
+/// Send a `GET` request to the API and parse the response, retrying failures that may be temporary.
+async fn get<T: DeserializeOwned>(url: &str) -> Result<T, Response> {
+    let mut delay = RETRY_DELAY_MS;
+    let mut attempt = 0;
+
+    loop {
+        match send_once(Request::get(url)).await {
+            Err(Failure::Temporary(error)) if attempt < RETRIES => {
+                error!(format!(
+                    "REQUEST TO {url} FAILED, RETRYING: {}",
+                    error.message
+                ));
+
+                TimeoutFuture::new(delay).await;
+                delay *= 2;
+                attempt += 1;
+            }
+            Err(Failure::Temporary(error) | Failure::Permanent(error)) => return Err(error),
+            Ok(data) => return Ok(data),
+        }
+    }
+}
+
+/// Send a `POST` request with a JSON body to the API and parse the response. These are never
+/// retried, since a request that timed out may still have gone through.
+async fn post<B: Serialize, T: DeserializeOwned>(url: &str, body: &B) -> Result<T, Response> {
+    let request = Request::post(url).json(body).map_err(|error| {
+        Response::status_500_with_message(format!("UNABLE TO SERIALIZE THE REQUEST: {error}"))
+    })?;
+
+    send_once(request)
+        .await
+        .map_err(|(Failure::Temporary(error) | Failure::Permanent(error))| error)
+}
+
+/// Why a request failed.
+enum Failure {
+    /// The request may succeed if it is retried, e.g. it timed out or the API is unavailable.
+    Temporary(Response),
+    /// The request will fail again if it is retried, e.g. the post does not exist.
+    Permanent(Response),
+}
+
+/// Send a single request to the API and parse the response.
+async fn send_once<T: DeserializeOwned>(request: Request) -> Result<T, Failure> {
+    let controller = AbortController::new().ok();
+    let request = request
+        .abort_signal(controller.as_ref().map(AbortController::signal).as_ref())
+        .send();
+
+    let response = match select(Box::pin(request), TimeoutFuture::new(TIMEOUT_MS)).await {
+        Either::Left((Ok(response), _)) => response,
+        Either::Left((Err(error), _)) => {
+            return Err(Failure::Temporary(Response::status_500_with_message(
+                format!("UNABLE TO REACH THE API: {error}"),
+            )))
+        }
+        Either::Right(_) => {
+            if let Some(controller) = controller {
+                controller.abort();
+            }
+
+            return Err(Failure::Temporary(Response {
+                code: Some("timeout".to_string()),
+                message: "The API took too long to respond. Try again later.".to_string(),
+                status_code: 504,
+            }));
+        }
+    };
+
+    if response.ok() {
+        return response.json::<T>().await.map_err(|error| {
+            Failure::Permanent(Response::status_500_with_message(format!(
+                "UNABLE TO PARSE THE API RESPONSE: {error}"
+            )))
+        });
+    }
+
+    let status_code = response.status();
+    let error = response
+        .json::<Response>()
+        .await
+        .unwrap_or_else(|_| Response {
+            code: None,
+            message: format!("The API responded with HTTP {status_code}."),
+            status_code,
+        });
+
+    if matches!(status_code, 502..=504) {
+        Err(Failure::Temporary(error))
+    } else {
+        Err(Failure::Permanent(error))
+    }
+}
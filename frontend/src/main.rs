@@ -6,11 +6,20 @@ use yew::prelude::*;
 use yew_router::prelude::*;
 
 use pages::{
-    about::About, blog::Blog, not_found::NotFound, post_view::PostView, root::Root,
+    about::About,
+    blog::Blog,
+    newsletter::{NewsletterConfirm, NewsletterUnsubscribe},
+    not_found::NotFound,
+    offline::OfflineBanner,
+    post_view::PostView,
+    revisions::Revisions,
+    root::Root,
+    stories::StoryArchive,
     violence::Violence,
 };
 use router::Route;
 
+mod api;
 mod errors;
 mod models;
 mod pages;
@@ -30,9 +39,12 @@ fn app() -> Html {
 
     html! {
         <div class="crt" style="display: flex; flex-direction: column; min-height: 100vh;">
+          <OfflineBanner />
           <BrowserRouter>
             <Switch<Route> render={switch} />
           </BrowserRouter>
+          <div class="background-fade" id="background-fade"></div>
+          <div class="background-attribution" id="background-attribution"></div>
           <footer>
             <small class="footer-small">
               { format!("v{version_number} | est. 2023 |") }
@@ -52,14 +64,20 @@ fn switch(route: Route) -> Html {
     match route {
         Route::About => html! { <About /> },
         Route::Blog => html! { <Blog /> },
+        Route::NewsletterConfirm { token } => html! { <NewsletterConfirm token={token} /> },
+        Route::NewsletterUnsubscribe => html! { <NewsletterUnsubscribe /> },
         Route::NotFound => html! { <NotFound /> },
+        Route::PostRevisions { post_id } => html! { <Revisions post_id={post_id} /> },
         Route::PostView { post_id } => html! { <PostView post_id={post_id} /> },
         Route::Root => html! { <Root /> },
+        Route::Stories => html! { <StoryArchive /> },
         Route::Violence => html! { <Violence /> },
     }
 }
 
 /// Run the frontend via `yew`.
 fn main() {
+    utils::service_worker::register_service_worker();
+
     yew::Renderer::<Main>::new().render();
 }
@@ -0,0 +1,18 @@
+//! Contains all models pertaining to background GIFs.
+
+use serde::Deserialize;
+
+/// The background GIF chosen by the API, along with its attribution.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Background {
+    /// The GIF's dominant color as a `#rrggbb` hex code.
+    pub dominant_color: Option<String>,
+    /// The link to the background GIF.
+    pub link: String,
+    /// Where the GIF is from.
+    pub source: Option<String>,
+    /// A link to the GIF's source.
+    pub source_link: Option<String>,
+    /// The GIF's title.
+    pub title: Option<String>,
+}
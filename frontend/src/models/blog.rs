@@ -10,7 +10,7 @@ pub struct AllPosts {
 }
 
 /// This struct holds post data sent from the API.
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
 pub struct PostData {
     /// The body of the post.
     pub body: String,
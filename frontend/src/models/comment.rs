@@ -0,0 +1,34 @@
+//! Contains all models pertaining to post comments.
+
+use serde::{Deserialize, Serialize};
+
+/// This struct holds all approved comments for a single post.
+#[derive(Clone, Debug, Deserialize)]
+pub struct AllComments {
+    /// A list containing all approved comments from oldest to newest.
+    pub comments: Vec<Comment>,
+}
+
+/// This struct holds a single comment sent from the API.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Comment {
+    /// The name the reader chose to comment under.
+    pub author: String,
+    /// The body of the comment.
+    pub body: String,
+    /// The comment's ID.
+    pub comment_id: String,
+    /// The created timestamp.
+    pub created: String,
+}
+
+/// This struct holds a new comment that is sent to the API.
+#[derive(Debug, Serialize)]
+pub struct NewComment {
+    /// The name the reader chose to comment under.
+    pub author: String,
+    /// The body of the comment.
+    pub body: String,
+    /// The honeypot field. This is hidden from readers and should always be empty.
+    pub website: String,
+}
@@ -1,6 +1,11 @@
 //! Contains all models used throughout the frontend.
 
+pub mod background;
 pub mod blog;
 pub mod chicago;
+pub mod comment;
+pub mod newsletter;
 pub mod response;
+pub mod revision;
 pub mod story;
+pub mod webmention;
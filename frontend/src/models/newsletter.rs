@@ -0,0 +1,20 @@
+//! Contains all models pertaining to the newsletter.
+
+use serde::{Deserialize, Serialize};
+
+/// This struct holds a subscription request that is sent to the API.
+#[derive(Debug, Serialize)]
+pub struct NewSubscriber {
+    /// The reader's email address.
+    pub email: String,
+}
+
+/// This struct holds an unsubscribe request. The fields are read from the query parameters of the
+/// unsubscribe link in a newsletter email, then sent to the API.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Unsubscribe {
+    /// The subscriber's email address.
+    pub email: String,
+    /// The signature of the email address.
+    pub signature: String,
+}
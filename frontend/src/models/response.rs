@@ -2,9 +2,13 @@
 
 use serde::Deserialize;
 
-/// Holds the standard message + status code response sent from the API.
+/// Holds the standard message + status code response sent from the API. Errors are sent as RFC 7807
+/// problem details, which are parsed into the same struct.
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(from = "RawResponse")]
 pub struct Response {
+    /// The stable error code, e.g. `not-found`, if this response is an error from the API.
+    pub code: Option<String>,
     /// The message associated with this response.
     pub message: String,
     /// The status code number associated with this response.
@@ -14,6 +18,7 @@ pub struct Response {
 impl Default for Response {
     fn default() -> Self {
         Self {
+            code: None,
             message: "UNKNOWN".to_string(),
             status_code: 500,
         }
@@ -24,8 +29,70 @@ impl Response {
     /// Create a new `Response` with a given message and an HTTP 500 status code.
     pub fn status_500_with_message(message: String) -> Self {
         Self {
+            code: None,
             message,
             status_code: 500,
         }
     }
 }
+
+/// Either shape of response the API sends.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawResponse {
+    /// An `application/problem+json` error.
+    Problem {
+        /// The stable error code.
+        code: String,
+        /// The explanation of this occurrence of the error.
+        detail: String,
+        /// The HTTP status code.
+        status: u16,
+    },
+    /// A message + status code response.
+    Message {
+        /// The message associated with this response.
+        message: String,
+        /// The status code number associated with this response.
+        status_code: u16,
+    },
+}
+
+impl From<RawResponse> for Response {
+    fn from(raw: RawResponse) -> Self {
+        match raw {
+            RawResponse::Problem {
+                code,
+                detail,
+                status,
+            } => Self {
+                message: problem_message(&code, detail),
+                code: Some(code),
+                status_code: status,
+            },
+            RawResponse::Message {
+                message,
+                status_code,
+            } => Self {
+                code: None,
+                message,
+                status_code,
+            },
+        }
+    }
+}
+
+/// Get the message to show for an error from the API. Errors caused by the reader (invalid input,
+/// rate limiting, etc.) already explain themselves, so their detail is shown as-is.
+fn problem_message(code: &str, detail: String) -> String {
+    match code {
+        "database-error" => "Something went wrong on my end. Try again later.".to_string(),
+        "database-unavailable" => {
+            "The database is down right now. Try again in a few minutes.".to_string()
+        }
+        "upstream-bad-data" | "upstream-timeout" | "upstream-unavailable" => {
+            format!("{detail} Try again later.")
+        }
+        _ => detail,
+    }
+}
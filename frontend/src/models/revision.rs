@@ -0,0 +1,60 @@
+//! Contains all models pertaining to post revisions.
+
+use serde::Deserialize;
+
+/// This struct holds all revisions for a single post and is used to render the post's change
+/// history.
+#[derive(Clone, Debug, Deserialize)]
+pub struct PostRevisions {
+    /// A list containing all revisions from oldest to newest.
+    pub revisions: Vec<PostRevision>,
+}
+
+/// This struct holds a snapshot of a post's title and body sent from the API.
+#[derive(Clone, Debug, Deserialize)]
+pub struct PostRevision {
+    /// The body of the post at this revision.
+    pub body: String,
+    /// The timestamp at which this revision was created.
+    pub created: String,
+    /// The ID of the post this revision belongs to.
+    pub post_id: String,
+    /// The revision number. The original post is always revision `0`.
+    pub revision_number: i32,
+    /// The title of the post at this revision.
+    pub title: String,
+}
+
+/// Contains all variants for the type of change a single line underwent between revisions.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum LineChange {
+    /// The line was removed.
+    Delete,
+    /// The line is unchanged.
+    Equal,
+    /// The line was added.
+    Insert,
+}
+
+/// A single line within a diff.
+#[derive(Clone, Debug, Deserialize)]
+pub struct DiffLine {
+    /// The type of change this line underwent.
+    pub change: LineChange,
+    /// The content of the line.
+    pub line: String,
+}
+
+/// This struct holds the line diffs for the title and body between two revisions.
+#[derive(Clone, Debug, Deserialize)]
+pub struct RevisionDiff {
+    /// The line diff for the post's body.
+    pub body: Vec<DiffLine>,
+    /// The revision number that was diffed from.
+    pub from: i32,
+    /// The line diff for the post's title.
+    pub title: Vec<DiffLine>,
+    /// The revision number that was diffed to.
+    pub to: i32,
+}
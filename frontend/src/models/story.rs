@@ -1,20 +1,67 @@
 //! Contains all models pertaining to stories.
 
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 /// The story to display on the 404 not found page.
 #[derive(Clone, Debug, Deserialize)]
 pub struct Story {
+    /// The name the reader submitted the story under, if it was submitted by a reader.
+    pub author: Option<String>,
+    /// The created timestamp, if the story was submitted by a reader.
+    pub created: Option<String>,
     /// The body of the story.
     pub story: String,
+    /// The story's ID.
+    #[serde(default)]
+    pub story_id: String,
 }
 
 impl Default for Story {
     fn default() -> Self {
         Self {
+            author: None,
+            created: None,
             story:
                 "If you don’t like the road you’re walking, pave another one. Except for this one."
                     .to_string(),
+            story_id: "fallback".to_string(),
         }
     }
 }
+
+/// This struct holds a page of the story archive.
+#[derive(Clone, Debug, Deserialize)]
+pub struct StoryPage {
+    /// The page number, starting at 1.
+    pub page: u64,
+    /// The stories on this page from newest to oldest.
+    pub stories: Vec<Story>,
+    /// The total number of pages in the archive.
+    pub total_pages: u64,
+}
+
+/// This struct holds the query parameters of a story's share link, which opens the 404 page with
+/// that story.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct StoryQuery {
+    /// The story's ID.
+    pub story: Option<String>,
+}
+
+/// This struct holds the query parameters of the story archive.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct StoryArchiveQuery {
+    /// The page number, starting at 1.
+    pub page: Option<u64>,
+}
+
+/// This struct holds a new story that is sent to the API.
+#[derive(Debug, Serialize)]
+pub struct NewStory {
+    /// The name the reader chose to submit the story under.
+    pub author: String,
+    /// The body of the story.
+    pub story: String,
+    /// The honeypot field. This is hidden from readers and should always be empty.
+    pub website: String,
+}
@@ -0,0 +1,39 @@
+//! Contains all models pertaining to Webmentions.
+
+use serde::Deserialize;
+
+/// Contains all types of Webmentions a post may receive.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum MentionType {
+    /// The source page likes the post.
+    Like,
+    /// The source page links to the post.
+    Mention,
+    /// The source page is a reply to the post.
+    Reply,
+}
+
+/// This struct holds all verified Webmentions for a single post.
+#[derive(Clone, Debug, Deserialize)]
+pub struct AllWebmentions {
+    /// A list containing all verified mentions from oldest to newest.
+    pub webmentions: Vec<Webmention>,
+}
+
+/// This struct holds a single verified Webmention sent from the API.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Webmention {
+    /// The name of the source page's author, if it could be found.
+    pub author_name: Option<String>,
+    /// The text content of the source page's entry. Only set for replies.
+    pub content: Option<String>,
+    /// The type of this mention.
+    pub mention_type: MentionType,
+    /// The URL of the page that mentions the post.
+    pub source: String,
+    /// The title of the source page, if it has one.
+    pub source_title: Option<String>,
+    /// The timestamp of the most recent successful verification.
+    pub verified: String,
+}
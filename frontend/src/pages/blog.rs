@@ -1,14 +1,16 @@
 //! The blog page containing post cards.
 
+use std::rc::Rc;
+
 use gloo_console::error;
-use gloo_net::http::Request;
 use yew::prelude::*;
 
-use crate::models::{
-    blog::{AllPosts, PostData},
-    response::Response,
+use crate::api::{self, hook::use_api};
+use crate::models::blog::{AllPosts, PostData};
+use crate::pages::{
+    newsletter::Subscribe,
+    utils::{self, Loading},
 };
-use crate::pages::utils::{self, Loading};
 use crate::utils::{
     background,
     open_graph::{self, OpenGraphTag, PageType},
@@ -20,86 +22,35 @@ use crate::FAVICON_GIF;
 pub fn blog() -> Html {
     gloo_utils::document().set_title("jl | blog");
 
-    let is_loading = use_state(|| true);
-    let get_posts_response = use_state(|| None);
-    {
-        let is_loading = is_loading.clone();
-        let get_posts_response = get_posts_response.clone();
-
-        use_effect_with_deps(
-            move |_| {
-                background::set_background(true);
+    use_effect_with_deps(
+        move |_| {
+            background::set_background(true);
 
-                wasm_bindgen_futures::spawn_local(async move {
-                    open_graph::set_open_graph_tag(OpenGraphTag::Description(
-                        "my blog".to_string(),
-                    ))
-                    .unwrap_or_else(|error| error!(error.to_string()));
-                    open_graph::set_open_graph_tag(OpenGraphTag::ImageLink(
-                        FAVICON_GIF.to_string(),
-                    ))
-                    .unwrap_or_else(|error| error!(error.to_string()));
-                    open_graph::set_open_graph_tag(OpenGraphTag::PageType(PageType::Website))
-                        .unwrap_or_else(|error| error!(error.to_string()));
-                    open_graph::set_open_graph_tag(OpenGraphTag::Title("jl | blog".to_string()))
-                        .unwrap_or_else(|error| error!(error.to_string()));
-                    open_graph::set_open_graph_tag(OpenGraphTag::Url(
-                        "https://josephlai.dev/blog".to_string(),
-                    ))
-                    .unwrap_or_else(|error| error!(error.to_string()));
+            open_graph::set_open_graph_tag(OpenGraphTag::Description("my blog".to_string()))
+                .unwrap_or_else(|error| error!(error.to_string()));
+            open_graph::set_open_graph_tag(OpenGraphTag::ImageLink(FAVICON_GIF.to_string()))
+                .unwrap_or_else(|error| error!(error.to_string()));
+            open_graph::set_open_graph_tag(OpenGraphTag::PageType(PageType::Website))
+                .unwrap_or_else(|error| error!(error.to_string()));
+            open_graph::set_open_graph_tag(OpenGraphTag::Title("jl | blog".to_string()))
+                .unwrap_or_else(|error| error!(error.to_string()));
+            open_graph::set_open_graph_tag(OpenGraphTag::Url(
+                "https://josephlai.dev/blog".to_string(),
+            ))
+            .unwrap_or_else(|error| error!(error.to_string()));
 
-                    match Request::get("/api/blog/posts").send().await {
-                        Ok(response) => match response.status() {
-                            200 => response.json::<AllPosts>().await.map_or_else(
-                                |error| {
-                                    is_loading.set(false);
-                                    get_posts_response.set(Some(Err(
-                                        Response::status_500_with_message(format!(
-                                            "UNABLE TO PARSE THE POSTS TO JSON: {error}"
-                                        )),
-                                    )))
-                                },
-                                |mut all_posts| {
-                                    all_posts.posts = all_posts.posts.into_iter().rev().collect();
+            || ()
+        },
+        (),
+    );
 
-                                    is_loading.set(false);
-                                    get_posts_response.set(Some(Ok(all_posts)))
-                                },
-                            ),
-                            _ => response.json::<Response>().await.map_or_else(
-                                |_| {
-                                    is_loading.set(false);
-                                    get_posts_response.set(Some(Err(
-                                        Response::status_500_with_message(
-                                            "No API response.".to_string(),
-                                        ),
-                                    )));
-                                },
-                                |response| {
-                                    is_loading.set(false);
-                                    get_posts_response.set(Some(Err(response)));
-                                },
-                            ),
-                        },
-                        Err(error) => {
-                            is_loading.set(false);
-                            get_posts_response.set(Some(Err(Response::status_500_with_message(
-                                format!("UNABLE TO GET POSTS FROM THE API: {error}"),
-                            ))))
-                        }
-                    }
-                });
+    let posts = use_api((), |_| api::get_posts());
 
-                || ()
-            },
-            (),
-        )
-    }
-
-    let response = get_posts_response
-        .as_ref()
-        .unwrap_or(&Ok(AllPosts { posts: vec![] }))
-        .to_owned();
+    let response = match (posts.data, posts.error) {
+        (_, Some(error)) => Err(error),
+        (Some(all_posts), None) => Ok(all_posts),
+        (None, None) => Ok(Rc::new(AllPosts { posts: vec![] })),
+    };
 
     let blog_body = match response {
         Ok(all_posts) => {
@@ -107,7 +58,7 @@ pub fn blog() -> Html {
                 html! {
                     <div class="container fade-in-slide-down">
                       <div class="card-columns">
-                        { for all_posts.posts.iter().map(make_bootstrap_card) }
+                        { for all_posts.posts.iter().rev().map(make_bootstrap_card) }
                       </div>
                     </div>
                 }
@@ -142,10 +93,15 @@ pub fn blog() -> Html {
 
     utils::create_page_with_nav(
         None,
-        if *is_loading {
+        if posts.loading {
             html! { <Loading /> }
         } else {
-            blog_body
+            html! {
+                <>
+                  { blog_body }
+                  <Subscribe />
+                </>
+            }
         },
     )
 }
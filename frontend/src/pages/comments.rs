@@ -0,0 +1,152 @@
+//! The comment section rendered underneath a blog post.
+
+use web_sys::{HtmlInputElement, HtmlTextAreaElement};
+use yew::prelude::*;
+
+use crate::{
+    api::{self, hook::use_api},
+    models::{
+        comment::{Comment, NewComment},
+        response::Response,
+    },
+};
+
+/// Contains properties that may be passed into the `Comments` component.
+#[derive(Debug, Eq, PartialEq, Properties)]
+pub struct CommentsProps {
+    /// The post's ID. This is used to query the API for that particular post's comments.
+    pub post_id: String,
+}
+
+/// The comment section containing the approved comment thread and the comment form.
+#[function_component(Comments)]
+pub fn comments(props: &CommentsProps) -> Html {
+    let post_id = props.post_id.clone();
+
+    let comments = use_api(post_id.clone(), |post_id| async move {
+        api::get_comments(&post_id).await
+    });
+    let submit_response: UseStateHandle<Option<Result<Response, Response>>> = use_state(|| None);
+
+    let author_ref = use_node_ref();
+    let body_ref = use_node_ref();
+    let website_ref = use_node_ref();
+
+    let onsubmit = {
+        let author_ref = author_ref.clone();
+        let body_ref = body_ref.clone();
+        let submit_response = submit_response.clone();
+        let website_ref = website_ref.clone();
+
+        Callback::from(move |event: SubmitEvent| {
+            event.prevent_default();
+
+            let (Some(author), Some(body), Some(website)) = (
+                author_ref.cast::<HtmlInputElement>(),
+                body_ref.cast::<HtmlTextAreaElement>(),
+                website_ref.cast::<HtmlInputElement>(),
+            ) else {
+                return;
+            };
+
+            let new_comment = NewComment {
+                author: author.value(),
+                body: body.value(),
+                website: website.value(),
+            };
+
+            let post_id = post_id.clone();
+            let submit_response = submit_response.clone();
+
+            wasm_bindgen_futures::spawn_local(async move {
+                let result = api::submit_comment(&post_id, &new_comment).await;
+                if result.is_ok() {
+                    body.set_value("");
+                }
+
+                submit_response.set(Some(result));
+            });
+        })
+    };
+
+    let thread = match (&comments.data, &comments.error) {
+        (_, Some(error)) => html! {
+            <p class="comment-meta">
+              { format!("HTTP {} | {}", error.status_code, error.message) }
+            </p>
+        },
+        (Some(all_comments), None) if !all_comments.comments.is_empty() => html! {
+            <div class="fade-in-slide-down">
+              { for all_comments.comments.iter().map(make_comment) }
+            </div>
+        },
+        (Some(_), None) => html! {
+            <p class="comment-meta">{ "no comments yet. be the first." }</p>
+        },
+        (None, None) => html! { <></> },
+    };
+
+    let submission_status = match submit_response.as_ref() {
+        Some(Ok(response)) => html! {
+            <small class="comment-meta">{ response.message.clone() }</small>
+        },
+        Some(Err(error)) => html! {
+            <small style="color: #832700;">
+              { format!("HTTP {} | {}", error.status_code, error.message) }
+            </small>
+        },
+        None => html! { <></> },
+    };
+
+    html! {
+        <div class="comments">
+          <div class="rusty-line-thicc"></div>
+          <h4>{ "comments" }</h4>
+          { thread }
+          <form class="comment-form" {onsubmit}>
+            <input
+              class="comment-input"
+              maxlength="50"
+              placeholder="name (optional)"
+              ref={author_ref}
+              type="text"
+            />
+            <textarea
+              class="comment-input"
+              maxlength="2000"
+              placeholder="say something"
+              ref={body_ref}
+              required=true
+              rows="4"
+            />
+            // Hidden from readers. Bots that fill out every field will fill this one out too.
+            <input
+              aria-hidden="true"
+              autocomplete="off"
+              class="comment-honeypot"
+              name="website"
+              ref={website_ref}
+              tabindex="-1"
+              type="text"
+            />
+            <div style="display: flex; align-items: center; gap: 1rem;">
+              <button class="comment-submit" type="submit">{ "submit" }</button>
+              { submission_status }
+            </div>
+          </form>
+        </div>
+    }
+}
+
+/// Create a single comment within the thread.
+fn make_comment(comment: &Comment) -> Html {
+    html! {
+        <div class="comment" key={ comment.comment_id.clone() }>
+          <div class="comment-meta">
+            <b>{ comment.author.clone() }</b>
+            { format!(" | {}", comment.created) }
+          </div>
+          <p class="comment-body">{ comment.body.clone() }</p>
+        </div>
+    }
+}
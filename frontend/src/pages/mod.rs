@@ -2,8 +2,14 @@
 
 pub mod about;
 pub mod blog;
+pub mod comments;
+pub mod newsletter;
 pub mod not_found;
+pub mod offline;
 pub mod post_view;
+pub mod revisions;
 pub mod root;
+pub mod stories;
 pub mod utils;
 pub mod violence;
+pub mod webmentions;
@@ -0,0 +1,184 @@
+//! The newsletter subscription form and the pages linked to from newsletter emails.
+
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+use yew_router::prelude::*;
+
+use crate::{
+    api::{self, hook::use_api},
+    models::{
+        newsletter::{NewSubscriber, Unsubscribe},
+        response::Response,
+    },
+    pages::utils::{self, Loading},
+    utils::background,
+};
+
+/// The form for subscribing to new posts.
+#[function_component(Subscribe)]
+pub fn subscribe() -> Html {
+    let email_ref = use_node_ref();
+    let subscribe_response: UseStateHandle<Option<Result<Response, Response>>> = use_state(|| None);
+
+    let onsubmit = {
+        let email_ref = email_ref.clone();
+        let subscribe_response = subscribe_response.clone();
+
+        Callback::from(move |event: SubmitEvent| {
+            event.prevent_default();
+
+            let Some(email) = email_ref.cast::<HtmlInputElement>() else {
+                return;
+            };
+
+            let new_subscriber = NewSubscriber {
+                email: email.value(),
+            };
+            let subscribe_response = subscribe_response.clone();
+
+            wasm_bindgen_futures::spawn_local(async move {
+                let result = api::subscribe(&new_subscriber).await;
+                if result.is_ok() {
+                    email.set_value("");
+                }
+
+                subscribe_response.set(Some(result));
+            });
+        })
+    };
+
+    html! {
+        <div class="container fade-in-slide-down">
+          <div class="rusty-line-thicc"></div>
+          <form class="comment-form" {onsubmit}>
+            <small class="comment-meta">{ "get an email whenever something new goes up" }</small>
+            <div style="display: flex; align-items: center; gap: 1rem;">
+              <input
+                class="comment-input"
+                placeholder="email"
+                ref={email_ref}
+                required=true
+                type="email"
+              />
+              <button class="comment-submit" type="submit">{ "subscribe" }</button>
+            </div>
+            { render_result(subscribe_response.as_ref()) }
+          </form>
+        </div>
+    }
+}
+
+/// Contains properties that may be passed into the `NewsletterConfirm` component.
+#[derive(Debug, Eq, PartialEq, Properties)]
+pub struct NewsletterConfirmProps {
+    /// The confirmation token from the confirmation email.
+    pub token: String,
+}
+
+/// The page linked to from the confirmation email. Confirms the subscription as soon as it loads.
+#[function_component(NewsletterConfirm)]
+pub fn newsletter_confirm(props: &NewsletterConfirmProps) -> Html {
+    gloo_utils::document().set_title("jl | newsletter");
+
+    use_effect_with_deps(
+        |_| {
+            background::set_background(true);
+
+            || ()
+        },
+        (),
+    );
+
+    let confirmation = use_api(props.token.clone(), |token| async move {
+        api::confirm_subscription(&token).await
+    });
+
+    utils::create_page_with_nav(
+        None,
+        html! {
+            <div class="left-half-container fade-in-slide-down">
+              {
+                  match (&confirmation.data, &confirmation.error) {
+                      (_, Some(error)) => render_result(Some(&Err(error.clone()))),
+                      (Some(response), None) => render_result(Some(&Ok((**response).clone()))),
+                      (None, None) => html! { <Loading /> },
+                  }
+              }
+            </div>
+        },
+    )
+}
+
+/// The page linked to from the unsubscribe link in a newsletter email. The email address and its
+/// signature are read from the query parameters.
+#[function_component(NewsletterUnsubscribe)]
+pub fn newsletter_unsubscribe() -> Html {
+    gloo_utils::document().set_title("jl | newsletter");
+
+    use_effect_with_deps(
+        |_| {
+            background::set_background(true);
+
+            || ()
+        },
+        (),
+    );
+
+    let unsubscribe = use_location().and_then(|location| location.query::<Unsubscribe>().ok());
+    let unsubscribe_response: UseStateHandle<Option<Result<Response, Response>>> =
+        use_state(|| None);
+
+    let page_body = match unsubscribe {
+        Some(unsubscribe) => {
+            let onclick = {
+                let unsubscribe_response = unsubscribe_response.clone();
+
+                Callback::from(move |_| {
+                    let unsubscribe = unsubscribe.clone();
+                    let unsubscribe_response = unsubscribe_response.clone();
+
+                    wasm_bindgen_futures::spawn_local(async move {
+                        unsubscribe_response.set(Some(api::unsubscribe(&unsubscribe).await));
+                    });
+                })
+            };
+
+            html! {
+                <>
+                  <p>{ "sorry to see you go." }</p>
+                  <button class="comment-submit" {onclick}>{ "unsubscribe" }</button>
+                  <div style="margin-top: 1rem;">
+                    { render_result(unsubscribe_response.as_ref()) }
+                  </div>
+                </>
+            }
+        }
+        None => html! {
+            <p class="error-text">{ "this unsubscribe link is missing something." }</p>
+        },
+    };
+
+    utils::create_page_with_nav(
+        None,
+        html! {
+            <div class="left-half-container fade-in-slide-down">
+              { page_body }
+            </div>
+        },
+    )
+}
+
+/// Render the result of a newsletter request.
+fn render_result(result: Option<&Result<Response, Response>>) -> Html {
+    match result {
+        Some(Ok(response)) => html! {
+            <small class="comment-meta">{ response.message.clone() }</small>
+        },
+        Some(Err(error)) => html! {
+            <small style="color: #832700;">
+              { format!("HTTP {} | {}", error.status_code, error.message) }
+            </small>
+        },
+        None => html! { <></> },
+    }
+}
@@ -1,12 +1,16 @@
 //! The 404 not found page.
 
 use gloo_console::error;
-use gloo_net::http::Request;
 use yew::prelude::*;
+use yew_router::prelude::*;
 
 use crate::{
-    models::story::Story,
-    pages::utils::{self, Loading},
+    api::{self, hook::use_api},
+    models::story::{Story, StoryQuery},
+    pages::{
+        stories::{self, SubmitStory},
+        utils::{self, Loading, PostBody},
+    },
     utils::{
         background,
         open_graph::{self, OpenGraphTag, PageType},
@@ -19,59 +23,41 @@ use crate::{
 pub fn not_found() -> Html {
     gloo_utils::document().set_title("jl | 404");
 
-    let is_loading = use_state(|| true);
-    let get_story_response = use_state(|| None);
-    {
-        let is_loading = is_loading.clone();
-        let get_story_response = get_story_response.clone();
-
-        use_effect_with_deps(
-            move |_| {
-                background::set_background(true);
-
-                wasm_bindgen_futures::spawn_local(async move {
-                    open_graph::set_open_graph_tag(OpenGraphTag::Description(
-                        "go the fuck home".to_string(),
-                    ))
-                    .unwrap_or_else(|error| error!(error.to_string()));
-                    open_graph::set_open_graph_tag(OpenGraphTag::ImageLink(
-                        FAVICON_GIF.to_string(),
-                    ))
-                    .unwrap_or_else(|error| error!(error.to_string()));
-                    open_graph::set_open_graph_tag(OpenGraphTag::PageType(PageType::Website))
-                        .unwrap_or_else(|error| error!(error.to_string()));
-                    open_graph::set_open_graph_tag(OpenGraphTag::Title("jl | 404".to_string()))
-                        .unwrap_or_else(|error| error!(error.to_string()));
+    use_effect_with_deps(
+        move |_| {
+            background::set_background(true);
 
-                    match Request::get("/api/story").send().await {
-                        Ok(response) => match response.status() {
-                            200 => {
-                                is_loading.set(false);
-                                get_story_response.set(response.json::<Story>().await.ok());
-                            }
-                            _ => {
-                                error!(format!("{:?}", response));
+            open_graph::set_open_graph_tag(OpenGraphTag::Description(
+                "go the fuck home".to_string(),
+            ))
+            .unwrap_or_else(|error| error!(error.to_string()));
+            open_graph::set_open_graph_tag(OpenGraphTag::ImageLink(FAVICON_GIF.to_string()))
+                .unwrap_or_else(|error| error!(error.to_string()));
+            open_graph::set_open_graph_tag(OpenGraphTag::PageType(PageType::Website))
+                .unwrap_or_else(|error| error!(error.to_string()));
+            open_graph::set_open_graph_tag(OpenGraphTag::Title("jl | 404".to_string()))
+                .unwrap_or_else(|error| error!(error.to_string()));
 
-                                is_loading.set(false);
-                                get_story_response.set(None);
-                            }
-                        },
-                        Err(error) => {
-                            error!(format!("{:?}", error));
-
-                            is_loading.set(false);
-                            get_story_response.set(None);
-                        }
-                    }
-                });
+            || ()
+        },
+        (),
+    );
 
-                || ()
-            },
-            (),
-        )
+    // A story's share link opens this page with that story instead of a random one.
+    let story_id = use_location()
+        .and_then(|location| location.query::<StoryQuery>().ok())
+        .and_then(|query| query.story);
+    let get_story_response = use_api(story_id, |story_id| async move {
+        match story_id {
+            Some(story_id) => api::get_story_by_id(&story_id).await,
+            None => api::get_story().await,
+        }
+    });
+    if let Some(error) = &get_story_response.error {
+        error!(format!("{error:?}"));
     }
 
-    let story = get_story_response.as_ref().map_or(
+    let story = get_story_response.data.as_ref().map_or(
         html! {
             <div class="container fade-in-slide-down">
               <p>
@@ -83,13 +69,12 @@ pub fn not_found() -> Html {
         |story| {
             let body = html! {
                 <div class="container fade-in-slide-down">
-                  <div id="404-story"></div>
+                  <PostBody body={ story.story.clone() } />
+                  { stories::story_credit(story) }
                   <p>{ "In other words, what the fuck are you doing? You have reached the 404 page. Go home." }</p>
                 </div>
             };
 
-            utils::inject_post_body("404-story", &story.story);
-
             body
         },
     );
@@ -99,12 +84,16 @@ pub fn not_found() -> Html {
         html! {
             <div class="left-half-container">
             {
-                if *is_loading {
+                if get_story_response.loading {
                     html! { <Loading /> }
                 } else {
                     story
                 }
             }
+            <div class="container fade-in-slide-down">
+              <div class="rusty-line-thicc"></div>
+              <SubmitStory />
+            </div>
             </div>
         },
     )
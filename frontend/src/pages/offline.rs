@@ -0,0 +1,57 @@
+//! Contains the banner shown while the reader is offline.
+
+use wasm_bindgen::{prelude::*, JsCast};
+use yew::prelude::*;
+
+/// A banner shown above every page while the browser is offline. The service worker still serves
+/// the posts that were read recently, so the banner says so rather than just reporting the error.
+#[function_component(OfflineBanner)]
+pub fn offline_banner() -> Html {
+    let offline = use_state(|| !gloo_utils::window().navigator().on_line());
+
+    {
+        let offline = offline.clone();
+
+        use_effect_with_deps(
+            move |_| {
+                let window = gloo_utils::window();
+
+                let on_online = {
+                    let offline = offline.clone();
+                    Closure::<dyn Fn()>::new(move || offline.set(false))
+                };
+                let on_offline = Closure::<dyn Fn()>::new(move || offline.set(true));
+
+                let _ = window
+                    .add_event_listener_with_callback("online", on_online.as_ref().unchecked_ref());
+                let _ = window.add_event_listener_with_callback(
+                    "offline",
+                    on_offline.as_ref().unchecked_ref(),
+                );
+
+                move || {
+                    let _ = window.remove_event_listener_with_callback(
+                        "online",
+                        on_online.as_ref().unchecked_ref(),
+                    );
+                    let _ = window.remove_event_listener_with_callback(
+                        "offline",
+                        on_offline.as_ref().unchecked_ref(),
+                    );
+                }
+            },
+            (),
+        );
+    }
+
+    if !*offline {
+        return html! { <></> };
+    }
+
+    html! {
+        <div class="offline-banner" role="status">
+          <i class="bi bi-wifi-off"></i>
+          { " You're offline. Posts you've read recently can still be read." }
+        </div>
+    }
+}
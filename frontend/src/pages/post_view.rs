@@ -1,12 +1,16 @@
 //! The page containing the blog post.
 
 use gloo_console::error;
-use gloo_net::http::Request;
 use yew::prelude::*;
 
 use crate::{
-    models::{blog::PostData, response::Response},
-    pages::utils::{self, Loading},
+    api::{self, hook::use_api},
+    models::blog::PostData,
+    pages::{
+        comments::Comments,
+        utils::{self, Loading, PostBody},
+        webmentions::Webmentions,
+    },
     utils::{
         background,
         open_graph::{self, OpenGraphTag, PageType},
@@ -26,11 +30,8 @@ pub struct PostViewProps {
 pub fn post_view(props: &PostViewProps) -> Html {
     let post_id = props.post_id.clone();
 
-    let is_loading = use_state(|| true);
-    let get_post_response = use_state(|| None);
     {
-        let is_loading = is_loading.clone();
-        let get_post_response = get_post_response.clone();
+        let post_id = post_id.clone();
 
         use_effect_with_deps(
             move |_| {
@@ -43,122 +44,58 @@ pub fn post_view(props: &PostViewProps) -> Html {
                 )))
                 .unwrap_or_else(|error| error!(error.to_string()));
 
-                wasm_bindgen_futures::spawn_local(async move {
-                    match Request::get(&format!("/api/blog/post/{post_id}"))
-                        .send()
-                        .await
-                    {
-                        Ok(response) => match response.status() {
-                            200 => response.json::<PostData>().await.map_or_else(
-                                |error| {
-                                    is_loading.set(false);
-
-                                    open_graph::set_open_graph_tag(OpenGraphTag::Description(
-                                        "something fucked up".to_string(),
-                                    ))
-                                    .unwrap_or_else(|error| error!(error.to_string()));
-                                    open_graph::set_open_graph_tag(OpenGraphTag::ImageLink(
-                                        FAVICON_GIF.to_string(),
-                                    ))
-                                    .unwrap_or_else(|error| error!(error.to_string()));
-                                    open_graph::set_open_graph_tag(OpenGraphTag::Title(
-                                        "jl | blog | fuck".to_string(),
-                                    ))
-                                    .unwrap_or_else(|error| error!(error.to_string()));
-
-                                    get_post_response.set(Some(Err(
-                                        Response::status_500_with_message(format!(
-                                            "UNABLE TO PARSE THE POST DATA TO JSON: {error}"
-                                        )),
-                                    )));
-                                },
-                                |post_data| {
-                                    is_loading.set(false);
-
-                                    open_graph::set_open_graph_tag(OpenGraphTag::Description(
-                                        post_data.preview_summary.clone(),
-                                    ))
-                                    .unwrap_or_else(|error| error!(error.to_string()));
-                                    open_graph::set_open_graph_tag(OpenGraphTag::ImageLink(
-                                        post_data.preview_image_link.clone(),
-                                    ))
-                                    .unwrap_or_else(|error| error!(error.to_string()));
-                                    open_graph::set_open_graph_tag(OpenGraphTag::Title(format!(
-                                        "jl | blog | {}",
-                                        post_data.title
-                                    )))
-                                    .unwrap_or_else(|error| error!(error.to_string()));
-
-                                    get_post_response.set(Some(Ok(post_data)));
-                                },
-                            ),
-                            _ => response.json::<Response>().await.map_or_else(
-                                |_| {
-                                    is_loading.set(false);
-
-                                    open_graph::set_open_graph_tag(OpenGraphTag::Description(
-                                        "something fucked up".to_string(),
-                                    ))
-                                    .unwrap_or_else(|error| error!(error.to_string()));
-                                    open_graph::set_open_graph_tag(OpenGraphTag::ImageLink(
-                                        FAVICON_GIF.to_string(),
-                                    ))
-                                    .unwrap_or_else(|error| error!(error.to_string()));
-                                    open_graph::set_open_graph_tag(OpenGraphTag::Title(
-                                        "jl | blog | fuck".to_string(),
-                                    ))
-                                    .unwrap_or_else(|error| error!(error.to_string()));
-
-                                    get_post_response.set(Some(Err(
-                                        Response::status_500_with_message(
-                                            "No API response.".to_string(),
-                                        ),
-                                    )));
-                                },
-                                |response| {
-                                    is_loading.set(false);
-
-                                    open_graph::set_open_graph_tag(OpenGraphTag::Description(
-                                        "something fucked up".to_string(),
-                                    ))
-                                    .unwrap_or_else(|error| error!(error.to_string()));
-                                    open_graph::set_open_graph_tag(OpenGraphTag::ImageLink(
-                                        FAVICON_GIF.to_string(),
-                                    ))
-                                    .unwrap_or_else(|error| error!(error.to_string()));
-                                    open_graph::set_open_graph_tag(OpenGraphTag::Title(
-                                        "jl | blog | fuck".to_string(),
-                                    ))
-                                    .unwrap_or_else(|error| error!(error.to_string()));
-
-                                    get_post_response.set(Some(Err(response)));
-                                },
-                            ),
-                        },
-                        Err(error) => {
-                            is_loading.set(false);
-
-                            get_post_response.set(Some(Err(Response::status_500_with_message(
-                                format!("UNABLE TO GET THE POST FROM THE API: {error}"),
-                            ))));
-                        }
-                    }
-                });
-
                 || ()
             },
             (),
         );
     }
 
-    let response = get_post_response
-        .as_ref()
-        .unwrap_or(&Ok(PostData::from_post_id(props.post_id.clone())))
-        .to_owned();
+    let post = use_api(
+        post_id,
+        |post_id| async move { api::get_post(&post_id).await },
+    );
+
+    // The title and Open Graph tags only change once the post (or an error) has loaded.
+    use_effect_with_deps(
+        |post| {
+            if post.loading {
+                return;
+            }
+
+            match (&post.data, &post.error) {
+                (_, Some(_error)) => {
+                    gloo_utils::document().set_title("jl | blog | fuck");
+
+                    set_open_graph_tags(
+                        "something fucked up".to_string(),
+                        FAVICON_GIF.to_string(),
+                        "jl | blog | fuck".to_string(),
+                    );
+                }
+                (Some(post_data), None) => {
+                    gloo_utils::document().set_title(&format!("jl | blog | {}", post_data.title));
+
+                    set_open_graph_tags(
+                        post_data.preview_summary.clone(),
+                        post_data.preview_image_link.clone(),
+                        format!("jl | blog | {}", post_data.title),
+                    );
+                }
+                (None, None) => {}
+            }
+        },
+        post.clone(),
+    );
+
+    let response = match (&post.data, &post.error) {
+        (_, Some(error)) => Err(error.clone()),
+        (Some(post_data), None) => Ok(post_data.as_ref().clone()),
+        (None, None) => Ok(PostData::from_post_id(props.post_id.clone())),
+    };
 
     let post_body = match response {
         Ok(post_data) => {
-            let post_skeleton = html! {
+            html! {
                 <div class="fade-in-slide-down">
                   <div class="d-flex flex-column">
                     <div style="margin-bottom: 1rem !important;">
@@ -174,9 +111,15 @@ pub fn post_view(props: &PostViewProps) -> Html {
                       if let Some(edited) = post_data.edited {
                           html! {
                               <div>
-                                <small>
-                                  { format!("[ edited {edited} ]") }
-                                </small>
+                                <a
+                                  href={ format!("/blog/post/{}/revisions", post_data.post_id) }
+                                  style="text-decoration: none;"
+                                  title="view the change history"
+                                >
+                                  <span class="badge p-2 blog-post-edited-badge">
+                                    { format!("edited {edited}") }
+                                  </span>
+                                </a>
                               </div>
                           }
                       } else {
@@ -192,20 +135,14 @@ pub fn post_view(props: &PostViewProps) -> Html {
                       { post_data.preview_summary }
                     </p>
                     <div class="rusty-line-thicc"></div>
-                    <div id="post-content"></div>
+                    <PostBody body={ post_data.body.clone() } />
+                    <Webmentions post_id={ post_data.post_id.clone() } />
+                    <Comments post_id={ post_data.post_id.clone() } />
                   </div>
                 </div>
-            };
-
-            gloo_utils::document().set_title(&format!("jl | blog | {}", post_data.title));
-
-            utils::inject_post_body("post-content", &post_data.body);
-
-            post_skeleton
+            }
         }
         Err(error) => {
-            gloo_utils::document().set_title("jl | blog | fuck");
-
             html! {
                 <div class="fade-in-slide-down">
                   <h1 class="error-text"><i>{"fuck"}</i></h1>
@@ -223,7 +160,7 @@ pub fn post_view(props: &PostViewProps) -> Html {
         html! {
             <div class="blog-post fade-in-slide-down">
             {
-                if *is_loading {
+                if post.loading {
                     html! { <Loading /> }
                 } else {
                     post_body
@@ -233,3 +170,13 @@ pub fn post_view(props: &PostViewProps) -> Html {
         },
     )
 }
+
+/// Set the Open Graph description, image, and title tags for the post.
+fn set_open_graph_tags(description: String, image_link: String, title: String) {
+    open_graph::set_open_graph_tag(OpenGraphTag::Description(description))
+        .unwrap_or_else(|error| error!(error.to_string()));
+    open_graph::set_open_graph_tag(OpenGraphTag::ImageLink(image_link))
+        .unwrap_or_else(|error| error!(error.to_string()));
+    open_graph::set_open_graph_tag(OpenGraphTag::Title(title))
+        .unwrap_or_else(|error| error!(error.to_string()));
+}
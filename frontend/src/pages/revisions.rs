@@ -0,0 +1,206 @@
+//! The page containing a blog post's revision history.
+
+use std::rc::Rc;
+
+use gloo_console::error;
+use yew::prelude::*;
+
+use crate::{
+    api::{
+        self,
+        hook::{use_api, ApiState},
+    },
+    models::revision::{DiffLine, LineChange, PostRevision, PostRevisions, RevisionDiff},
+    pages::utils::{self, Loading},
+    utils::{
+        background,
+        open_graph::{self, OpenGraphTag, PageType},
+    },
+    FAVICON_GIF,
+};
+
+/// Contains properties that may be passed into the `Revisions` component.
+#[derive(Debug, Eq, PartialEq, Properties)]
+pub struct RevisionsProps {
+    /// The post's ID. This is used to query the API for that particular post's revisions.
+    pub post_id: String,
+}
+
+/// The revision history page.
+#[function_component(Revisions)]
+pub fn revisions(props: &RevisionsProps) -> Html {
+    gloo_utils::document().set_title("jl | blog | revisions");
+
+    let post_id = props.post_id.clone();
+
+    use_effect_with_deps(
+        move |_| {
+            background::set_background(true);
+
+            open_graph::set_open_graph_tag(OpenGraphTag::Description(
+                "the change history for this post".to_string(),
+            ))
+            .unwrap_or_else(|error| error!(error.to_string()));
+            open_graph::set_open_graph_tag(OpenGraphTag::ImageLink(FAVICON_GIF.to_string()))
+                .unwrap_or_else(|error| error!(error.to_string()));
+            open_graph::set_open_graph_tag(OpenGraphTag::PageType(PageType::Website))
+                .unwrap_or_else(|error| error!(error.to_string()));
+            open_graph::set_open_graph_tag(OpenGraphTag::Title(
+                "jl | blog | revisions".to_string(),
+            ))
+            .unwrap_or_else(|error| error!(error.to_string()));
+
+            || ()
+        },
+        (),
+    );
+
+    let revisions = use_api(post_id.clone(), |post_id| async move {
+        api::get_revisions(&post_id).await
+    });
+
+    // The revision whose changes are shown, if one was picked. Its diff is against the revision
+    // before it.
+    let selected_revision = use_state(|| None);
+    let diff = use_api(
+        (post_id, *selected_revision),
+        |(post_id, selected_revision)| async move {
+            match selected_revision {
+                Some(to) => api::get_revision_diff(&post_id, to - 1, to).await.map(Some),
+                None => Ok(None),
+            }
+        },
+    );
+
+    let response = match (revisions.data, revisions.error) {
+        (_, Some(error)) => Err(error),
+        (Some(post_revisions), None) => Ok(post_revisions),
+        (None, None) => Ok(Rc::new(PostRevisions { revisions: vec![] })),
+    };
+
+    let revisions_body = match response {
+        Ok(post_revisions) => {
+            if post_revisions.revisions.is_empty() {
+                html! {
+                    <div class="fade-in-slide-down">
+                      <p class="error-text">{ "this post has never been edited" }</p>
+                    </div>
+                }
+            } else {
+                html! {
+                    <div class="fade-in-slide-down">
+                      <h3>{ "change history" }</h3>
+                      <div class="rusty-line-thicc"></div>
+                      {
+                          for post_revisions.revisions.iter().map(|revision| {
+                              make_revision_row(revision, selected_revision.clone())
+                          })
+                      }
+                      { render_diff(&diff) }
+                    </div>
+                }
+            }
+        }
+        Err(error) => html! {
+            <div class="fade-in-slide-down">
+              <h1 class="error-text"><i>{ "fuck" }</i></h1>
+              <h4 style="color: #832700;">
+                <b>{ format!("HTTP {}", error.status_code) }</b>
+              </h4>
+              <small>{ error.message.clone() }</small>
+            </div>
+        },
+    };
+
+    utils::create_page_with_nav(
+        Some(format!("/blog/post/{}", props.post_id)),
+        html! {
+            <div class="blog-post fade-in-slide-down">
+            {
+                if revisions.loading {
+                    html! { <Loading /> }
+                } else {
+                    revisions_body
+                }
+            }
+            </div>
+        },
+    )
+}
+
+/// Create a row for a single revision. Every revision except the original post includes a link
+/// that loads the diff against the previous revision.
+fn make_revision_row(
+    revision: &PostRevision,
+    selected_revision: UseStateHandle<Option<i32>>,
+) -> Html {
+    let label = if revision.revision_number == 0 {
+        "original".to_string()
+    } else {
+        format!("revision {}", revision.revision_number)
+    };
+
+    let view_changes = if revision.revision_number > 0 {
+        let revision_number = revision.revision_number;
+        let onclick = Callback::from(move |_| selected_revision.set(Some(revision_number)));
+
+        html! {
+            <a href="#revision-diff" {onclick}>{ "view changes" }</a>
+        }
+    } else {
+        html! { <></> }
+    };
+
+    html! {
+        <div style="display: flex; justify-content: space-between; margin-bottom: 0.5rem;">
+          <span>
+            <b>{ label }</b>
+            <small style="color: #929292;">{ format!(" | {} | {}", revision.created, revision.title) }</small>
+          </span>
+          { view_changes }
+        </div>
+    }
+}
+
+/// Render the currently selected diff, if there is one.
+fn render_diff(diff: &ApiState<Option<RevisionDiff>>) -> Html {
+    if let Some(error) = &diff.error {
+        return html! {
+            <div id="revision-diff" class="fade-in-slide-down" style="margin-top: 2rem;">
+              <h4 style="color: #832700;">
+                <b>{ format!("HTTP {}", error.status_code) }</b>
+              </h4>
+              <small>{ error.message.clone() }</small>
+            </div>
+        };
+    }
+
+    match diff.data.as_deref() {
+        Some(Some(revision_diff)) => html! {
+            <div id="revision-diff" class="fade-in-slide-down" style="margin-top: 2rem;">
+              <h5>{ format!("revision {} → revision {}", revision_diff.from, revision_diff.to) }</h5>
+              <div class="rusty-line-thicc"></div>
+              <div class="diff-container">
+                { for revision_diff.title.iter().map(make_diff_line) }
+              </div>
+              <div class="diff-container">
+                { for revision_diff.body.iter().map(make_diff_line) }
+              </div>
+            </div>
+        },
+        _ => html! { <></> },
+    }
+}
+
+/// Create a single line within a diff.
+fn make_diff_line(diff_line: &DiffLine) -> Html {
+    let (class, prefix) = match diff_line.change {
+        LineChange::Delete => ("diff-line-delete", "-"),
+        LineChange::Equal => ("diff-line-equal", " "),
+        LineChange::Insert => ("diff-line-insert", "+"),
+    };
+
+    html! {
+        <div class={ class }>{ format!("{prefix} {}", diff_line.line) }</div>
+    }
+}
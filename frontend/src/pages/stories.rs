@@ -0,0 +1,273 @@
+//! The story archive, and components pertaining to the stories shown on the 404 page.
+
+use gloo_console::error;
+use gloo_net::http::Request;
+use web_sys::{HtmlInputElement, HtmlTextAreaElement};
+use yew::prelude::*;
+use yew_router::prelude::*;
+
+use crate::{
+    api::{self, hook::use_api},
+    models::{
+        response::Response,
+        story::{NewStory, Story, StoryArchiveQuery},
+    },
+    pages::utils::{self, Loading, PostBody},
+    router::Route,
+    utils::{
+        background,
+        open_graph::{self, OpenGraphTag, PageType},
+    },
+    FAVICON_GIF,
+};
+
+/// The form for submitting a story to be shown on the 404 page. Stories are not shown until they
+/// are approved.
+#[function_component(SubmitStory)]
+pub fn submit_story() -> Html {
+    let submit_response: UseStateHandle<Option<Result<Response, Response>>> = use_state(|| None);
+
+    let author_ref = use_node_ref();
+    let story_ref = use_node_ref();
+    let website_ref = use_node_ref();
+
+    let onsubmit = {
+        let author_ref = author_ref.clone();
+        let story_ref = story_ref.clone();
+        let submit_response = submit_response.clone();
+        let website_ref = website_ref.clone();
+
+        Callback::from(move |event: SubmitEvent| {
+            event.prevent_default();
+
+            let (Some(author), Some(story), Some(website)) = (
+                author_ref.cast::<HtmlInputElement>(),
+                story_ref.cast::<HtmlTextAreaElement>(),
+                website_ref.cast::<HtmlInputElement>(),
+            ) else {
+                return;
+            };
+
+            let new_story = NewStory {
+                author: author.value(),
+                story: story.value(),
+                website: website.value(),
+            };
+
+            let submit_response = submit_response.clone();
+
+            wasm_bindgen_futures::spawn_local(async move {
+                let request = match Request::post("/api/stories").json(&new_story) {
+                    Ok(request) => request,
+                    Err(error) => {
+                        submit_response.set(Some(Err(Response::status_500_with_message(format!(
+                            "UNABLE TO SERIALIZE THE STORY: {error}"
+                        )))));
+
+                        return;
+                    }
+                };
+
+                match request.send().await {
+                    Ok(response) => {
+                        let parsed = response.json::<Response>().await.unwrap_or_else(|_| {
+                            Response::status_500_with_message("No API response.".to_string())
+                        });
+
+                        if response.ok() {
+                            story.set_value("");
+                            submit_response.set(Some(Ok(parsed)));
+                        } else {
+                            submit_response.set(Some(Err(parsed)));
+                        }
+                    }
+                    Err(error) => {
+                        submit_response.set(Some(Err(Response::status_500_with_message(format!(
+                            "UNABLE TO SUBMIT THE STORY: {error}"
+                        )))))
+                    }
+                }
+            });
+        })
+    };
+
+    let submission_status = match submit_response.as_ref() {
+        Some(Ok(response)) => html! {
+            <small class="comment-meta">{ response.message.clone() }</small>
+        },
+        Some(Err(error)) => html! {
+            <small style="color: #832700;">
+              { format!("HTTP {} | {}", error.status_code, error.message) }
+            </small>
+        },
+        None => html! { <></> },
+    };
+
+    html! {
+        <form class="comment-form" {onsubmit}>
+          <small class="comment-meta">{ "got a better story? it might show up here." }</small>
+          <input
+            class="comment-input"
+            maxlength="50"
+            placeholder="name (optional)"
+            ref={author_ref}
+            type="text"
+          />
+          <textarea
+            class="comment-input"
+            maxlength="2000"
+            placeholder="once upon a time"
+            ref={story_ref}
+            required=true
+            rows="4"
+          />
+          // Hidden from readers. Bots that fill out every field will fill this one out too.
+          <input
+            aria-hidden="true"
+            autocomplete="off"
+            class="comment-honeypot"
+            name="website"
+            ref={website_ref}
+            tabindex="-1"
+            type="text"
+          />
+          <div style="display: flex; align-items: center; gap: 1rem;">
+            <button class="comment-submit" type="submit">{ "submit" }</button>
+            { submission_status }
+          </div>
+        </form>
+    }
+}
+
+/// The archive of every story shown on the 404 page, newest first.
+#[function_component(StoryArchive)]
+pub fn story_archive() -> Html {
+    gloo_utils::document().set_title("jl | stories");
+
+    use_effect_with_deps(
+        move |_| {
+            background::set_background(true);
+
+            open_graph::set_open_graph_tag(OpenGraphTag::Description(
+                "every story from the 404 page".to_string(),
+            ))
+            .unwrap_or_else(|error| error!(error.to_string()));
+            open_graph::set_open_graph_tag(OpenGraphTag::ImageLink(FAVICON_GIF.to_string()))
+                .unwrap_or_else(|error| error!(error.to_string()));
+            open_graph::set_open_graph_tag(OpenGraphTag::PageType(PageType::Website))
+                .unwrap_or_else(|error| error!(error.to_string()));
+            open_graph::set_open_graph_tag(OpenGraphTag::Title("jl | stories".to_string()))
+                .unwrap_or_else(|error| error!(error.to_string()));
+            open_graph::set_open_graph_tag(OpenGraphTag::Url(
+                "https://josephlai.dev/stories".to_string(),
+            ))
+            .unwrap_or_else(|error| error!(error.to_string()));
+
+            || ()
+        },
+        (),
+    );
+
+    // The page lives in the query string, so that every page of the archive has its own link.
+    let page = use_location()
+        .and_then(|location| location.query::<StoryArchiveQuery>().ok())
+        .and_then(|query| query.page)
+        .unwrap_or(1)
+        .max(1);
+    let navigator = use_navigator();
+
+    let story_page = use_api(page, api::get_stories);
+
+    let go_to_page = |page: u64| {
+        let navigator = navigator.clone();
+
+        Callback::from(move |_: MouseEvent| {
+            if let Some(navigator) = &navigator {
+                if let Err(error) = navigator
+                    .push_with_query(&Route::Stories, &StoryArchiveQuery { page: Some(page) })
+                {
+                    error!(error.to_string());
+                }
+            }
+        })
+    };
+
+    let archive_body = match (&story_page.data, &story_page.error) {
+        (_, Some(error)) => html! {
+            <p class="error-text">
+              { format!("HTTP {} | {}", error.status_code, error.message) }
+            </p>
+        },
+        (Some(story_page), None) if story_page.stories.is_empty() => html! {
+            <p class="comment-meta">{ "no stories yet." }</p>
+        },
+        (Some(story_page), None) => html! {
+            <>
+              { for story_page.stories.iter().map(make_story) }
+              <div style="display: flex; align-items: center; gap: 1rem;">
+                if story_page.page > 1 {
+                  <button class="comment-submit" onclick={go_to_page(story_page.page - 1)}>
+                    { "newer" }
+                  </button>
+                }
+                <small class="comment-meta">
+                  { format!("page {} of {}", story_page.page, story_page.total_pages) }
+                </small>
+                if story_page.page < story_page.total_pages {
+                  <button class="comment-submit" onclick={go_to_page(story_page.page + 1)}>
+                    { "older" }
+                  </button>
+                }
+              </div>
+            </>
+        },
+        (None, None) => html! { <Loading /> },
+    };
+
+    utils::create_page_with_nav(
+        None,
+        html! {
+            <div class="left-half-container fade-in-slide-down">
+              { archive_body }
+            </div>
+        },
+    )
+}
+
+/// Create a single story within the archive.
+fn make_story(story: &Story) -> Html {
+    html! {
+        <div class="comment" key={ story.story_id.clone() }>
+          <PostBody body={ story.story.clone() } />
+          { story_credit(story) }
+        </div>
+    }
+}
+
+/// Create the line crediting a story's author, if it was submitted by a reader, along with the
+/// story's share link.
+pub fn story_credit(story: &Story) -> Html {
+    let credit = match (&story.author, &story.created) {
+        (Some(author), Some(created)) => Some(format!("— {author} | {created}")),
+        (Some(author), None) => Some(format!("— {author}")),
+        _ => None,
+    };
+
+    // The fallback story is not stored in MongoDB, so there is nothing to link to.
+    let share_link = match story.story_id.as_str() {
+        "" | "fallback" => None,
+        story_id => Some(format!("/404?story={story_id}")),
+    };
+
+    html! {
+        <p class="comment-meta">
+          if let Some(credit) = credit {
+            { credit }{ " | " }
+          }
+          if let Some(share_link) = share_link {
+            <a href={ share_link }>{ "link" }</a>{ " | " }
+          }
+          <a href="/stories">{ "more stories" }</a>
+        </p>
+    }
+}
@@ -1,68 +1,147 @@
 //! Contains miscellaneous utilities for creating pages.
 
 use gloo_console::error;
-use js_sys::Function;
-use pulldown_cmark::{html, Options, Parser};
-use wasm_bindgen::JsCast;
-use web_sys::{
-    HtmlTableCellElement, HtmlTableElement, HtmlTableRowElement, MutationObserver,
-    MutationObserverInit,
-};
+use gloo_timers::future::TimeoutFuture;
+use js_sys::{Object, Reflect};
+use wasm_bindgen::{prelude::*, JsCast};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{Element, HtmlTableCellElement, HtmlTableElement, HtmlTableRowElement};
 use yew::prelude::*;
 
 use crate::errors::StaccError;
 
-/// Convert the post's body from Markdown to HTML.
-fn create_post_body(post_body: &str) -> String {
-    let mut parser_options = Options::empty();
-    parser_options.insert(Options::ENABLE_HEADING_ATTRIBUTES);
-    parser_options.insert(Options::ENABLE_SMART_PUNCTUATION);
-    parser_options.insert(Options::ENABLE_STRIKETHROUGH);
-    parser_options.insert(Options::ENABLE_TABLES);
+#[wasm_bindgen]
+extern "C" {
+    /// Render TeX into an element with KaTeX.
+    #[wasm_bindgen(catch, js_namespace = katex, js_name = render)]
+    fn render_math(tex: &str, element: &Element, options: &Object) -> Result<(), JsValue>;
+}
+
+/// Contains properties that may be passed into the `PostBody` component.
+#[derive(Debug, Eq, PartialEq, Properties)]
+pub struct PostBodyProps {
+    /// The post's body Markdown.
+    pub body: String,
+}
 
-    let parser = Parser::new_ext(post_body, parser_options);
+/// A post's body, parsed from Markdown into sanitized HTML and injected once the component has
+/// been rendered. Code blocks are highlighted by the Markdown renderer, so only their copy buttons
+/// and the math are set up afterwards.
+#[function_component(PostBody)]
+pub fn post_body(props: &PostBodyProps) -> Html {
+    let content_ref = use_node_ref();
 
-    let mut post_body = String::new();
-    html::push_html(&mut post_body, parser);
+    {
+        let content_ref = content_ref.clone();
+
+        use_effect_with_deps(
+            move |body| {
+                if let Some(content_div) = content_ref.cast::<Element>() {
+                    content_div.set_inner_html(&markdown::render(body));
+                    render_post_extras(&content_div);
+                }
+            },
+            props.body.clone(),
+        );
+    }
 
-    post_body
+    html! { <div ref={ content_ref }></div> }
 }
 
-/// Parse the post's body Markdown into HTML, then inject the HTML into the page.
-pub fn inject_post_body(element_id: &str, post_body: &str) {
-    let post_body = create_post_body(post_body);
-
-    let arguments = "mutations, observer";
-    let body = &format!(
-        r#"
-            const contentDiv = document.getElementById('{element_id}');
-            if (contentDiv) {{
-              contentDiv.innerHTML = `{}`;
-              hljs.highlightAll();
-
-              observer.disconnect();
-
-              return;
-            }}
-        "#,
-        &post_body
-    );
-
-    MutationObserver::new(&Function::new_with_args(arguments, body)).map_or_else(
-        |error| {
-            error!("An error occurred while instantiating a MutationObserver!");
-            error!(format!("{:#?}", error));
-        },
-        |observer| {
-            if let Err(error) = observer.observe_with_options(
-                &gloo_utils::body().get_root_node(),
-                MutationObserverInit::new().child_list(true).subtree(true),
-            ) {
-                error!("Observation failure!");
-                error!(format!("{:#?}", error));
-            }
-        },
-    );
+/// Set up the code blocks' copy buttons and render the math within an injected post body. KaTeX
+/// may have failed to load from its CDN, in which case the math is left as TeX.
+fn render_post_extras(content_div: &Element) {
+    add_copy_buttons(content_div);
+
+    let Ok(math_elements) = content_div.query_selector_all(".math") else {
+        return;
+    };
+
+    for index in 0..math_elements.length() {
+        let Some(element) = math_elements
+            .item(index)
+            .and_then(|node| node.dyn_into::<Element>().ok())
+        else {
+            continue;
+        };
+
+        let options = Object::new();
+        let _ = Reflect::set(
+            &options,
+            &"displayMode".into(),
+            &element.class_list().contains("math-display").into(),
+        );
+        let _ = Reflect::set(&options, &"throwOnError".into(), &false.into());
+
+        if let Err(error) = render_math(
+            &element.text_content().unwrap_or_default(),
+            &element,
+            &options,
+        ) {
+            error!("Failed to render math!");
+            error!(error);
+
+            return;
+        }
+    }
+}
+
+/// Make every code block's copy button copy the block's code to the clipboard. The button's label
+/// says whether it worked for a couple of seconds afterwards.
+fn add_copy_buttons(content_div: &Element) {
+    let Ok(buttons) = content_div.query_selector_all(".code-copy") else {
+        return;
+    };
+
+    for index in 0..buttons.length() {
+        let Some(button) = buttons
+            .item(index)
+            .and_then(|node| node.dyn_into::<Element>().ok())
+        else {
+            continue;
+        };
+        let Some(code) = button
+            .parent_element()
+            .and_then(|code_block| code_block.query_selector("code").ok().flatten())
+        else {
+            continue;
+        };
+
+        let clicked_button = button.clone();
+        let callback = Closure::<dyn FnMut()>::new(move || {
+            let copied = gloo_utils::window()
+                .navigator()
+                .clipboard()
+                .write_text(&code.text_content().unwrap_or_default());
+            let button = clicked_button.clone();
+
+            wasm_bindgen_futures::spawn_local(async move {
+                let label = match JsFuture::from(copied).await {
+                    Ok(_) => "Copied",
+                    Err(error) => {
+                        error!("Failed to copy code!");
+                        error!(error);
+
+                        "Failed"
+                    }
+                };
+
+                button.set_text_content(Some(label));
+                TimeoutFuture::new(2_000).await;
+                button.set_text_content(Some("Copy"));
+            });
+        });
+
+        if let Err(error) =
+            button.add_event_listener_with_callback("click", callback.as_ref().unchecked_ref())
+        {
+            error!("Failed to add a code block's copy button!");
+            error!(error);
+        }
+
+        // The button lives as long as the post, so the callback has to outlive this function.
+        callback.forget();
+    }
 }
 
 /// Create `Html` containing the navigation bar and an `Html` component underneath it.
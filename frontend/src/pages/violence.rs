@@ -3,7 +3,6 @@
 use std::env;
 
 use gloo_console::error;
-use gloo_net::http::Request;
 use lazy_static::lazy_static;
 use leaflet::{
     Icon, IconOptions, LatLng, LayerGroup, Map, MapOptions, Marker, MarkerOptions, Point, Popup,
@@ -15,10 +14,10 @@ use web_sys::{HtmlElement, Node};
 use yew::{prelude::*, virtual_dom::VNode};
 
 use crate::{
+    api::{self, hook::use_api},
     errors::StaccError,
-    models::{
-        chicago::{ChicagoMapData, CleanedShotData, CleanedViolenceData, ShotData, ViolenceData},
-        response::Response,
+    models::chicago::{
+        ChicagoMapData, CleanedShotData, CleanedViolenceData, ShotData, ViolenceData,
     },
     pages::utils::Loading,
     traits::{abstractable_hashmap::AbstractableHashMap, popup::Popup as PopupTrait},
@@ -76,84 +75,33 @@ lazy_static! {
 pub fn violence() -> Html {
     gloo_utils::document().set_title("jl | violence");
 
-    let is_loading = use_state(|| true);
-    let get_chiraq_response = use_state(|| None);
-    {
-        let is_loading = is_loading.clone();
-        let get_chiraq_response = get_chiraq_response.clone();
-
-        use_effect_with_deps(
-            move |_| {
-                background::set_background(true);
-
-                wasm_bindgen_futures::spawn_local(async move {
-                    open_graph::set_open_graph_tag(OpenGraphTag::Description(
-                        "Visualizing violence in Chicago".to_string(),
-                    ))
-                    .unwrap_or_else(|error| error!(error.to_string()));
-                    open_graph::set_open_graph_tag(OpenGraphTag::ImageLink(
-                        FAVICON_GIF.to_string(),
-                    ))
-                    .unwrap_or_else(|error| error!(error.to_string()));
-                    open_graph::set_open_graph_tag(OpenGraphTag::PageType(PageType::Website))
-                        .unwrap_or_else(|error| error!(error.to_string()));
-                    open_graph::set_open_graph_tag(OpenGraphTag::Title(
-                        "jl | violence".to_string(),
-                    ))
-                    .unwrap_or_else(|error| error!(error.to_string()));
-
-                    match Request::get("/api/chiraq").send().await {
-                        Ok(response) => match response.status() {
-                            200 => {
-                                is_loading.set(false);
-                                response.json::<ChicagoMapData>().await.map_or_else(
-                                    |error| {
-                                        error!("FAILED TO PARSE CHIRAQ DATA TO THE CHICAGOMAPDATA STRUCT!");
-                                        error!(error.to_string());
-
-                                        is_loading.set(false);
-                                        get_chiraq_response.set(Some(Err(
-                                            Response::status_500_with_message(format!(
-                                                "UNABLE TO PARSE CHIRAQ DATA TO JSON: {error}"
-                                            )),
-                                        )))
-                                    },
-                                    |shotspotter_data| {
-                                        is_loading.set(false);
-                                        get_chiraq_response.set(Some(Ok(shotspotter_data)))
-                                    },
-                                )
-                            }
-                            _ => {
-                                error!(format!("{:?}", response));
-
-                                is_loading.set(false);
-                                get_chiraq_response.set(Some(Err(
-                                    Response::status_500_with_message(
-                                        "No API response".to_string(),
-                                    ),
-                                )));
-                            }
-                        },
-                        Err(error) => {
-                            error!(format!("{:?}", error));
-
-                            is_loading.set(false);
-                            get_chiraq_response.set(Some(Err(Response::status_500_with_message(
-                                format!("UNABLE TO GET CHIRAQ DATA FROM THE API: {error}"),
-                            ))));
-                        }
-                    }
-                });
-            },
-            (),
-        )
-    }
+    use_effect_with_deps(
+        move |_| {
+            background::set_background(true);
+
+            open_graph::set_open_graph_tag(OpenGraphTag::Description(
+                "Visualizing violence in Chicago".to_string(),
+            ))
+            .unwrap_or_else(|error| error!(error.to_string()));
+            open_graph::set_open_graph_tag(OpenGraphTag::ImageLink(FAVICON_GIF.to_string()))
+                .unwrap_or_else(|error| error!(error.to_string()));
+            open_graph::set_open_graph_tag(OpenGraphTag::PageType(PageType::Website))
+                .unwrap_or_else(|error| error!(error.to_string()));
+            open_graph::set_open_graph_tag(OpenGraphTag::Title("jl | violence".to_string()))
+                .unwrap_or_else(|error| error!(error.to_string()));
+
+            || ()
+        },
+        (),
+    );
 
-    let chiraq_response = get_chiraq_response
-        .as_ref()
-        .unwrap_or(&Ok(ChicagoMapData::default()))
-        .to_owned();
+    let chiraq = use_api((), |_| api::get_chiraq());
+
+    let chiraq_response = match (&chiraq.data, &chiraq.error) {
+        (_, Some(error)) => Err(error.clone()),
+        (Some(chicago_map_data), None) => Ok(chicago_map_data.as_ref().clone()),
+        (None, None) => Ok(ChicagoMapData::default()),
+    };
 
     let (dates, map, tables) = match chiraq_response {
         Ok(chicago_map_data) => {
@@ -294,7 +242,7 @@ pub fn violence() -> Html {
 
     utils::create_page_with_nav(
         None,
-        if *is_loading {
+        if chiraq.loading {
             html! { <Loading /> }
         } else {
             // NOTE:
@@ -0,0 +1,112 @@
+//! The Webmentions section rendered underneath a blog post.
+
+use gloo_console::error;
+use yew::prelude::*;
+
+use crate::{
+    api::{self, hook::use_api},
+    models::webmention::{MentionType, Webmention},
+};
+
+/// Contains properties that may be passed into the `Webmentions` component.
+#[derive(Debug, Eq, PartialEq, Properties)]
+pub struct WebmentionsProps {
+    /// The post's ID. This is used to query the API for that particular post's Webmentions.
+    pub post_id: String,
+}
+
+/// The section containing all likes, replies, and mentions this post received from around the web.
+/// Nothing is rendered if the post has not received any Webmentions.
+#[function_component(Webmentions)]
+pub fn webmentions(props: &WebmentionsProps) -> Html {
+    let post_id = props.post_id.clone();
+
+    // Failing to load Webmentions should not get in the way of reading the post, so errors are
+    // only logged.
+    let webmentions = use_api(post_id, |post_id| async move {
+        api::get_webmentions(&post_id).await.map_err(|error| {
+            error!(format!("{:?}", error));
+            error
+        })
+    });
+
+    let Some(all_webmentions) = webmentions.data.as_deref() else {
+        return html! { <></> };
+    };
+    if all_webmentions.webmentions.is_empty() {
+        return html! { <></> };
+    }
+
+    let of_type = |mention_type: MentionType| -> Vec<&Webmention> {
+        all_webmentions
+            .webmentions
+            .iter()
+            .filter(|webmention| webmention.mention_type == mention_type)
+            .collect()
+    };
+    let likes = of_type(MentionType::Like);
+    let mentions = of_type(MentionType::Mention);
+    let replies = of_type(MentionType::Reply);
+
+    html! {
+        <div class="webmentions fade-in-slide-down">
+          <div class="rusty-line-thicc"></div>
+          <h4>{ "from around the web" }</h4>
+          {
+              if !likes.is_empty() {
+                  html! {
+                      <p class="comment-meta">
+                        <b>{ format!("♥ {} likes ", likes.len()) }</b>
+                        { for likes.iter().map(|like| make_source_link(like)) }
+                      </p>
+                  }
+              } else {
+                  html! { <></> }
+              }
+          }
+          { for replies.iter().map(|reply| make_reply(reply)) }
+          {
+              if !mentions.is_empty() {
+                  html! {
+                      <div class="comment-meta">
+                        <b>{ "mentioned by" }</b>
+                        <ul>
+                          { for mentions.iter().map(|mention| html! { <li>{ make_source_link(mention) }</li> }) }
+                        </ul>
+                      </div>
+                  }
+              } else {
+                  html! { <></> }
+              }
+          }
+        </div>
+    }
+}
+
+/// Create a link to the page that sent the Webmention, labeled with its author or title.
+fn make_source_link(webmention: &Webmention) -> Html {
+    let label = webmention
+        .author_name
+        .clone()
+        .or_else(|| webmention.source_title.clone())
+        .unwrap_or_else(|| webmention.source.clone());
+
+    html! {
+        <a class="webmention-source" href={ webmention.source.clone() } rel="nofollow noopener">
+          { label }
+        </a>
+    }
+}
+
+/// Create a single reply, styled like a comment.
+fn make_reply(webmention: &Webmention) -> Html {
+    html! {
+        <div class="comment">
+          <div class="comment-meta">
+            { make_source_link(webmention) }
+            { format!(" | {}", webmention.verified) }
+          </div>
+          <p class="comment-body">{ webmention.content.clone().unwrap_or_default() }</p>
+        </div>
+    }
+}
@@ -11,6 +11,12 @@ pub enum Route {
     /// Blog page.
     #[at("/blog")]
     Blog,
+    /// Confirms a newsletter subscription with the token from the confirmation email.
+    #[at("/newsletter/confirm/:token")]
+    NewsletterConfirm { token: String },
+    /// Unsubscribes from the newsletter with the signed link from a newsletter email.
+    #[at("/newsletter/unsubscribe")]
+    NewsletterUnsubscribe,
     /// 404 page for fuckers tryna dox me or some shit.
     #[not_found]
     #[at("/404")]
@@ -18,6 +24,12 @@ pub enum Route {
     /// Single post view.
     #[at("/blog/post/:post_id")]
     PostView { post_id: String },
+    /// The revision history for a single post.
+    #[at("/blog/post/:post_id/revisions")]
+    PostRevisions { post_id: String },
+    /// The archive of every story shown on the 404 page.
+    #[at("/stories")]
+    Stories,
     /// Root page (landing page).
     #[at("/")]
     Root,
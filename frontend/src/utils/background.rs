@@ -1,16 +1,40 @@
 //! Contains utilities pertaining to setting the dynamic GIF background.
 
+use std::cell::Cell;
+
 use gloo_console::error;
-use gloo_net::http::Request;
+use gloo_timers::future::TimeoutFuture;
 use lazy_static::lazy_static;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{HtmlElement, HtmlImageElement};
 
-use crate::errors::StaccError;
+use crate::{api, errors::StaccError, models::background::Background};
 
 lazy_static! {
     /// The path to the `STATIC.gif` GIF that is set as the default background GIF.
     static ref STATIC_GIF: &'static str = "../../STATIC.gif";
 }
 
+/// The ID of the element that displays the background GIF's attribution.
+const ATTRIBUTION_ID: &str = "background-attribution";
+
+/// The ID of the element that a new background GIF fades in on before it replaces the old one.
+const FADE_ID: &str = "background-fade";
+
+/// How long the crossfade to a new background GIF takes, in milliseconds. This matches the
+/// transition on `.background-fade` in `styles.css`.
+const FADE_MS: u32 = 800;
+
+/// The minimum amount of time the `STATIC.gif` placeholder is shown for, in milliseconds.
+const MIN_STATIC_MS: f64 = 1000.0;
+
+thread_local! {
+    /// Incremented every time a new background GIF is requested, so that a slow request cannot
+    /// replace the background chosen by a newer one.
+    static GENERATION: Cell<u32> = Cell::new(0);
+}
+
 /// Make a `GET` request to the API to grab a random background GIF, then set the background image
 /// of the site.
 pub fn set_background(force_refresh: bool) {
@@ -34,35 +58,172 @@ fn check_background_cookie() -> Option<String> {
     wasm_cookies::get("background").and_then(Result::ok)
 }
 
-/// Get a new background GIF link from the API, then set the background after reading the GIF link
-/// from the `background` cookie.
+/// Get a new background GIF from the API. The `STATIC.gif` placeholder is shown while the new GIF
+/// is requested and preloaded, then the background crossfades to it once it has been decoded.
+/// Falls back to the `background` cookie if the API's response could not be read.
 fn get_new_background() {
+    let generation = GENERATION.with(|generation| {
+        generation.set(generation.get().wrapping_add(1));
+        generation.get()
+    });
+    let started = js_sys::Date::now();
+
     if let Err(error) = set_background_gif(&STATIC_GIF) {
         error!(error.to_string());
     }
+    set_attribution(None);
 
     wasm_bindgen_futures::spawn_local(async move {
-        if let Err(error) = Request::get("/api/background").send().await {
-            error!("FAILED TO GET A NEW BACKGROUND GIF FROM THE API! DEFAULTING.");
+        let background = api::get_background()
+            .await
+            .map_err(|response| {
+                error!("FAILED TO GET A NEW BACKGROUND GIF FROM THE API! DEFAULTING.");
+                error!(response.message);
+            })
+            .ok();
+
+        let Some(gif_link) = background
+            .as_ref()
+            .map(|background| background.link.clone())
+            .or_else(check_background_cookie)
+        else {
+            return;
+        };
+
+        if let Err(error) = preload_gif(&gif_link).await {
+            error!("FAILED TO LOAD THE BACKGROUND GIF! KEEPING THE PLACEHOLDER.");
+            error!(error.to_string());
+            return;
+        }
+
+        let elapsed = js_sys::Date::now() - started;
+        if elapsed < MIN_STATIC_MS {
+            TimeoutFuture::new((MIN_STATIC_MS - elapsed) as u32).await;
+        }
+
+        if is_stale(generation) {
+            return;
+        }
+
+        let dominant_color = background
+            .as_ref()
+            .and_then(|background| background.dominant_color.as_deref());
+        if let Err(error) = set_background_color(dominant_color) {
             error!(error.to_string());
         }
 
-        let gif_link = check_background_cookie().unwrap_or(STATIC_GIF.to_string());
-        if let Err(error) = set_background_gif(&gif_link) {
+        if let Err(error) = crossfade_to(&gif_link, generation).await {
             error!(error.to_string());
         }
+        if !is_stale(generation) {
+            set_attribution(background.as_ref());
+        }
     })
 }
 
+/// Check whether a newer background GIF has been requested since the given one.
+fn is_stale(generation: u32) -> bool {
+    GENERATION.with(|current| current.get() != generation)
+}
+
+/// Download and decode a GIF so that it is displayed immediately once it is set as the background.
+async fn preload_gif(gif_link: &str) -> Result<(), StaccError> {
+    let image = HtmlImageElement::new()?;
+    image.set_src(gif_link);
+
+    JsFuture::from(image.decode()).await?;
+
+    Ok(())
+}
+
+/// Fade the new GIF in on top of the current background, then swap it in as the background itself
+/// and hide the fade layer again. Sets the background directly if the fade layer is missing.
+async fn crossfade_to(gif_link: &str, generation: u32) -> Result<(), StaccError> {
+    let Some(layer) = gloo_utils::document()
+        .get_element_by_id(FADE_ID)
+        .and_then(|element| element.dyn_into::<HtmlElement>().ok())
+    else {
+        return set_background_gif(gif_link);
+    };
+
+    let style = layer.style();
+    style.set_property("background-image", &background_image(gif_link))?;
+    style.set_property("opacity", "1")?;
+
+    TimeoutFuture::new(FADE_MS).await;
+
+    if !is_stale(generation) {
+        set_background_gif(gif_link)?;
+    }
+
+    // Hide the layer without fading it out. Reading the layout in between makes the browser apply
+    // the opacity before the transition is restored.
+    style.set_property("transition", "none")?;
+    style.set_property("opacity", "0")?;
+    layer.offset_width();
+    style.remove_property("transition")?;
+
+    Ok(())
+}
+
+/// Fill in the attribution overlay with the background's title and source. The overlay is
+/// emptied, and therefore hidden, if there is nothing to attribute. Text is set as text content
+/// rather than HTML, and the source is only linked if it is an `http(s)` link.
+fn set_attribution(background: Option<&Background>) {
+    let document = gloo_utils::document();
+    let Some(overlay) = document.get_element_by_id(ATTRIBUTION_ID) else {
+        return;
+    };
+    overlay.set_text_content(None);
+
+    let Some(background) = background else {
+        return;
+    };
+
+    let text = match (&background.title, &background.source) {
+        (Some(title), Some(source)) => format!("{title} | {source}"),
+        (Some(text), None) | (None, Some(text)) => text.to_string(),
+        (None, None) => return,
+    };
+
+    let source_link = background
+        .source_link
+        .as_deref()
+        .filter(|link| link.starts_with("https://") || link.starts_with("http://"));
+
+    match source_link.map(|link| (link, document.create_element("a"))) {
+        Some((link, Ok(anchor))) => {
+            anchor.set_text_content(Some(&text));
+            if let Err(error) = anchor
+                .set_attribute("href", link)
+                .and_then(|_| anchor.set_attribute("rel", "noopener noreferrer"))
+                .and_then(|_| overlay.append_child(&anchor).map(|_| ()))
+            {
+                error!(error);
+            }
+        }
+        _ => overlay.set_text_content(Some(&text)),
+    }
+}
+
+/// Set the color shown behind the background GIF while it loads.
+fn set_background_color(dominant_color: Option<&str>) -> Result<(), StaccError> {
+    let style = gloo_utils::body().style();
+
+    match dominant_color {
+        Some(color) => style.set_property("background-color", color)?,
+        None => style.remove_property("background-color").map(|_| ())?,
+    }
+
+    Ok(())
+}
+
 /// Set the background image of the page by modifying the `document.body` element.
 fn set_background_gif(gif_link: &str) -> Result<(), StaccError> {
     let style = gloo_utils::body().style();
 
     style.set_property("background-attachment", "fixed")?;
-    style.set_property(
-        "background-image",
-        &format!("linear-gradient(rgba(0, 0, 0, 0.7), rgba(0, 0, 0, 0.7)), url({gif_link})"),
-    )?;
+    style.set_property("background-image", &background_image(gif_link))?;
     style.set_property("background-position", "center center")?;
     style.set_property("background-repeat", "no-repeat")?;
     style.set_property("background-size", "cover")?;
@@ -72,3 +233,8 @@ fn set_background_gif(gif_link: &str) -> Result<(), StaccError> {
 
     Ok(())
 }
+
+/// Get the `background-image` value for a GIF, darkened so that text stays readable on top of it.
+fn background_image(gif_link: &str) -> String {
+    format!("linear-gradient(rgba(0, 0, 0, 0.7), rgba(0, 0, 0, 0.7)), url({gif_link})")
+}
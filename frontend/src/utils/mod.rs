@@ -3,3 +3,4 @@
 pub mod background;
 pub mod date;
 pub mod open_graph;
+pub mod service_worker;
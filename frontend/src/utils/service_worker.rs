@@ -0,0 +1,27 @@
+//! Contains the registration of the service worker (`sw.js`), which caches the app and recently
+//! read posts so that they can be read offline.
+
+use gloo_console::error;
+use js_sys::Reflect;
+use wasm_bindgen_futures::JsFuture;
+
+/// Where the service worker is served from. It has to be served from the root of the site to
+/// control every page.
+const SERVICE_WORKER_URL: &str = "/sw.js";
+
+/// Register the service worker if the browser supports them. Browsers only expose service workers
+/// to secure origins, so this does nothing over plain HTTP (except on `localhost`).
+pub fn register_service_worker() {
+    let navigator = gloo_utils::window().navigator();
+    if !Reflect::has(&navigator, &"serviceWorker".into()).unwrap_or(false) {
+        return;
+    }
+
+    let registration = navigator.service_worker().register(SERVICE_WORKER_URL);
+    wasm_bindgen_futures::spawn_local(async move {
+        if let Err(error) = JsFuture::from(registration).await {
+            error!("Failed to register the service worker!");
+            error!(error);
+        }
+    });
+}
@@ -0,0 +1,345 @@
+// The site's service worker. It keeps the app shell and recently read posts cached so that posts
+// can be read offline, and queues the visits it serves from the cache so that they can be sent to
+// the API's visitor beacon once the reader is back online.
+//
+// Bump `VERSION` whenever the caching below changes, so that the old caches are dropped.
+
+const VERSION = "v2";
+
+// The app shell: `index.html`, the files Trunk links from it, and the site's own media.
+const SHELL_CACHE = `stacc-shell-${VERSION}`;
+// Recently read posts, and the list of posts.
+const POSTS_CACHE = `stacc-posts-${VERSION}`;
+
+// Cached when the service worker is installed, along with every file `index.html` links to.
+const SHELL_FILES = [
+  "/",
+  "/STATIC.gif",
+  "/nonsense.gif",
+  "/icon-192.png",
+  "/icon-512.png",
+  "/manifest.webmanifest",
+];
+
+// Matches the files Trunk names with a content hash, e.g. `/frontend-0123456789abcdef_bg.wasm`.
+// Old versions of these are dropped whenever a new `index.html` is cached.
+const HASHED_FILE = /-[0-9a-f]{16}(_bg)?\.[a-z]+$/;
+
+// The number of posts kept for offline reading. The least recently read are dropped first.
+const MAX_CACHED_POSTS = 20;
+
+// The route of the list of posts.
+const POSTS_PATH = "/api/blog/posts";
+// Matches the route of a single post, capturing its ID.
+const POST_PATH = /^\/api\/blog\/post\/([^/]+)$/;
+
+// The API's visitor beacon, and the Background Sync tag that sends it.
+const BEACON_PATH = "/api/visits";
+const BEACON_SYNC_TAG = "visitor-beacon";
+
+// The most queued views sent in one beacon, which is also the most post IDs the API accepts in one.
+// The API counts each post once per beacon, and at most 10 visits.
+const MAX_BEACON_VIEWS = 50;
+
+// The most views kept in the queue. Views past this are dropped.
+const MAX_QUEUED_VIEWS = 500;
+
+// The IndexedDB database and object store that queued views are kept in.
+const QUEUE_DB = "stacc-visitor-beacon";
+const QUEUE_STORE = "views";
+
+self.addEventListener("install", (event) => {
+  event.waitUntil(refreshShell().then(() => self.skipWaiting()));
+});
+
+self.addEventListener("activate", (event) => {
+  event.waitUntil(
+    (async () => {
+      const current = [SHELL_CACHE, POSTS_CACHE];
+      for (const name of await caches.keys()) {
+        if (!current.includes(name)) {
+          await caches.delete(name);
+        }
+      }
+
+      await self.clients.claim();
+      await flushBeacon().catch(() => undefined);
+    })(),
+  );
+});
+
+self.addEventListener("fetch", (event) => {
+  const { request } = event;
+  if (request.method !== "GET" || request.headers.has("range")) {
+    return;
+  }
+
+  // Files from CDNs are left to the browser. Fetching them from here would count as a connection
+  // under the site's Content Security Policy, which only allows connecting to the site itself.
+  const url = new URL(request.url);
+  if (url.origin !== self.location.origin) {
+    return;
+  }
+
+  if (request.mode === "navigate") {
+    event.respondWith(navigate(event));
+  } else if (url.pathname === POSTS_PATH || POST_PATH.test(url.pathname)) {
+    event.respondWith(networkFirst(event, url));
+  } else if (!url.pathname.startsWith("/api/")) {
+    event.respondWith(cacheFirst(request, SHELL_CACHE));
+  }
+});
+
+self.addEventListener("sync", (event) => {
+  if (event.tag === BEACON_SYNC_TAG) {
+    event.waitUntil(flushBeacon());
+  }
+});
+
+// Every route serves the same `index.html`, so navigations load it from the network, keeping the
+// latest copy as the shell, and fall back to the cached shell while offline.
+async function navigate(event) {
+  try {
+    const response = await fetch(event.request);
+    if (response.ok) {
+      event.waitUntil(refreshShell(response.clone()).catch(() => undefined));
+      event.waitUntil(flushBeacon().catch(() => undefined));
+    }
+
+    return response;
+  } catch (error) {
+    const shell = await caches.match("/", { cacheName: SHELL_CACHE });
+    if (shell) {
+      return shell;
+    }
+
+    throw error;
+  }
+}
+
+// Load posts from the network, caching each one, and fall back to the cache while offline. Posts
+// served from the cache are queued for the visitor beacon.
+async function networkFirst(event, url) {
+  const cache = await caches.open(POSTS_CACHE);
+
+  try {
+    const response = await fetch(event.request);
+    if (response.ok) {
+      // Deleting first moves the post to the end of the cache's keys, which keeps them ordered
+      // from least to most recently read.
+      await cache.delete(event.request);
+      await cache.put(event.request, response.clone());
+
+      event.waitUntil(trimPosts(cache));
+      event.waitUntil(flushBeacon().catch(() => undefined));
+    }
+
+    return response;
+  } catch {
+    const cached = await cache.match(event.request);
+    if (!cached) {
+      return offlineProblem();
+    }
+
+    const match = url.pathname.match(POST_PATH);
+    event.waitUntil(queueView(match ? decodeURIComponent(match[1]) : null));
+
+    return cached;
+  }
+}
+
+// Load a file from the cache, or from the network if it has not been cached yet.
+async function cacheFirst(request, cacheName) {
+  const cache = await caches.open(cacheName);
+
+  const cached = await cache.match(request);
+  if (cached) {
+    return cached;
+  }
+
+  const response = await fetch(request);
+  // Stylesheets loaded without `crossorigin` are opaque, but are still worth keeping.
+  if (response.ok || response.type === "opaque") {
+    await cache.put(request, response.clone());
+  }
+
+  return response;
+}
+
+// The error the API client shows when a request cannot be served while offline. This has the same
+// shape as the API's own errors.
+function offlineProblem() {
+  return new Response(
+    JSON.stringify({
+      code: "offline",
+      detail:
+        "You're offline, and this hasn't been saved for offline reading. Try again once you're back online.",
+      status: 503,
+      title: "Offline",
+      type: "urn:stacc:problem:offline",
+    }),
+    {
+      headers: { "Content-Type": "application/problem+json" },
+      status: 503,
+    },
+  );
+}
+
+// Cache `index.html` and every file it links to, then drop the hashed files an older
+// `index.html` linked to. Fetches `index.html` if it is not given.
+async function refreshShell(index) {
+  const cache = await caches.open(SHELL_CACHE);
+
+  const response = index ?? (await fetch("/", { cache: "no-cache" }));
+  if (!response.ok) {
+    return;
+  }
+
+  const files = new Set([
+    ...SHELL_FILES,
+    ...linkedFiles(await response.clone().text()),
+  ]);
+  await cache.put("/", response);
+
+  await Promise.all(
+    [...files].map(async (file) => {
+      if (!(await cache.match(file))) {
+        // A missing file should not stop the rest of the shell from being cached.
+        await cache.add(file).catch(() => undefined);
+      }
+    }),
+  );
+
+  for (const request of await cache.keys()) {
+    const { pathname } = new URL(request.url);
+    if (HASHED_FILE.test(pathname) && !files.has(pathname)) {
+      await cache.delete(request);
+    }
+  }
+}
+
+// Find the same-origin files an `index.html` links to, e.g. the WASM bundle and the stylesheet.
+function linkedFiles(html) {
+  const files = [];
+
+  for (const [, path] of html.matchAll(
+    /["'](\/[^"'\s]+\.(?:css|gif|ico|js|png|svg|wasm|webmanifest))["']/g,
+  )) {
+    const url = new URL(path, self.location.origin);
+    if (url.origin === self.location.origin) {
+      files.push(url.pathname);
+    }
+  }
+
+  return files;
+}
+
+// Drop the least recently read posts once there are more than `MAX_CACHED_POSTS`.
+async function trimPosts(cache) {
+  const posts = (await cache.keys()).filter((request) =>
+    POST_PATH.test(new URL(request.url).pathname),
+  );
+
+  for (const request of posts.slice(0, -MAX_CACHED_POSTS)) {
+    await cache.delete(request);
+  }
+}
+
+// Queue a visit for the visitor beacon, along with a view of the post if one was read, then ask
+// for a Background Sync to send it. Browsers without Background Sync send the queue after the
+// next request that reaches the network.
+async function queueView(postId) {
+  const db = await openQueue();
+
+  const count = await idbResult(
+    db.transaction(QUEUE_STORE).objectStore(QUEUE_STORE).count(),
+  );
+  if (count < MAX_QUEUED_VIEWS) {
+    await idbResult(
+      db
+        .transaction(QUEUE_STORE, "readwrite")
+        .objectStore(QUEUE_STORE)
+        .add({ post_id: postId }),
+    );
+  }
+
+  db.close();
+
+  try {
+    await self.registration.sync?.register(BEACON_SYNC_TAG);
+  } catch {
+    // Background Sync may be unavailable or denied, in which case the queue is sent later.
+  }
+}
+
+// The queue currently being sent, so that it is only sent once at a time.
+let flushing = null;
+
+// Send every queued view to the visitor beacon. Rejects if the API could not be reached, so that
+// Background Sync tries again later.
+function flushBeacon() {
+  flushing ??= sendQueue().finally(() => {
+    flushing = null;
+  });
+
+  return flushing;
+}
+
+// Send the queued views in batches the API accepts, removing each batch once it has been sent.
+async function sendQueue() {
+  const db = await openQueue();
+
+  try {
+    const store = db.transaction(QUEUE_STORE).objectStore(QUEUE_STORE);
+    const [keys, views] = await Promise.all([
+      idbResult(store.getAllKeys()),
+      idbResult(store.getAll()),
+    ]);
+
+    for (let start = 0; start < views.length; start += MAX_BEACON_VIEWS) {
+      const batch = views.slice(start, start + MAX_BEACON_VIEWS);
+
+      const response = await fetch(BEACON_PATH, {
+        body: JSON.stringify({
+          post_ids: [
+            ...new Set(batch.map((view) => view.post_id).filter(Boolean)),
+          ],
+          visits: batch.length,
+        }),
+        headers: { "Content-Type": "application/json" },
+        method: "POST",
+      });
+      // Rate limited or unavailable, so try the rest again later. Any other error will not go away
+      // by retrying, so that batch is dropped.
+      if (response.status === 429 || response.status >= 500) {
+        throw new Error(`the visitor beacon failed with HTTP ${response.status}`);
+      }
+
+      await idbResult(
+        db
+          .transaction(QUEUE_STORE, "readwrite")
+          .objectStore(QUEUE_STORE)
+          .delete(IDBKeyRange.bound(keys[start], keys[start + batch.length - 1])),
+      );
+    }
+  } finally {
+    db.close();
+  }
+}
+
+// Open the database that queued views are kept in, creating it if it does not exist.
+function openQueue() {
+  const open = indexedDB.open(QUEUE_DB, 1);
+  open.onupgradeneeded = () =>
+    open.result.createObjectStore(QUEUE_STORE, { autoIncrement: true });
+
+  return idbResult(open);
+}
+
+// Wait for an IndexedDB request to finish.
+function idbResult(idbRequest) {
+  return new Promise((resolve, reject) => {
+    idbRequest.onsuccess = () => resolve(idbRequest.result);
+    idbRequest.onerror = () => reject(idbRequest.error);
+  });
+}
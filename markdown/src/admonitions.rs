@@ -0,0 +1,33 @@
+//! Renders GitHub-style alert blockquotes as admonition blocks.
+
+use pulldown_cmark::{BlockQuoteKind, CowStr, Event, Tag, TagEnd};
+
+/// Replace every alert blockquote (`> [!NOTE]` and friends) with an admonition `div` containing a
+/// title. Ordinary blockquotes are left alone.
+pub fn render_admonitions(events: Vec<Event>) -> Vec<Event> {
+    events
+        .into_iter()
+        .map(|event| match event {
+            Event::Start(Tag::BlockQuote(Some(kind))) => {
+                let name = kind_name(kind);
+
+                Event::Html(CowStr::from(format!(
+                    "<div class=\"admonition admonition-{name}\">\n<p class=\"admonition-title\">{name}</p>\n"
+                )))
+            }
+            Event::End(TagEnd::BlockQuote(Some(_))) => Event::Html(CowStr::from("</div>\n")),
+            event => event,
+        })
+        .collect()
+}
+
+/// The lowercase name of an admonition, used for both its CSS class and its title.
+fn kind_name(kind: BlockQuoteKind) -> &'static str {
+    match kind {
+        BlockQuoteKind::Caution => "caution",
+        BlockQuoteKind::Important => "important",
+        BlockQuoteKind::Note => "note",
+        BlockQuoteKind::Tip => "tip",
+        BlockQuoteKind::Warning => "warning",
+    }
+}
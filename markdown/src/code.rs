@@ -0,0 +1,198 @@
+//! Renders code blocks with syntax highlighting, line numbers, highlighted lines, and a copy
+//! button.
+//!
+//! Highlighting happens while rendering, so it works the same on the server and in WASM. Tokens
+//! are wrapped in spans classed with their TextMate scopes (e.g. `hl-keyword hl-control hl-rust`),
+//! and the site's stylesheet decides what each scope looks like.
+
+use std::{ops::RangeInclusive, sync::OnceLock};
+
+use pulldown_cmark::{CodeBlockKind, CowStr, Event, Tag, TagEnd};
+use syntect::{
+    html::{self, ClassStyle},
+    parsing::{ParseState, Scope, ScopeStack, SyntaxReference, SyntaxSet},
+    util::LinesWithEndings,
+};
+
+use crate::escape;
+
+/// The prefix of every class added to highlighted tokens.
+pub const CLASS_PREFIX: &str = "hl-";
+
+/// How highlighted tokens are classed.
+const CLASS_STYLE: ClassStyle = ClassStyle::SpacedPrefixed {
+    prefix: CLASS_PREFIX,
+};
+
+/// The syntaxes that code blocks can be highlighted with. Loading them takes a while, so they are
+/// loaded once, the first time a code block is rendered.
+fn syntax_set() -> &'static SyntaxSet {
+    static SYNTAX_SET: OnceLock<SyntaxSet> = OnceLock::new();
+
+    SYNTAX_SET.get_or_init(SyntaxSet::load_defaults_newlines)
+}
+
+/// Replace every code block with a highlighted one.
+///
+/// The fence's info string picks the language, and may end with the lines to highlight in braces,
+/// e.g. `` ```rust {1,4-6} ``. Languages without a syntax, and indented code blocks, are rendered
+/// as plain text, but still get line numbers and a copy button.
+pub fn render_code_blocks(events: Vec<Event>) -> Vec<Event> {
+    let mut rendered = Vec::with_capacity(events.len());
+
+    let mut events = events.into_iter();
+    while let Some(event) = events.next() {
+        let Event::Start(Tag::CodeBlock(kind)) = event else {
+            rendered.push(event);
+            continue;
+        };
+
+        let code: String = events
+            .by_ref()
+            .take_while(|event| !matches!(event, Event::End(TagEnd::CodeBlock)))
+            .filter_map(|event| match event {
+                Event::Text(text) => Some(text.into_string()),
+                _ => None,
+            })
+            .collect();
+
+        let info = match &kind {
+            CodeBlockKind::Fenced(info) => FenceInfo::parse(info),
+            CodeBlockKind::Indented => FenceInfo::default(),
+        };
+
+        rendered.push(Event::Html(CowStr::from(code_block(&code, &info))));
+    }
+
+    rendered
+}
+
+/// The parts of a fence's info string that affect rendering.
+#[derive(Debug, Default)]
+struct FenceInfo {
+    /// The language, if one was given.
+    language: Option<String>,
+    /// The ranges of line numbers to highlight, starting from 1. These are kept as ranges rather
+    /// than expanded, since a range like `{1-4000000000}` is far longer than any code block.
+    highlighted_lines: Vec<RangeInclusive<usize>>,
+}
+
+impl FenceInfo {
+    /// Parse an info string like `rust {1,4-6}`. The language is the first word, up to any comma
+    /// (so rustdoc's `rust,ignore` still highlights as Rust). Ranges that cannot be parsed are
+    /// ignored.
+    fn parse(info: &str) -> Self {
+        let (language, rest) = match info.find('{') {
+            Some(start) => (&info[..start], &info[start + 1..]),
+            None => (info, ""),
+        };
+
+        let language = language
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .find(|word| !word.is_empty())
+            .map(str::to_string);
+
+        let highlighted_lines = rest
+            .split('}')
+            .next()
+            .unwrap_or_default()
+            .split(',')
+            .filter_map(|range| {
+                let range = range.trim();
+                let (start, end) = range.split_once('-').unwrap_or((range, range));
+
+                Some(start.trim().parse::<usize>().ok()?..=end.trim().parse::<usize>().ok()?)
+            })
+            .collect();
+
+        Self {
+            language,
+            highlighted_lines,
+        }
+    }
+
+    /// Whether the given line, starting from 1, should be highlighted.
+    fn is_highlighted(&self, line: usize) -> bool {
+        self.highlighted_lines
+            .iter()
+            .any(|range| range.contains(&line))
+    }
+}
+
+/// Create the HTML for a code block. Every line is wrapped in a `code-line` span, which the
+/// stylesheet numbers, and highlighted lines also get the `highlighted-line` class.
+fn code_block(code: &str, info: &FenceInfo) -> String {
+    let syntax = info
+        .language
+        .as_deref()
+        .and_then(|language| syntax_set().find_syntax_by_token(language));
+
+    let lines = syntax
+        .and_then(|syntax| highlight(code, syntax).ok())
+        .unwrap_or_else(|| {
+            LinesWithEndings::from(code)
+                .map(|line| escape(line.trim_end_matches(['\n', '\r'])))
+                .collect()
+        });
+
+    let mut html = String::from(
+        "<div class=\"code-block\"><button aria-label=\"Copy code\" class=\"code-copy\" type=\"button\">Copy</button><pre><code",
+    );
+    if let Some(language) = &info.language {
+        html.push_str(&format!(" class=\"language-{}\"", escape(language)));
+    }
+    html.push('>');
+
+    for (index, line) in lines.iter().enumerate() {
+        if info.is_highlighted(index + 1) {
+            html.push_str("<span class=\"code-line highlighted-line\">");
+        } else {
+            html.push_str("<span class=\"code-line\">");
+        }
+        html.push_str(line);
+        html.push_str("</span>\n");
+    }
+
+    html.push_str("</code></pre></div>\n");
+
+    html
+}
+
+/// Highlight code, returning the HTML for each line without its line ending. Scopes that span
+/// several lines are closed at the end of each line and reopened at the start of the next, so that
+/// every line's spans are balanced and it can be wrapped on its own.
+fn highlight(code: &str, syntax: &SyntaxReference) -> Result<Vec<String>, syntect::Error> {
+    let mut parse_state = ParseState::new(syntax);
+    let mut scope_stack = ScopeStack::new();
+    let mut lines = Vec::new();
+
+    for line in LinesWithEndings::from(code) {
+        let mut html = String::new();
+        for scope in scope_stack.as_slice() {
+            html.push_str(&open_span(*scope));
+        }
+
+        let ops = parse_state.parse_line(line, syntax_set())?;
+        let (spans, _delta) =
+            html::line_tokens_to_classed_spans(line, &ops, CLASS_STYLE, &mut scope_stack)?;
+        // The line ending is the only newline in the line, and the `code-line` spans are joined
+        // with their own.
+        html.push_str(&spans.replace(['\n', '\r'], ""));
+
+        html.push_str(&"</span>".repeat(scope_stack.len()));
+        lines.push(html);
+    }
+
+    Ok(lines)
+}
+
+/// Create the opening tag of a span classed with a scope, e.g. `<span class="hl-source hl-rust">`.
+fn open_span(scope: Scope) -> String {
+    let classes: Vec<String> = scope
+        .build_string()
+        .split('.')
+        .map(|atom| format!("{CLASS_PREFIX}{}", escape(atom)))
+        .collect();
+
+    format!("<span class=\"{}\">", classes.join(" "))
+}
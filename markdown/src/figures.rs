@@ -0,0 +1,85 @@
+//! Renders images as lazy-loaded `img` elements, and images on their own line as captioned
+//! figures.
+
+use pulldown_cmark::{CowStr, Event, Tag, TagEnd};
+
+use crate::{escape, escape_url, headings::plain_text};
+
+/// Replace every image with a lazy-loaded `img` element. A paragraph containing nothing but an
+/// image becomes a `figure` instead, captioned with the image's title or alt text.
+pub fn render_images(events: Vec<Event>) -> Vec<Event> {
+    let mut rendered = Vec::with_capacity(events.len());
+
+    let mut events = events.into_iter().peekable();
+    while let Some(event) = events.next() {
+        match event {
+            Event::Start(Tag::Paragraph) => {
+                let Some(Event::Start(Tag::Image {
+                    dest_url, title, ..
+                })) = events.next_if(|event| matches!(event, Event::Start(Tag::Image { .. })))
+                else {
+                    rendered.push(Event::Start(Tag::Paragraph));
+                    continue;
+                };
+                let alt = take_alt_text(&mut events);
+
+                // Only a lone image becomes a figure. Anything else stays a paragraph.
+                if matches!(events.peek(), Some(Event::End(TagEnd::Paragraph))) {
+                    events.next();
+                    rendered.push(Event::Html(CowStr::from(figure(&dest_url, &title, &alt))));
+                } else {
+                    rendered.push(Event::Start(Tag::Paragraph));
+                    rendered.push(Event::Html(CowStr::from(image(&dest_url, &title, &alt))));
+                }
+            }
+            Event::Start(Tag::Image {
+                dest_url, title, ..
+            }) => {
+                let alt = take_alt_text(&mut events);
+                rendered.push(Event::Html(CowStr::from(image(&dest_url, &title, &alt))));
+            }
+            event => rendered.push(event),
+        }
+    }
+
+    rendered
+}
+
+/// Consume the events up to and including the end of an image, returning its alt text.
+fn take_alt_text<'a>(events: &mut impl Iterator<Item = Event<'a>>) -> String {
+    let alt: Vec<Event> = events
+        .take_while(|event| !matches!(event, Event::End(TagEnd::Image)))
+        .collect();
+
+    plain_text(&alt)
+}
+
+/// Create a lazy-loaded `img` element.
+fn image(src: &str, title: &str, alt: &str) -> String {
+    let title = if title.is_empty() {
+        String::new()
+    } else {
+        format!(" title=\"{}\"", escape(title))
+    };
+
+    format!(
+        "<img alt=\"{}\" class=\"post-image\" decoding=\"async\" loading=\"lazy\" src=\"{}\"{title} />",
+        escape(alt),
+        escape_url(src)
+    )
+}
+
+/// Create a `figure` wrapping a lazy-loaded image, captioned with its title or alt text.
+fn figure(src: &str, title: &str, alt: &str) -> String {
+    let caption = if title.is_empty() { alt } else { title };
+    let caption = if caption.is_empty() {
+        String::new()
+    } else {
+        format!("<figcaption>{}</figcaption>", escape(caption))
+    };
+
+    format!(
+        "<figure class=\"post-figure\">{}{caption}</figure>\n",
+        image(src, "", alt)
+    )
+}
@@ -0,0 +1,95 @@
+//! Gives every heading an ID and an anchor link so that sections of a post can be linked to.
+
+use std::collections::HashMap;
+
+use pulldown_cmark::{CowStr, Event, Tag, TagEnd};
+
+use crate::escape;
+
+/// Give every heading an ID generated from its text, then append an anchor link to it. IDs set
+/// explicitly with `{#id}` are kept. Duplicate IDs get a numeric suffix, e.g. `setup-1`.
+pub fn add_anchors(events: Vec<Event>) -> Vec<Event> {
+    let mut seen_ids: HashMap<String, usize> = HashMap::new();
+    let mut anchored = Vec::with_capacity(events.len());
+
+    let mut events = events.into_iter();
+    while let Some(event) = events.next() {
+        let Event::Start(Tag::Heading {
+            attrs,
+            classes,
+            id,
+            level,
+        }) = event
+        else {
+            anchored.push(event);
+            continue;
+        };
+
+        let contents: Vec<Event> = events
+            .by_ref()
+            .take_while(|event| !matches!(event, Event::End(TagEnd::Heading(_))))
+            .collect();
+
+        let base_id = id.map_or_else(|| slugify(&plain_text(&contents)), |id| id.to_string());
+        let id = unique_id(&mut seen_ids, base_id);
+
+        anchored.push(Event::Start(Tag::Heading {
+            attrs,
+            classes,
+            id: Some(CowStr::from(id.clone())),
+            level,
+        }));
+        anchored.extend(contents);
+        anchored.push(Event::Html(CowStr::from(format!(
+            "<a aria-label=\"Link to this section\" class=\"heading-anchor\" href=\"#{}\">#</a>",
+            escape(&id)
+        ))));
+        anchored.push(Event::End(TagEnd::Heading(level)));
+    }
+
+    anchored
+}
+
+/// Collect the readable text within a run of inline events.
+pub fn plain_text(events: &[Event]) -> String {
+    events
+        .iter()
+        .filter_map(|event| match event {
+            Event::Code(text) | Event::InlineMath(text) | Event::Text(text) => Some(text.as_ref()),
+            Event::SoftBreak | Event::HardBreak => Some(" "),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Turn heading text into a URL fragment, e.g. "What's New?" becomes `whats-new`.
+fn slugify(text: &str) -> String {
+    let mut slug = String::new();
+
+    for character in text.chars().flat_map(char::to_lowercase) {
+        if character.is_alphanumeric() || character == '_' {
+            slug.push(character);
+        } else if (character.is_whitespace() || character == '-') && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+
+    let slug = slug.trim_matches('-');
+    if slug.is_empty() {
+        "section".to_string()
+    } else {
+        slug.to_string()
+    }
+}
+
+/// Make sure an ID has not been used by an earlier heading.
+fn unique_id(seen_ids: &mut HashMap<String, usize>, base_id: String) -> String {
+    let count = seen_ids.entry(base_id.clone()).or_insert(0);
+    *count += 1;
+
+    if *count == 1 {
+        base_id
+    } else {
+        format!("{base_id}-{}", *count - 1)
+    }
+}
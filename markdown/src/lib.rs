@@ -0,0 +1,75 @@
+//! The Markdown pipeline used to render blog posts and stories. Every Markdown extension that a
+//! post may use is configured here so that the frontend and any server-side rendering produce the
+//! same HTML.
+
+mod admonitions;
+mod code;
+mod figures;
+mod headings;
+mod sanitize;
+
+use pulldown_cmark::{html, Event, Options, Parser};
+
+/// The Markdown extensions that are enabled for every post.
+pub fn options() -> Options {
+    let mut options = Options::empty();
+    options.insert(Options::ENABLE_FOOTNOTES);
+    options.insert(Options::ENABLE_GFM);
+    options.insert(Options::ENABLE_HEADING_ATTRIBUTES);
+    options.insert(Options::ENABLE_MATH);
+    options.insert(Options::ENABLE_SMART_PUNCTUATION);
+    options.insert(Options::ENABLE_STRIKETHROUGH);
+    options.insert(Options::ENABLE_TABLES);
+    options.insert(Options::ENABLE_TASKLISTS);
+
+    options
+}
+
+/// Convert Markdown to HTML.
+///
+/// On top of CommonMark, this supports footnotes, task lists, tables, strikethrough, and these
+/// additions:
+///
+/// - Math between `$` (inline) or `$$` (display) delimiters, rendered into `math-inline` and
+///   `math-display` spans that KaTeX picks up in the browser.
+/// - Code blocks are syntax highlighted into spans classed with their scopes, with line numbers,
+///   highlighted lines (e.g. `` ```rust {1,4-6} ``), and a copy button.
+/// - Headings get an ID generated from their text (unless one is set with `{#id}`) and an anchor
+///   link pointing at it.
+/// - Blockquotes starting with `[!NOTE]`, `[!TIP]`, `[!IMPORTANT]`, `[!WARNING]` or `[!CAUTION]`
+///   become admonition blocks.
+/// - Images are lazy-loaded, and an image on its own line becomes a figure captioned with the
+///   image's title, or its alt text if there is no title.
+///
+/// The HTML is sanitized before it is returned, so raw HTML in a post is limited to an allowlist of
+/// elements and attributes, and every ID is prefixed with `user-content-`. See `sanitize` for the
+/// details.
+pub fn render(markdown: &str) -> String {
+    let events: Vec<Event> = Parser::new_ext(markdown, options()).collect();
+
+    let events = headings::add_anchors(events);
+    let events = figures::render_images(events);
+    let events = admonitions::render_admonitions(events);
+    let events = code::render_code_blocks(events);
+
+    let mut rendered = String::new();
+    html::push_html(&mut rendered, events.into_iter());
+
+    sanitize::sanitize(&rendered)
+}
+
+/// Escape text so that it can be placed inside HTML text or a quoted attribute value.
+fn escape(text: &str) -> String {
+    let mut escaped = String::new();
+    let _ = pulldown_cmark_escape::escape_html(&mut escaped, text);
+
+    escaped
+}
+
+/// Escape a URL so that it can be placed inside an `href` or `src` attribute.
+fn escape_url(url: &str) -> String {
+    let mut escaped = String::new();
+    let _ = pulldown_cmark_escape::escape_href(&mut escaped, url);
+
+    escaped
+}
@@ -0,0 +1,198 @@
+//! Sanitizes rendered HTML against an allowlist, so that raw HTML in a post can only use the
+//! elements, attributes, and classes that the rest of the pipeline produces.
+
+use std::{borrow::Cow, sync::OnceLock};
+
+use ammonia::Builder;
+
+use crate::code;
+
+/// The elements a post may contain. Anything else is removed, keeping its text, except for
+/// `script` and `style`, which are removed along with their contents.
+const TAGS: &[&str] = &[
+    "a",
+    "abbr",
+    "b",
+    "blockquote",
+    "br",
+    "button",
+    "code",
+    "dd",
+    "del",
+    "details",
+    "div",
+    "dl",
+    "dt",
+    "em",
+    "figcaption",
+    "figure",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "hr",
+    "i",
+    "img",
+    "input",
+    "ins",
+    "kbd",
+    "li",
+    "mark",
+    "ol",
+    "p",
+    "pre",
+    "s",
+    "samp",
+    "small",
+    "span",
+    "strong",
+    "sub",
+    "summary",
+    "sup",
+    "table",
+    "tbody",
+    "td",
+    "th",
+    "thead",
+    "tr",
+    "u",
+    "ul",
+];
+
+/// The attributes each element may have, on top of `GENERIC_ATTRIBUTES`.
+const TAG_ATTRIBUTES: &[(&str, &[&str])] = &[
+    ("a", &["aria-label", "href"]),
+    ("button", &["aria-label"]),
+    (
+        "img",
+        &["alt", "decoding", "height", "loading", "src", "width"],
+    ),
+    ("input", &["checked", "disabled", "type"]),
+    ("ol", &["start"]),
+    ("td", &["colspan", "rowspan", "style"]),
+    ("th", &["colspan", "rowspan", "style"]),
+];
+
+/// The attributes every element may have.
+const GENERIC_ATTRIBUTES: &[&str] = &["class", "id", "title"];
+
+/// The only URL schemes links and images may use.
+const URL_SCHEMES: &[&str] = &["http", "https", "mailto"];
+
+/// Prepended to every ID in a post, and to every link pointing at one, so that a post cannot
+/// clobber globals through named elements or collide with the IDs of the page around it.
+const ID_PREFIX: &str = "user-content-";
+
+/// The classes each element may have. Classes are what the site's stylesheet and scripts hook
+/// into, so posts only get the ones the pipeline itself produces.
+const CLASSES: &[(&str, &[&str])] = &[
+    ("a", &["heading-anchor"]),
+    ("button", &["code-copy"]),
+    (
+        "div",
+        &[
+            "admonition",
+            "admonition-caution",
+            "admonition-important",
+            "admonition-note",
+            "admonition-tip",
+            "admonition-warning",
+            "code-block",
+            "footnote-definition",
+        ],
+    ),
+    ("figure", &["post-figure"]),
+    ("img", &["post-image"]),
+    ("p", &["admonition-title"]),
+    (
+        "span",
+        &[
+            "code-line",
+            "highlighted-line",
+            "math",
+            "math-display",
+            "math-inline",
+        ],
+    ),
+    ("sup", &["footnote-definition-label", "footnote-reference"]),
+];
+
+/// Sanitize rendered HTML. Elements, attributes, and URL schemes outside the allowlists are
+/// removed, classes outside `CLASSES` are dropped, and `style` attributes are only kept for table
+/// cell alignment.
+pub fn sanitize(html: &str) -> String {
+    static SANITIZER: OnceLock<Builder<'static>> = OnceLock::new();
+
+    SANITIZER
+        .get_or_init(|| {
+            let mut builder = Builder::empty();
+            builder
+                .tags(TAGS.iter().copied().collect())
+                .clean_content_tags(["script", "style"].into_iter().collect())
+                .generic_attributes(GENERIC_ATTRIBUTES.iter().copied().collect())
+                .tag_attributes(
+                    TAG_ATTRIBUTES
+                        .iter()
+                        .map(|(tag, attributes)| (*tag, attributes.iter().copied().collect()))
+                        .collect(),
+                )
+                .set_tag_attribute_value("button", "type", "button")
+                .set_tag_attribute_value("input", "disabled", "")
+                .url_schemes(URL_SCHEMES.iter().copied().collect())
+                .link_rel(Some("noopener noreferrer"))
+                .strip_comments(true)
+                .attribute_filter(filter_attribute);
+
+            builder
+        })
+        .clean(html)
+        .to_string()
+}
+
+/// Check the value of an allowed attribute, returning the value to keep or `None` to remove it.
+fn filter_attribute<'a>(element: &str, attribute: &str, value: &'a str) -> Option<Cow<'a, str>> {
+    match (element, attribute) {
+        (_, "class") => {
+            let classes: Vec<&str> = value
+                .split_ascii_whitespace()
+                .filter(|class| is_allowed_class(element, class))
+                .collect();
+
+            (!classes.is_empty()).then(|| Cow::Owned(classes.join(" ")))
+        }
+        (_, "id") => Some(Cow::Owned(format!("{ID_PREFIX}{value}"))),
+        ("a", "href") => match value.strip_prefix('#') {
+            Some(fragment) => Some(Cow::Owned(format!("#{ID_PREFIX}{fragment}"))),
+            None => Some(Cow::Borrowed(value)),
+        },
+        ("input", "type") => (value == "checkbox").then_some(Cow::Borrowed(value)),
+        (_, "style") => matches!(
+            value,
+            "text-align: left" | "text-align: center" | "text-align: right"
+        )
+        .then_some(Cow::Borrowed(value)),
+        _ => Some(Cow::Borrowed(value)),
+    }
+}
+
+/// Check whether an element may have a class. Code blocks may also have a `language-*` class, and
+/// spans may have the scope classes added by syntax highlighting.
+fn is_allowed_class(element: &str, class: &str) -> bool {
+    let suffix = match element {
+        "code" => class.strip_prefix("language-"),
+        "span" => class.strip_prefix(code::CLASS_PREFIX),
+        _ => None,
+    };
+    if let Some(suffix) = suffix {
+        return !suffix.is_empty()
+            && suffix
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '_' | '#'));
+    }
+
+    CLASSES
+        .iter()
+        .any(|(tag, classes)| *tag == element && classes.contains(&class))
+}
@@ -0,0 +1,124 @@
+//! Renders code blocks and checks their highlighting, line numbers, highlighted lines, and copy
+//! buttons.
+
+/// Count the lines rendered within a code block.
+fn count_lines(html: &str) -> usize {
+    html.matches("<span class=\"code-line").count()
+}
+
+#[test]
+fn code_is_highlighted_with_scope_classes() {
+    let html = markdown::render("```rust\nfn main() {\n    let answer = \"42\";\n}\n```");
+
+    assert!(html.contains("<code class=\"language-rust\">"));
+    assert!(html.contains("<span class=\"hl-storage hl-type hl-function hl-rust\">fn</span>"));
+    assert!(html.contains("hl-string hl-quoted hl-double hl-rust"));
+    assert!(html.contains("hl-entity hl-name hl-function hl-rust"));
+}
+
+#[test]
+fn every_line_is_wrapped() {
+    let html = markdown::render("```python\na = 1\n\nb = 2\n```");
+
+    assert_eq!(count_lines(&html), 3);
+    assert_eq!(
+        html.matches("<span").count(),
+        html.matches("</span>").count()
+    );
+}
+
+#[test]
+fn scopes_spanning_lines_are_balanced_on_each_line() {
+    let html = markdown::render("```rust\n/* one\ntwo\nthree */\n```");
+
+    for line in html.lines().filter(|line| line.contains("code-line")) {
+        assert_eq!(
+            line.matches("<span").count(),
+            line.matches("</span>").count(),
+            "{line} is not balanced"
+        );
+        assert!(
+            line.contains("hl-comment hl-block hl-rust"),
+            "{line} lost its scope"
+        );
+    }
+}
+
+#[test]
+fn info_string_highlights_lines() {
+    let html = markdown::render("```rust {1, 3-4}\na();\nb();\nc();\nd();\ne();\n```");
+
+    let highlighted: Vec<bool> = html
+        .lines()
+        .filter(|line| line.contains("code-line"))
+        .map(|line| line.contains("highlighted-line"))
+        .collect();
+
+    assert_eq!(highlighted, [true, false, true, true, false]);
+}
+
+#[test]
+fn invalid_line_ranges_are_ignored() {
+    let html = markdown::render("```rust {x, 2-, 2}\na();\nb();\n```");
+
+    assert_eq!(html.matches("highlighted-line").count(), 1);
+    assert!(html.contains("<code class=\"language-rust\">"));
+}
+
+#[test]
+fn huge_line_ranges_only_highlight_the_block() {
+    let html =
+        markdown::render("```rust {2-4000000000, 18446744073709551615}\na();\nb();\nc();\n```");
+
+    let highlighted: Vec<bool> = html
+        .lines()
+        .filter(|line| line.contains("code-line"))
+        .map(|line| line.contains("highlighted-line"))
+        .collect();
+
+    assert_eq!(highlighted, [false, true, true]);
+}
+
+#[test]
+fn rustdoc_attributes_still_pick_the_language() {
+    let html = markdown::render("```rust,ignore\nfn main() {}\n```");
+
+    assert!(html.contains("<code class=\"language-rust\">"));
+    assert!(html.contains("hl-rust"));
+}
+
+#[test]
+fn unknown_languages_are_plain_text() {
+    let html = markdown::render("```brainfuck\n<script>+[-]</script>\n```");
+
+    assert!(html.contains("<code class=\"language-brainfuck\">"));
+    assert!(html.contains("&lt;script&gt;+[-]&lt;/script&gt;"));
+    assert!(!html.contains("hl-"));
+    assert_eq!(count_lines(&html), 1);
+}
+
+#[test]
+fn indented_code_is_plain_text() {
+    let html = markdown::render("Some code:\n\n    let a = 1;\n    let b = 2;\n");
+
+    assert!(html.contains("<pre><code>"));
+    assert!(!html.contains("hl-"));
+    assert_eq!(count_lines(&html), 2);
+}
+
+#[test]
+fn code_blocks_get_a_copy_button() {
+    let html = markdown::render("```\nplain\n```");
+
+    assert!(html.contains(
+        "<div class=\"code-block\"><button aria-label=\"Copy code\" class=\"code-copy\" type=\"button\">Copy</button><pre>"
+    ));
+}
+
+#[test]
+fn code_in_admonitions_is_highlighted() {
+    let html = markdown::render("> [!TIP]\n> ```rust\n> let a = 1;\n> ```");
+
+    assert!(html.contains("<div class=\"admonition admonition-tip\">"));
+    assert!(html.contains("hl-storage hl-type hl-rust"));
+}
@@ -0,0 +1,233 @@
+//! Renders hostile Markdown and checks that nothing executable or spoofed survives, and that the
+//! HTML the pipeline produces itself does.
+
+/// Render Markdown, lowercased so that mixed-case tricks cannot hide from the assertions.
+fn render_lowercase(markdown: &str) -> String {
+    markdown::render(markdown).to_ascii_lowercase()
+}
+
+#[test]
+fn script_elements_are_removed_with_their_contents() {
+    let html = render_lowercase("Hello\n\n<script>alert(1)</script>\n\n<ScRiPt>alert(2)</ScRiPt>");
+
+    assert!(!html.contains("<script"));
+    assert!(!html.contains("alert"));
+}
+
+#[test]
+fn event_handlers_are_removed() {
+    let html = render_lowercase(
+        "<img src=\"https://example.com/a.png\" onerror=\"alert(1)\">\n\n\
+         <p onclick=\"alert(2)\" onmouseover=\"alert(3)\">text</p>",
+    );
+
+    assert!(!html.contains("onerror"));
+    assert!(!html.contains("onclick"));
+    assert!(!html.contains("onmouseover"));
+    assert!(html.contains("<p>text</p>"));
+}
+
+#[test]
+fn heading_attributes_cannot_add_event_handlers() {
+    let html = render_lowercase("# Title {#title onclick=alert(1) style=color:red}");
+
+    assert!(!html.contains("onclick"));
+    assert!(!html.contains("color"));
+    assert!(html.contains("id=\"user-content-title\""));
+}
+
+#[test]
+fn javascript_links_are_removed() {
+    for markdown in [
+        "[click](javascript:alert(1))",
+        "[click](JaVaScRiPt:alert(1))",
+        "[click](<javascript&#58;alert(1)>)",
+        "<a href=\"jav&#x09;ascript:alert(1)\">click</a>",
+        "<a href=\" javascript:alert(1)\">click</a>",
+        "<javascript:alert(1)>",
+    ] {
+        let html = render_lowercase(markdown);
+
+        assert!(!html.contains("href"), "{markdown} rendered as {html}");
+    }
+}
+
+#[test]
+fn data_and_vbscript_urls_are_removed() {
+    for markdown in [
+        "![image](data:text/html;base64,PHNjcmlwdD5hbGVydCgxKTwvc2NyaXB0Pg==)",
+        "[link](data:text/html,<script>alert(1)</script>)",
+        "<img src=\"data:image/svg+xml,<svg onload=alert(1)>\">",
+        "[link](vbscript:msgbox(1))",
+    ] {
+        let html = render_lowercase(markdown);
+
+        assert!(!html.contains("data:"), "{markdown} rendered as {html}");
+        assert!(!html.contains("vbscript"), "{markdown} rendered as {html}");
+    }
+}
+
+#[test]
+fn embedding_elements_are_removed() {
+    let html = render_lowercase(
+        "<iframe src=\"https://example.com\"></iframe>\n\n\
+         <object data=\"https://example.com/a.swf\"></object>\n\n\
+         <embed src=\"https://example.com/a.swf\">\n\n\
+         <svg><script>alert(1)</script></svg>\n\n\
+         <math><mtext><script>alert(2)</script></mtext></math>\n\n\
+         <form action=\"https://example.com\"><input type=\"submit\" formaction=\"https://example.com\"></form>\n\n\
+         <meta http-equiv=\"refresh\" content=\"0; url=https://example.com\">\n\n\
+         <base href=\"https://example.com\">\n\n\
+         <link rel=\"stylesheet\" href=\"https://example.com/a.css\">",
+    );
+
+    for element in [
+        "<iframe", "<object", "<embed", "<svg", "<math", "<form", "<meta", "<base", "<link",
+    ] {
+        assert!(!html.contains(element), "{element} survived in {html}");
+    }
+    assert!(!html.contains("alert"));
+    assert!(!html.contains("submit"));
+    assert!(!html.contains("formaction"));
+}
+
+#[test]
+fn style_elements_and_attributes_are_removed() {
+    let html = render_lowercase(
+        "<style>body { display: none; }</style>\n\n\
+         <div style=\"position: fixed; inset: 0\">overlay</div>",
+    );
+
+    assert!(!html.contains("<style"));
+    assert!(!html.contains("display"));
+    assert!(!html.contains("position"));
+    assert!(html.contains("overlay"));
+}
+
+#[test]
+fn comments_are_removed() {
+    let html = render_lowercase("<!-- <script>alert(1)</script> -->\n\ntext");
+
+    assert!(!html.contains("<!--"));
+    assert!(!html.contains("alert"));
+}
+
+#[test]
+fn template_literal_syntax_is_left_as_text() {
+    let html =
+        markdown::render("Costs `${price}` and uses \\` backticks: ``a ` b``\n\n${alert(1)}");
+
+    assert!(html.contains("<code>${price}</code>"));
+    assert!(html.contains("<code>a ` b</code>"));
+    assert!(html.contains("${alert(1)}"));
+}
+
+#[test]
+fn unbalanced_html_cannot_escape_the_post() {
+    let html = render_lowercase("</div></div></main>\n\n<div><div>unclosed");
+
+    assert!(!html.contains("</main>"));
+    assert_eq!(html.matches("<div").count(), html.matches("</div>").count());
+}
+
+#[test]
+fn spoofed_classes_are_removed() {
+    let html = markdown::render(
+        "<div class=\"fixed-top modal-backdrop admonition\">text</div>\n\n\
+         <span class=\"visually-hidden\">hidden</span>\n\n\
+         <code class=\"language-rust language-a(b) hljs\">code</code>",
+    );
+
+    assert!(!html.contains("fixed-top"));
+    assert!(!html.contains("modal-backdrop"));
+    assert!(!html.contains("visually-hidden"));
+    assert!(html.contains("<div class=\"admonition\">text</div>"));
+    assert!(html.contains("<code class=\"language-rust\">code</code>"));
+}
+
+#[test]
+fn ids_are_prefixed_to_prevent_clobbering() {
+    let html = markdown::render(
+        "<div id=\"post-content\">a</div>\n\n<img name=\"getElementById\" id=\"location\" src=\"https://example.com/a.png\">",
+    );
+
+    assert!(html.contains("id=\"user-content-post-content\""));
+    assert!(html.contains("id=\"user-content-location\""));
+    assert!(!html.contains("name="));
+}
+
+#[test]
+fn only_checkbox_inputs_keep_their_type() {
+    let html = render_lowercase("<input type=\"password\"> <input type=\"checkbox\" checked>");
+
+    assert!(!html.contains("password"));
+    assert!(html.contains("<input disabled=\"\">"));
+    assert!(html.contains("<input type=\"checkbox\" checked=\"\" disabled=\"\">"));
+}
+
+#[test]
+fn links_cannot_reach_back_into_the_page() {
+    let html = render_lowercase("<a href=\"https://example.com\" target=\"_blank\">link</a>");
+
+    assert!(!html.contains("target"));
+    assert!(html.contains("rel=\"noopener noreferrer\""));
+}
+
+#[test]
+fn headings_keep_their_anchors() {
+    let html = markdown::render("# Getting Started");
+
+    assert!(html.contains("<h1 id=\"user-content-getting-started\">"));
+    assert!(html.contains("class=\"heading-anchor\""));
+    assert!(html.contains("href=\"#user-content-getting-started\""));
+}
+
+#[test]
+fn footnotes_keep_their_links() {
+    let html = markdown::render("A claim.[^source]\n\n[^source]: The source.");
+
+    assert!(html.contains("<sup class=\"footnote-reference\"><a href=\"#user-content-source\""));
+    assert!(html.contains("<div class=\"footnote-definition\" id=\"user-content-source\">"));
+}
+
+#[test]
+fn task_lists_tables_math_and_code_are_kept() {
+    let html = markdown::render(
+        "- [x] done\n- [ ] not done\n\n\
+         | Left | Center | Right |\n| :--- | :----: | ----: |\n| a | b | c |\n\n\
+         $e^{i\\pi} + 1 = 0$\n\n$$\\int_0^1 x\\,dx$$\n\n\
+         ```c++\nint main() {}\n```",
+    );
+
+    assert!(html.contains("<input disabled=\"\" type=\"checkbox\" checked=\"\">"));
+    assert!(html.contains("<input disabled=\"\" type=\"checkbox\">"));
+    assert!(html.contains("<th style=\"text-align: left\">Left</th>"));
+    assert!(html.contains("<th style=\"text-align: center\">Center</th>"));
+    assert!(html.contains("<td style=\"text-align: right\">c</td>"));
+    assert!(html.contains("<span class=\"math math-inline\">"));
+    assert!(html.contains("<span class=\"math math-display\">"));
+    assert!(html.contains("<code class=\"language-c++\">"));
+}
+
+#[test]
+fn admonitions_and_figures_are_kept() {
+    let html = markdown::render(
+        "> [!WARNING]\n> Careful.\n\n![A cat](https://example.com/cat.png \"My cat\")",
+    );
+
+    assert!(html.contains("<div class=\"admonition admonition-warning\">"));
+    assert!(html.contains("<p class=\"admonition-title\">"));
+    assert!(html.contains("<figure class=\"post-figure\">"));
+    assert!(html.contains("class=\"post-image\""));
+    assert!(html.contains("src=\"https://example.com/cat.png\""));
+    assert!(html.contains("<figcaption>My cat</figcaption>"));
+}
+
+#[test]
+fn buttons_cannot_submit_forms() {
+    let html = render_lowercase(
+        "<button type=\"submit\" form=\"comment-form\" formaction=\"https://example.com\">go</button>",
+    );
+
+    assert!(html.contains("<button type=\"button\">go</button>"));
+}